@@ -0,0 +1,1410 @@
+//! ABC notation of tunes, reading into a staff and writing it back.
+use std::{collections::HashMap, fmt::Display};
+
+use crate::{
+    beat::{
+        ChordSymbol, Hairpin, HairpinKind, Measure, NoteAttribute, NotePosition, Staff,
+        TimeSignature,
+    },
+    chord::Chord,
+    note::{Articulation, Attribute, Duration, DurationName, Dynamic, Note, PPQ, Tuplet},
+    pitch::{Accidental, Pitch, PitchClass},
+    scale::{Scale, ScaleDegree, ScaleType},
+    scales::modes,
+};
+
+/// Longest note or rest which is read, 64 whole notes, measure rests like
+/// Z4 included.
+const MAX_TICKS: u32 = PPQ * 4 * 64;
+
+/// Mode names of the K: field (only the first three letters count) and the
+/// modes of the crate.
+const MODES: [(&str, &str, [ScaleDegree; 7]); 9] = [
+    ("maj", "ionian", modes::IONIAN),
+    ("ion", "ionian", modes::IONIAN),
+    ("min", "aeolian", modes::AEOLIAN),
+    ("aeo", "aeolian", modes::AEOLIAN),
+    ("dor", "dorian", modes::DORIAN),
+    ("phr", "phrygian", modes::PHRYGIAN),
+    ("lyd", "lydian", modes::LYDIAN),
+    ("mix", "mixolydian", modes::MIXOLYDIAN),
+    ("loc", "locrian", modes::LOCRIAN),
+];
+
+/// Decorations written with a single character, like ~ for a roll.
+const DECORATIONS: &str = ".~HLMOPSTuv";
+
+/// Articulations as they are written and the other names and characters
+/// which are read as them.
+const ARTICULATIONS: [(Articulation, &str, &[&str]); 6] = [
+    (Articulation::Staccato, ".", &["staccato"]),
+    (Articulation::Staccatissimo, "!wedge!", &[]),
+    (Articulation::Tenuto, "!tenuto!", &[]),
+    (Articulation::Accent, "!accent!", &["L", ">", "emphasis"]),
+    (Articulation::Marcato, "!marcato!", &["^"]),
+    (Articulation::Fermata, "!fermata!", &["H"]),
+];
+
+/// Header fields which can also change in the body of the tune.
+const BODY_FIELDS: &str = "KLMQTPVWwNR";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tempo {
+    pub beat: Duration,
+    pub per_minute: u32,
+}
+
+#[derive(Clone, Debug)]
+pub struct AbcTune {
+    /// Reference number from the X: field.
+    pub reference: u32,
+    pub title: Option<String>,
+    pub key: Scale,
+    /// Unit note length from the L: field.
+    pub unit: Duration,
+    pub tempo: Option<Tempo>,
+    pub staff: Staff,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum AbcParseError {
+    /// The tune has no K: field.
+    MissingKey,
+    InvalidField(String),
+    InvalidKey(String),
+    InvalidNote(String),
+    /// Length which cannot be written with (dotted) note values.
+    InvalidLength(String),
+    InvalidChord(String),
+    /// Tuplet like (3 or (3:2:3 whose numbers are zero or too large.
+    InvalidTuplet(String),
+    /// Annotation, decoration, grace notes or inline field without the
+    /// closing character.
+    Unclosed(char),
+    Unsupported(String),
+}
+
+impl AbcTune {
+    /// Tune of the staff in the key with eighth notes as unit length.
+    pub fn new(staff: Staff, key: Scale) -> Self {
+        AbcTune {
+            reference: 1,
+            title: None,
+            key,
+            unit: Duration::new(DurationName::Eigth),
+            tempo: None,
+            staff,
+        }
+    }
+
+    /// Read the first tune of the text. Repeats and endings are read as
+    /// plain bar lines, grace notes and the decorations which are not
+    /// articulations, dynamics or hairpins are skipped.
+    pub fn parse(text: &str) -> Result<Self, AbcParseError> {
+        let mut reader = Reader::default();
+
+        for line in text.lines() {
+            let line = line.split('%').next().unwrap_or_default().trim();
+
+            if line.is_empty() {
+                // An empty line ends the tune
+                if reader.key.is_some() {
+                    break;
+                }
+
+                continue;
+            }
+
+            if let Some((field, value)) = split_field(line)
+                && (reader.key.is_none() || BODY_FIELDS.contains(field))
+            {
+                reader.field(field, value)?;
+                continue;
+            }
+
+            if reader.key.is_some() {
+                reader.body(line)?;
+            }
+        }
+
+        reader.bar()?;
+
+        Ok(AbcTune {
+            reference: reader.reference,
+            title: reader.title,
+            key: reader.key.ok_or(AbcParseError::MissingKey)?,
+            unit: reader.unit.unwrap_or(Duration::new(DurationName::Eigth)),
+            tempo: reader.tempo,
+            staff: Staff {
+                measures: reader.measures,
+                hairpins: reader.hairpins,
+            },
+        })
+    }
+
+    /// The tune in ABC notation, with four measures in a line.
+    pub fn to_abc(&self) -> String {
+        let time_signature = self
+            .staff
+            .measures
+            .first()
+            .map_or(TimeSignature::COMMON_TIME, |m| m.time_signature);
+        let mut abc = format!("X:{}\n", self.reference);
+
+        if let Some(title) = &self.title {
+            abc.push_str(&format!("T:{title}\n"));
+        }
+
+        abc.push_str(&format!("M:{}\n", meter(&time_signature)));
+        abc.push_str(&format!("L:1/{}\n", self.unit.name as u8));
+
+        if let Some(tempo) = self.tempo {
+            abc.push_str(&format!(
+                "Q:{}={}\n",
+                fraction(tempo.beat.to_ticks(PPQ), PPQ * 4),
+                tempo.per_minute
+            ));
+        }
+
+        abc.push_str(&format!("K:{}\n", key_name(&self.key)));
+
+        let unit = self.unit.to_ticks(PPQ);
+        let mut previous = time_signature;
+
+        for (i, measure) in self.staff.measures.iter().enumerate() {
+            let mut accidentals = HashMap::new();
+            let mut tokens = vec![];
+
+            if measure.time_signature != previous {
+                tokens.push(format!("[M:{}]", meter(&measure.time_signature)));
+                previous = measure.time_signature;
+            }
+
+            for (j, note) in measure.notes.iter().enumerate() {
+                let mut token = String::new();
+                let tuplet = note.duration().tuplet;
+
+                // Notes of the same tuplet next to each other are one group
+                if let Some(tuplet) = tuplet
+                    && (j == 0 || measure.notes[j - 1].duration().tuplet != Some(tuplet))
+                {
+                    let count = measure.notes[j..]
+                        .iter()
+                        .take_while(|n| n.duration().tuplet == Some(tuplet))
+                        .count();
+
+                    token.push_str(&tuplet_mark(tuplet, count, &measure.time_signature));
+                }
+
+                if measure.has_attribute(j, Attribute::SlurStart) {
+                    token.push('(');
+                }
+
+                token.push_str(&self.hairpin_marks(NotePosition {
+                    measure: i,
+                    note: j,
+                }));
+
+                for symbol in measure.chord_symbols.iter().filter(|s| s.note_index == j) {
+                    token.push_str(&format!("\"{}\"", symbol.chord));
+                }
+
+                for attribute in measure.note_attributes(j) {
+                    match attribute {
+                        Attribute::Dynamic(dynamic) => {
+                            token.push_str(&format!("!{}!", dynamic.name()));
+                        }
+                        Attribute::Articulation(articulation) => {
+                            token.push_str(articulation_mark(*articulation));
+                        }
+                        _ => {}
+                    }
+                }
+
+                match note {
+                    Note::Tone(pitch, _) => {
+                        let expected = accidentals
+                            .get(&(pitch.class.index(), pitch.octave))
+                            .copied()
+                            .unwrap_or_else(|| key_accidental(&self.key, pitch.class));
+
+                        if pitch.accidental != expected {
+                            token.push_str(accidental_mark(pitch.accidental));
+                            accidentals
+                                .insert((pitch.class.index(), pitch.octave), pitch.accidental);
+                        }
+
+                        token.push_str(&note_letter(pitch));
+                    }
+                    Note::Rest(_) => token.push('z'),
+                }
+
+                // Lengths in a tuplet are written without it
+                let written = Duration {
+                    tuplet: None,
+                    ..*note.duration()
+                };
+
+                token.push_str(&length_suffix(written.to_ticks(PPQ), unit));
+
+                if measure.has_attribute(j, Attribute::Tie) {
+                    token.push('-');
+                }
+
+                if measure.has_attribute(j, Attribute::SlurStop) {
+                    token.push(')');
+                }
+
+                tokens.push(token);
+            }
+
+            abc.push_str(&tokens.join(" "));
+
+            if i + 1 == self.staff.measures.len() {
+                abc.push_str(" |]\n");
+            } else if i % 4 == 3 {
+                abc.push_str(" |\n");
+            } else {
+                abc.push_str(" | ");
+            }
+        }
+
+        abc
+    }
+
+    /// Decorations of the hairpins which start or end at the note. A
+    /// hairpin ending at the note is written before it like its start.
+    fn hairpin_marks(&self, position: NotePosition) -> String {
+        let mut marks: Vec<(u8, &str)> = vec![];
+
+        for hairpin in &self.staff.hairpins {
+            let (start, end) = match hairpin.kind {
+                HairpinKind::Crescendo => ("!<(!", "!<)!"),
+                HairpinKind::Diminuendo => ("!>(!", "!>)!"),
+            };
+
+            if hairpin.start == position {
+                marks.push((1, start));
+            }
+
+            // The previous hairpin ends before the next one starts
+            if hairpin.end == position {
+                marks.push((if hairpin.start == position { 2 } else { 0 }, end));
+            }
+        }
+
+        marks.sort_by_key(|(order, _)| *order);
+        marks.into_iter().map(|(_, mark)| mark).collect()
+    }
+}
+
+/// Field letter and value of lines like `K:G`.
+fn split_field(line: &str) -> Option<(char, &str)> {
+    let mut chars = line.chars();
+    let letter = chars.next().filter(char::is_ascii_alphabetic)?;
+
+    (chars.next() == Some(':')).then(|| (letter, line[2..].trim()))
+}
+
+fn key_accidental(key: &Scale, class: PitchClass) -> Accidental {
+    key.pitches
+        .iter()
+        .find(|p| p.class == class)
+        .map_or(Accidental::Natural, |p| p.accidental)
+}
+
+fn articulation_mark(articulation: Articulation) -> &'static str {
+    ARTICULATIONS
+        .iter()
+        .find(|(a, _, _)| *a == articulation)
+        .map_or("", |(_, mark, _)| mark)
+}
+
+/// Articulation of a decoration, without the ! around it.
+fn parse_articulation(name: &str) -> Option<Articulation> {
+    ARTICULATIONS
+        .iter()
+        .find(|(_, mark, aliases)| mark.trim_matches('!') == name || aliases.contains(&name))
+        .map(|(articulation, _, _)| *articulation)
+}
+
+fn accidental_mark(accidental: Accidental) -> &'static str {
+    match accidental {
+        Accidental::DoubleFlat => "__",
+        Accidental::Flat => "_",
+        Accidental::Natural => "=",
+        Accidental::Sharp => "^",
+        Accidental::DoubleSharp => "^^",
+    }
+}
+
+/// Letter with octave marks, C is the middle C and c is an octave higher.
+fn note_letter(pitch: &Pitch) -> String {
+    let letter = pitch.class.to_string();
+
+    if pitch.octave >= 5 {
+        format!(
+            "{}{}",
+            letter.to_lowercase(),
+            "'".repeat((pitch.octave - 5) as usize)
+        )
+    } else {
+        format!("{letter}{}", ",".repeat((4 - pitch.octave).max(0) as usize))
+    }
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn fraction(numerator: u32, denominator: u32) -> String {
+    let divisor = gcd(numerator, denominator).max(1);
+
+    format!("{}/{}", numerator / divisor, denominator / divisor)
+}
+
+/// Multiplier of the unit length, like 2, 3/2 or / for the half.
+fn length_suffix(ticks: u32, unit: u32) -> String {
+    let divisor = gcd(ticks, unit).max(1);
+
+    match (ticks / divisor, unit / divisor) {
+        (1, 1) => String::new(),
+        (n, 1) => n.to_string(),
+        (1, 2) => "/".to_string(),
+        (1, d) => format!("/{d}"),
+        (n, d) => format!("{n}/{d}"),
+    }
+}
+
+/// Notes in whose time the `actual` notes of a tuplet are played when the
+/// tuplet doesn't say, so (3 is 3 in the time of 2.
+fn tuplet_normal(actual: u8, meter: &TimeSignature) -> u8 {
+    match actual {
+        2 | 4 | 8 => 3,
+        3 | 6 => 2,
+        // Compound meters like 6/8
+        _ if meter.numerator > 3 && meter.numerator.is_multiple_of(3) => 3,
+        _ => 2,
+    }
+}
+
+/// Tuplet like (3 or (3:2:3 for `count` notes, in the shortest form.
+fn tuplet_mark(tuplet: Tuplet, count: usize, meter: &TimeSignature) -> String {
+    let Tuplet { actual, normal } = tuplet;
+
+    match (
+        normal == tuplet_normal(actual, meter),
+        count == actual as usize,
+    ) {
+        (true, true) => format!("({actual}"),
+        (false, true) => format!("({actual}:{normal}"),
+        _ => format!("({actual}:{normal}:{count}"),
+    }
+}
+
+/// Tuplet like (3, (3:2 or (3:2:3 starting at `start`, with the number of
+/// notes in it and the position after it.
+fn parse_tuplet(
+    chars: &[char],
+    start: usize,
+    meter: &TimeSignature,
+) -> Result<(Tuplet, u32, usize), AbcParseError> {
+    let mut i = start + 1;
+    let mut parts = vec![];
+
+    loop {
+        let digits: String = chars[i..]
+            .iter()
+            .take_while(|c| c.is_ascii_digit())
+            .collect();
+
+        i += digits.len();
+        parts.push(digits);
+
+        if parts.len() < 3 && chars.get(i) == Some(&':') {
+            i += 1;
+        } else {
+            break;
+        }
+    }
+
+    let invalid = || AbcParseError::InvalidTuplet(chars[start..i].iter().collect());
+    let numbers = parts
+        .iter()
+        .map(|digits| match digits.as_str() {
+            "" => Ok(None),
+            _ => digits
+                .parse::<u8>()
+                .ok()
+                .filter(|n| *n > 0)
+                .map(Some)
+                .ok_or_else(invalid),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let actual = numbers[0].ok_or_else(invalid)?;
+    let normal = numbers
+        .get(1)
+        .copied()
+        .flatten()
+        .unwrap_or_else(|| tuplet_normal(actual, meter));
+    let count = numbers.get(2).copied().flatten().unwrap_or(actual);
+
+    Ok((Tuplet::new(actual, normal), count as u32, i))
+}
+
+fn meter(time_signature: &TimeSignature) -> String {
+    format!(
+        "{}/{}",
+        time_signature.numerator, time_signature.denominator
+    )
+}
+
+fn key_name(key: &Scale) -> String {
+    let root = format!("{}{}", key.root_note.class, key.root_note.accidental);
+
+    match key.scale_type.name.as_str() {
+        "ionian" => root,
+        mode => format!("{root} {mode}"),
+    }
+}
+
+/// Key like `D dorian`, `Bbm`, `F#mix` or `none` (C major).
+fn parse_key(value: &str) -> Result<Scale, AbcParseError> {
+    let invalid = || AbcParseError::InvalidKey(value.to_string());
+    let value = value
+        .split_whitespace()
+        .filter(|t| !t.contains('='))
+        .collect::<Vec<_>>();
+    let first = value.first().copied().unwrap_or("none");
+
+    if first.eq_ignore_ascii_case("none") {
+        return parse_key("C");
+    }
+
+    if value.iter().any(|t| t.eq_ignore_ascii_case("exp")) || first.starts_with('H') {
+        return Err(AbcParseError::Unsupported(format!("K:{}", value.join(" "))));
+    }
+
+    let mut chars = first.chars();
+    let class = chars
+        .next()
+        .and_then(PitchClass::parse)
+        .ok_or_else(invalid)?;
+    let mut rest = chars.as_str();
+    let accidental = match rest.chars().next() {
+        Some('#') => Accidental::Sharp,
+        Some('b') => Accidental::Flat,
+        _ => Accidental::Natural,
+    };
+
+    if accidental != Accidental::Natural {
+        rest = &rest[1..];
+    }
+
+    let mode = if rest.is_empty() {
+        value.get(1).copied().unwrap_or_default()
+    } else {
+        rest
+    }
+    .to_lowercase();
+    let (_, name, degrees) = if mode.is_empty() {
+        &MODES[0]
+    } else if mode == "m" {
+        &MODES[2]
+    } else {
+        MODES
+            .iter()
+            .find(|(abbreviation, _, _)| mode.starts_with(abbreviation))
+            .ok_or_else(invalid)?
+    };
+
+    let root = Pitch {
+        class,
+        accidental,
+        octave: 4,
+    };
+
+    Scale::checked_new(
+        &root,
+        &ScaleType {
+            name: name.to_string(),
+            scale_degrees: degrees.to_vec(),
+        },
+    )
+    .ok_or_else(invalid)
+}
+
+fn parse_meter(value: &str) -> Option<TimeSignature> {
+    match value {
+        "C" | "none" => Some(TimeSignature::COMMON_TIME),
+        "C|" => Some(TimeSignature::new(2, 2)),
+        _ => {
+            let (numerator, denominator) = value.split_once('/')?;
+            let numerator = numerator
+                .split('+')
+                .try_fold(0u8, |sum, n| sum.checked_add(n.trim().parse().ok()?))
+                .filter(|n| *n > 0)?;
+
+            Some(TimeSignature::new(
+                numerator,
+                denominator.trim().parse().ok()?,
+            ))
+        }
+    }
+}
+
+/// Note value of a fraction like 1/8 or 3/8, which is a dotted quarter.
+fn parse_note_value(value: &str) -> Option<Duration> {
+    let (numerator, denominator) = value.split_once('/')?;
+    let numerator: u32 = numerator.trim().parse().ok()?;
+    let denominator: u32 = denominator.trim().parse().ok()?;
+    let ticks = (PPQ * 4).checked_mul(numerator)? / denominator.max(1);
+
+    match Duration::fill(ticks, PPQ).as_slice() {
+        [duration] if duration.to_ticks(PPQ) == ticks => Some(*duration),
+        _ => None,
+    }
+}
+
+/// Decoration or slur before a note, or a tie or the end of a slur after
+/// it.
+#[derive(Clone, Copy)]
+enum Mark {
+    Attribute(Attribute),
+    HairpinStart(HairpinKind),
+    HairpinEnd,
+}
+
+/// Note which is not in the measure yet.
+struct Pending {
+    /// No pitch for a rest.
+    pitch: Option<Pitch>,
+    /// Length as it is written, without the tuplet.
+    ticks: u32,
+    tuplet: Option<Tuplet>,
+    token: String,
+    marks: Vec<Mark>,
+}
+
+/// State while reading a tune.
+#[derive(Default)]
+struct Reader {
+    reference: u32,
+    title: Option<String>,
+    meter: Option<TimeSignature>,
+    unit: Option<Duration>,
+    tempo: Option<Tempo>,
+    key: Option<Scale>,
+    measures: Vec<Measure>,
+    notes: Vec<Note>,
+    chord_symbols: Vec<ChordSymbol>,
+    attributes: Vec<NoteAttribute>,
+    /// Marks read before the next note.
+    marks: Vec<Mark>,
+    /// Hairpin which started at a note and has not ended yet.
+    open_hairpin: Option<(HairpinKind, NotePosition)>,
+    hairpins: Vec<Hairpin>,
+    /// Accidentals of the measure which are valid until the bar line.
+    accidentals: HashMap<(u8, i8), Accidental>,
+    /// The last note, which can still change by a broken rhythm.
+    pending: Option<Pending>,
+    /// Multiplier of the next note after a broken rhythm, in fourths.
+    next_multiplier: Option<u32>,
+    /// Tuplet of the next notes and how many of them are left.
+    tuplet: Option<(Tuplet, u32)>,
+}
+
+impl Reader {
+    fn field(&mut self, field: char, value: &str) -> Result<(), AbcParseError> {
+        let invalid = || AbcParseError::InvalidField(format!("{field}:{value}"));
+
+        match field {
+            'X' => self.reference = value.parse().map_err(|_| invalid())?,
+            'T' => {
+                self.title.get_or_insert(value.to_string());
+            }
+            'M' => {
+                let meter = parse_meter(value).ok_or_else(invalid)?;
+
+                self.meter = Some(meter);
+            }
+            'L' => {
+                self.unit = Some(
+                    parse_note_value(value)
+                        .filter(|unit| unit.dots == 0)
+                        .ok_or_else(invalid)?,
+                );
+            }
+            'Q' => {
+                let (beat, per_minute) = value
+                    .rsplit_once('=')
+                    .map_or(("1/4", value), |(beat, bpm)| (beat, bpm));
+                // The beat may have a text before it, like "Allegro" 1/4
+                let beat = beat.rsplit('"').next().unwrap_or_default().trim();
+
+                self.tempo = Some(Tempo {
+                    beat: parse_note_value(beat).ok_or_else(invalid)?,
+                    per_minute: per_minute.trim().parse().map_err(|_| invalid())?,
+                });
+            }
+            'K' => {
+                self.key = Some(parse_key(value)?);
+
+                let meter = self.meter.get_or_insert(TimeSignature::COMMON_TIME);
+
+                // Short meters like 2/4 have 16th as default unit length
+                if self.unit.is_none() {
+                    let name = if (meter.numerator as u32) * 4 < 3 * meter.denominator as u32 {
+                        DurationName::Sixteenth
+                    } else {
+                        DurationName::Eigth
+                    };
+
+                    self.unit = Some(Duration::new(name));
+                }
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn body(&mut self, line: &str) -> Result<(), AbcParseError> {
+        let chars: Vec<char> = line.chars().collect();
+        let mut i = 0;
+
+        // Position after the closing character
+        let skip_to = |i: usize, end: char| {
+            chars[i + 1..]
+                .iter()
+                .position(|c| *c == end)
+                .map(|p| i + p + 2)
+                .ok_or(AbcParseError::Unclosed(chars[i]))
+        };
+
+        while i < chars.len() {
+            let c = chars[i];
+
+            match c {
+                '"' => {
+                    let end = skip_to(i, '"')?;
+                    let text: String = chars[i + 1..end - 1].iter().collect();
+
+                    // Annotations start with a position mark
+                    if !text.starts_with(['^', '_', '<', '>', '@']) {
+                        self.flush()?;
+
+                        let chord =
+                            Chord::parse(&text).map_err(|_| AbcParseError::InvalidChord(text))?;
+
+                        self.chord_symbols.push(ChordSymbol {
+                            note_index: self.notes.len(),
+                            chord,
+                        });
+                    }
+
+                    i = end;
+                }
+                '!' | '+' => {
+                    let end = skip_to(i, c)?;
+                    let name: String = chars[i + 1..end - 1].iter().collect();
+
+                    self.decoration(&name);
+                    i = end;
+                }
+                '{' => i = skip_to(i, '}')?,
+                '(' if chars.get(i + 1).is_some_and(char::is_ascii_digit) => {
+                    let meter = self.meter.unwrap_or(TimeSignature::COMMON_TIME);
+                    let (tuplet, count, end) = parse_tuplet(&chars, i, &meter)?;
+
+                    self.tuplet = Some((tuplet, count));
+                    i = end;
+                }
+                '(' => {
+                    self.marks.push(Mark::Attribute(Attribute::SlurStart));
+                    i += 1;
+                }
+                ')' => {
+                    self.after_note(Attribute::SlurStop);
+                    i += 1;
+                }
+                '-' => {
+                    self.after_note(Attribute::Tie);
+                    i += 1;
+                }
+                '[' if chars.get(i + 1).is_some_and(char::is_ascii_alphabetic)
+                    && chars.get(i + 2) == Some(&':') =>
+                {
+                    let end = skip_to(i, ']')?;
+                    let inline: String = chars[i + 1..end - 1].iter().collect();
+
+                    if let Some((field, value)) = split_field(&inline) {
+                        self.flush()?;
+                        self.field(field, value)?;
+                    }
+
+                    i = end;
+                }
+                '[' if chars.get(i + 1).is_some_and(char::is_ascii_digit) => {
+                    // First and second endings
+                    i += 1;
+
+                    while chars.get(i).is_some_and(char::is_ascii_digit) {
+                        i += 1;
+                    }
+                }
+                '[' if chars.get(i + 1) != Some(&'|') => {
+                    return Err(AbcParseError::Unsupported("chord".to_string()));
+                }
+                '|' | ':' | '[' | ']' => {
+                    while chars.get(i).is_some_and(|c| "|:[]".contains(*c)) {
+                        i += 1;
+                    }
+
+                    while chars
+                        .get(i)
+                        .is_some_and(|c| c.is_ascii_digit() || *c == ',')
+                    {
+                        i += 1;
+                    }
+
+                    self.bar()?;
+                }
+                '>' | '<' => {
+                    let count = chars[i..].iter().take_while(|d| **d == c).count() as u32;
+                    let mut pending = self
+                        .pending
+                        .take()
+                        .ok_or_else(|| AbcParseError::InvalidNote(c.to_string()))?;
+                    // a>b is 3/2 and 1/2 of the length, a>>b is 7/4 and 1/4
+                    let short = 4 >> count.min(2);
+                    let long = 8 - short;
+                    let (previous, next) = if c == '>' {
+                        (long, short)
+                    } else {
+                        (short, long)
+                    };
+
+                    pending.ticks = pending.ticks * previous / 4;
+                    self.pending = Some(pending);
+                    self.next_multiplier = Some(next);
+                    i += count as usize;
+                }
+                '^' | '_' | '=' | 'A'..='G' | 'a'..='g' | 'z' | 'x' | 'Z' => {
+                    i = self.note(&chars, i)?;
+                }
+                _ if DECORATIONS.contains(c) => {
+                    self.decoration(&c.to_string());
+                    i += 1;
+                }
+                _ if c.is_whitespace() || "`\\".contains(c) => {
+                    i += 1;
+                }
+                _ => return Err(AbcParseError::InvalidNote(c.to_string())),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Read a note or rest from `start`, returns the position after it.
+    fn note(&mut self, chars: &[char], start: usize) -> Result<usize, AbcParseError> {
+        let mut i = start;
+        let mut accidental = None;
+
+        while let Some(c) = chars.get(i).filter(|c| "^_=".contains(**c)) {
+            accidental = Some(match (accidental, c) {
+                (None, '^') => Accidental::Sharp,
+                (Some(Accidental::Sharp), '^') => Accidental::DoubleSharp,
+                (None, '_') => Accidental::Flat,
+                (Some(Accidental::Flat), '_') => Accidental::DoubleFlat,
+                (None, '=') => Accidental::Natural,
+                _ => {
+                    return Err(AbcParseError::InvalidNote(
+                        chars[start..=i].iter().collect(),
+                    ));
+                }
+            });
+            i += 1;
+        }
+
+        let letter = *chars
+            .get(i)
+            .ok_or_else(|| AbcParseError::InvalidNote(chars[start..].iter().collect()))?;
+        let mut octave: i8 = if letter.is_ascii_lowercase() { 5 } else { 4 };
+
+        i += 1;
+
+        while let Some(mark) = chars.get(i).filter(|c| **c == '\'' || **c == ',') {
+            octave = octave
+                .checked_add(if *mark == '\'' { 1 } else { -1 })
+                .ok_or_else(|| AbcParseError::InvalidNote(chars[start..=i].iter().collect()))?;
+            i += 1;
+        }
+
+        let number = |i: &mut usize| {
+            let digits: String = chars[*i..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit())
+                .collect();
+
+            *i += digits.len();
+            digits.parse::<u32>().ok()
+        };
+        let numerator = number(&mut i).unwrap_or(1);
+        let mut denominator = Some(1u32);
+
+        while chars.get(i) == Some(&'/') {
+            i += 1;
+            denominator = denominator.and_then(|d| d.checked_mul(number(&mut i).unwrap_or(2)));
+        }
+
+        let token: String = chars[start..i].iter().collect();
+        let unit = self
+            .unit
+            .unwrap_or(Duration::new(DurationName::Eigth))
+            .to_ticks(PPQ);
+        let length = unit.checked_mul(numerator);
+        let ticks = match (length, denominator) {
+            (Some(length), Some(denominator))
+                if denominator > 0 && length.is_multiple_of(denominator) =>
+            {
+                length / denominator
+            }
+            _ => return Err(AbcParseError::InvalidLength(token)),
+        };
+
+        if ticks > MAX_TICKS {
+            return Err(AbcParseError::InvalidLength(token));
+        }
+
+        self.flush()?;
+
+        let marks = std::mem::take(&mut self.marks);
+
+        match letter {
+            'z' | 'x' => {
+                self.pending = Some(Pending {
+                    pitch: None,
+                    ticks,
+                    tuplet: self.next_tuplet(),
+                    token,
+                    marks,
+                })
+            }
+            'Z' => {
+                // Whole measure rests, Z4 is four measures
+                let length = self
+                    .meter
+                    .unwrap_or(TimeSignature::COMMON_TIME)
+                    .to_ticks(PPQ);
+
+                if length.checked_mul(numerator).is_none_or(|l| l > MAX_TICKS) {
+                    return Err(AbcParseError::InvalidLength(token));
+                }
+
+                for n in 0..numerator {
+                    if n > 0 {
+                        self.bar()?;
+                    }
+
+                    let marks = if n == 0 { marks.clone() } else { vec![] };
+
+                    self.push(None, length, None, &token, marks)?;
+                }
+            }
+            _ => {
+                let class = PitchClass::parse(letter.to_ascii_uppercase())
+                    .ok_or_else(|| AbcParseError::InvalidNote(token.clone()))?;
+                let key = (class.index(), octave);
+
+                if let Some(accidental) = accidental {
+                    self.accidentals.insert(key, accidental);
+                }
+
+                let accidental = match self.accidentals.get(&key) {
+                    Some(accidental) => *accidental,
+                    None => key_accidental(self.key.as_ref().expect("Body after K:"), class),
+                };
+
+                self.pending = Some(Pending {
+                    pitch: Some(Pitch {
+                        class,
+                        accidental,
+                        octave,
+                    }),
+                    ticks,
+                    tuplet: self.next_tuplet(),
+                    token,
+                    marks,
+                });
+            }
+        }
+
+        if let Some(multiplier) = self.next_multiplier.take()
+            && let Some(pending) = self.pending.as_mut()
+        {
+            pending.ticks = pending.ticks * multiplier / 4;
+        }
+
+        Ok(i)
+    }
+
+    /// Tuplet of the next note, it counts as one of the tuplet's notes.
+    fn next_tuplet(&mut self) -> Option<Tuplet> {
+        let (tuplet, left) = self.tuplet.as_mut()?;
+        let tuplet = *tuplet;
+
+        *left -= 1;
+
+        if *left == 0 {
+            self.tuplet = None;
+        }
+
+        Some(tuplet)
+    }
+
+    /// Articulation, dynamic or hairpin of the next note, the other
+    /// decorations are skipped.
+    fn decoration(&mut self, name: &str) {
+        let mark = match name {
+            "<(" | "crescendo(" => Some(Mark::HairpinStart(HairpinKind::Crescendo)),
+            ">(" | "diminuendo(" => Some(Mark::HairpinStart(HairpinKind::Diminuendo)),
+            "<)" | "crescendo)" | ">)" | "diminuendo)" => Some(Mark::HairpinEnd),
+            _ => Dynamic::parse(name)
+                .map(Attribute::Dynamic)
+                .or_else(|| parse_articulation(name).map(Attribute::Articulation))
+                .map(Mark::Attribute),
+        };
+
+        self.marks.extend(mark);
+    }
+
+    /// Tie or end of a slur written after a note.
+    fn after_note(&mut self, attribute: Attribute) {
+        if let Some(pending) = self.pending.as_mut() {
+            pending.marks.push(Mark::Attribute(attribute));
+        } else if let Some(note_index) = self.notes.len().checked_sub(1) {
+            self.attributes.push(NoteAttribute {
+                note_index,
+                attribute,
+            });
+        }
+    }
+
+    /// Add the pending note to the measure.
+    fn flush(&mut self) -> Result<(), AbcParseError> {
+        if let Some(pending) = self.pending.take() {
+            self.push(
+                pending.pitch,
+                pending.ticks,
+                pending.tuplet,
+                &pending.token,
+                pending.marks,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Notes lasting `ticks`, split into several tied ones if the length
+    /// has no note value like 5/8, and all of them in the tuplet. Ties and
+    /// ends of slurs belong to the last of them, the other marks to the
+    /// first.
+    fn push(
+        &mut self,
+        pitch: Option<Pitch>,
+        ticks: u32,
+        tuplet: Option<Tuplet>,
+        token: &str,
+        marks: Vec<Mark>,
+    ) -> Result<(), AbcParseError> {
+        let durations = Duration::fill(ticks, PPQ);
+
+        if durations.iter().map(|d| d.to_ticks(PPQ)).sum::<u32>() != ticks {
+            return Err(AbcParseError::InvalidLength(token.to_string()));
+        }
+
+        let first = self.notes.len();
+
+        for duration in durations {
+            let duration = match tuplet {
+                Some(tuplet) => duration.in_tuplet(tuplet),
+                None => duration,
+            };
+
+            if pitch.is_some() && self.notes.len() > first {
+                self.attributes.push(NoteAttribute {
+                    note_index: self.notes.len() - 1,
+                    attribute: Attribute::Tie,
+                });
+            }
+
+            self.notes.push(match pitch {
+                Some(pitch) => Note::Tone(pitch, duration),
+                None => Note::Rest(duration),
+            });
+        }
+
+        let Some(last) = self
+            .notes
+            .len()
+            .checked_sub(1)
+            .filter(|last| *last >= first)
+        else {
+            return Ok(());
+        };
+        let position = |note| NotePosition {
+            measure: self.measures.len(),
+            note,
+        };
+
+        for mark in marks {
+            match mark {
+                Mark::Attribute(attribute @ (Attribute::Tie | Attribute::SlurStop)) => {
+                    self.attributes.push(NoteAttribute {
+                        note_index: last,
+                        attribute,
+                    });
+                }
+                Mark::Attribute(attribute) => self.attributes.push(NoteAttribute {
+                    note_index: first,
+                    attribute,
+                }),
+                Mark::HairpinStart(kind) => self.open_hairpin = Some((kind, position(first))),
+                Mark::HairpinEnd => {
+                    if let Some((kind, start)) = self.open_hairpin.take() {
+                        self.hairpins.push(Hairpin {
+                            kind,
+                            start,
+                            end: position(last),
+                        });
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn bar(&mut self) -> Result<(), AbcParseError> {
+        self.flush()?;
+        self.accidentals.clear();
+
+        if !self.notes.is_empty() {
+            let mut measure = Measure::new(
+                self.meter.unwrap_or(TimeSignature::COMMON_TIME),
+                std::mem::take(&mut self.notes),
+            );
+
+            measure.chord_symbols = std::mem::take(&mut self.chord_symbols);
+            measure.attributes = std::mem::take(&mut self.attributes);
+            // ABC has no mark for an anacrusis, a short first bar is one
+            measure.pickup =
+                self.measures.is_empty() && measure.filled() < measure.time_signature.length();
+            self.measures.push(measure);
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for AbcParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AbcParseError::MissingKey => f.write_str("missing K: field"),
+            AbcParseError::InvalidField(s) => write!(f, "invalid field {s}"),
+            AbcParseError::InvalidKey(s) => write!(f, "invalid key {s}"),
+            AbcParseError::InvalidNote(s) => write!(f, "invalid note {s}"),
+            AbcParseError::InvalidLength(s) => write!(f, "invalid note length {s}"),
+            AbcParseError::InvalidChord(s) => write!(f, "invalid chord symbol {s}"),
+            AbcParseError::InvalidTuplet(s) => write!(f, "invalid tuplet {s}"),
+            AbcParseError::Unclosed(c) => write!(f, "unclosed {c}"),
+            AbcParseError::Unsupported(s) => write!(f, "{s} is not supported"),
+        }
+    }
+}
+
+impl std::error::Error for AbcParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TUNE: &str = r#"X:3
+T:The Kesh % a jig
+M:6/8
+L:1/8
+Q:3/8=100
+K:G
+|:"G"GAG GAB|"D"ABA ABd|"G"edd gdd|"D"edB dBA:|
+"Em"e2 ^c =c>B A|_b/a/ z z3|]
+
+X:4
+T:Second tune
+K:C
+"#;
+
+    fn notes(tune: &AbcTune, measure: usize) -> Vec<String> {
+        tune.staff.measures[measure]
+            .notes
+            .iter()
+            .map(|note| match note {
+                Note::Tone(pitch, duration) => format!(
+                    "{pitch}{}/{}{}",
+                    pitch.octave,
+                    duration.name as u8,
+                    ".".repeat(duration.dots as usize)
+                ),
+                Note::Rest(duration) => format!(
+                    "r/{}{}",
+                    duration.name as u8,
+                    ".".repeat(duration.dots as usize)
+                ),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_tune() {
+        let tune = AbcTune::parse(TUNE).unwrap();
+
+        assert_eq!(tune.reference, 3);
+        assert_eq!(tune.title, Some("The Kesh".to_string()));
+        assert_eq!(tune.unit, Duration::new(DurationName::Eigth));
+        assert_eq!(
+            tune.tempo,
+            Some(Tempo {
+                beat: Duration::dotted(DurationName::Quarter, 1),
+                per_minute: 100
+            })
+        );
+        assert_eq!(tune.key.scale_type.name, "ionian");
+        assert_eq!(tune.staff.measures.len(), 6);
+        assert_eq!(
+            tune.staff.measures[0].time_signature,
+            TimeSignature::new(6, 8)
+        );
+        assert_eq!(
+            notes(&tune, 2),
+            vec!["E5/8", "D5/8", "D5/8", "G5/8", "D5/8", "D5/8"]
+        );
+        assert_eq!(
+            tune.staff.measures[1].chord_symbols,
+            vec![ChordSymbol {
+                note_index: 0,
+                chord: Chord::parse("D").unwrap()
+            }]
+        );
+        // Accidentals last until the bar line, the F is sharp in G major
+        assert_eq!(
+            notes(&tune, 4),
+            vec!["E5/4", "C#5/8", "C5/8.", "B4/16", "A4/8"]
+        );
+        assert_eq!(notes(&tune, 5), vec!["Bb5/16", "A5/16", "r/8", "r/4."]);
+        assert!(!tune.staff.measures[0].pickup);
+
+        let pickup = AbcTune::parse("X:1\nM:3/4\nL:1/4\nK:C\nG|c2 e|").unwrap();
+
+        assert!(pickup.staff.measures[0].pickup);
+        assert!(!pickup.staff.measures[1].pickup);
+    }
+
+    #[test]
+    fn test_key_field() {
+        let dorian = AbcTune::parse("X:1\nK:D dorian\nDEFG|").unwrap();
+        let expected = Scale::new(
+            &Pitch::parse("D4").unwrap(),
+            &ScaleType {
+                name: "dorian".to_string(),
+                scale_degrees: modes::DORIAN.to_vec(),
+            },
+        );
+
+        assert_eq!(dorian.key.scale_type.name, "dorian");
+        assert_eq!(dorian.key.pitches, expected.pitches);
+
+        let keys: Vec<String> = ["Bbm", "F#mix", "Eb Lydian", "Aaeolian", "none"]
+            .iter()
+            .map(|k| {
+                let scale = parse_key(k).unwrap();
+
+                format!("{} {}", key_name(&scale), scale.pitches.len())
+            })
+            .collect();
+
+        assert_eq!(
+            keys,
+            vec![
+                "Bb aeolian 7",
+                "F# mixolydian 7",
+                "Eb lydian 7",
+                "A aeolian 7",
+                "C 7"
+            ]
+        );
+        assert_eq!(
+            AbcTune::parse("X:1\nK:Dxyz\nD|").unwrap_err(),
+            AbcParseError::InvalidKey("Dxyz".to_string())
+        );
+    }
+
+    #[test]
+    fn test_write_tune() {
+        let mut tune = AbcTune::parse(TUNE).unwrap();
+
+        tune.staff.measures[5].time_signature = TimeSignature::new(3, 8);
+
+        let abc = tune.to_abc();
+
+        assert_eq!(
+            abc,
+            r#"X:3
+T:The Kesh
+M:6/8
+L:1/8
+Q:3/8=100
+K:G
+"G"G A G G A B | "D"A B A A B d | "G"e d d g d d | "D"e d B d B A |
+"Em"e2 ^c =c3/2 B/ A | [M:3/8] _b/ a/ z z3 |]
+"#
+        );
+
+        let again = AbcTune::parse(&abc).unwrap();
+
+        assert_eq!(again.staff, tune.staff);
+    }
+
+    #[test]
+    fn test_attributes() {
+        let abc = "X:1\nM:2/4\nL:1/8\nK:C\n(!p!.C2 !<(!D2- | !<)!!f!!marcato!D2) !fermata!z2 |]\n";
+        let tune = AbcTune::parse(abc).unwrap();
+        let first = &tune.staff.measures[0];
+
+        assert_eq!(
+            first.note_attributes(0).copied().collect::<Vec<_>>(),
+            vec![
+                Attribute::SlurStart,
+                Attribute::Dynamic(Dynamic::P),
+                Attribute::Articulation(Articulation::Staccato)
+            ]
+        );
+        assert!(first.has_attribute(1, Attribute::Tie));
+        assert!(tune.staff.measures[1].has_attribute(0, Attribute::SlurStop));
+        assert_eq!(
+            tune.staff.hairpins,
+            vec![Hairpin {
+                kind: HairpinKind::Crescendo,
+                start: NotePosition {
+                    measure: 0,
+                    note: 1
+                },
+                end: NotePosition {
+                    measure: 1,
+                    note: 0
+                },
+            }]
+        );
+        assert_eq!(tune.to_abc(), abc);
+
+        // Lengths without a note value are tied notes
+        let tune = AbcTune::parse("X:1\nL:1/8\nK:C\nHC5 z3|]").unwrap();
+        let measure = &tune.staff.measures[0];
+
+        assert_eq!(notes(&tune, 0), vec!["C4/2", "C4/8", "r/4."]);
+        assert!(measure.has_attribute(0, Attribute::Articulation(Articulation::Fermata)));
+        assert!(measure.has_attribute(0, Attribute::Tie));
+        assert!(!measure.has_attribute(1, Attribute::Tie));
+    }
+
+    #[test]
+    fn test_tuplets() {
+        let abc = "X:1\nM:2/4\nL:1/8\nK:C\n(3C D E G2 | (3:2:2C2 D z2 |]\n";
+        let tune = AbcTune::parse(abc).unwrap();
+        let tuplets = |measure: usize| -> Vec<Option<Tuplet>> {
+            tune.staff.measures[measure]
+                .notes
+                .iter()
+                .map(|note| note.duration().tuplet)
+                .collect()
+        };
+
+        assert_eq!(notes(&tune, 0), vec!["C4/8", "D4/8", "E4/8", "G4/4"]);
+        assert_eq!(
+            tuplets(0),
+            vec![
+                Some(Tuplet::TRIPLET),
+                Some(Tuplet::TRIPLET),
+                Some(Tuplet::TRIPLET),
+                None
+            ]
+        );
+        assert_eq!(notes(&tune, 1), vec!["C4/4", "D4/8", "r/4"]);
+        assert_eq!(
+            tuplets(1),
+            vec![Some(Tuplet::TRIPLET), Some(Tuplet::TRIPLET), None]
+        );
+        assert!(tune.staff.measures.iter().all(|m| m.validate().is_ok()));
+        assert_eq!(tune.to_abc(), abc);
+
+        // Five in the time of three in compound meters
+        let tune = AbcTune::parse("X:1\nM:6/8\nL:1/8\nK:C\n(5CDEFG ABc|]").unwrap();
+
+        assert_eq!(
+            tune.staff.measures[0].notes[0].duration().tuplet,
+            Some(Tuplet::new(5, 3))
+        );
+        assert!(tune.staff.measures[0].validate().is_ok());
+    }
+
+    #[test]
+    fn test_invalid_tunes() {
+        assert_eq!(
+            AbcTune::parse("X:1\nT:No key\n").unwrap_err(),
+            AbcParseError::MissingKey
+        );
+        assert_eq!(
+            AbcTune::parse("X:1\nK:C\n(3:0CDE|").unwrap_err(),
+            AbcParseError::InvalidTuplet("(3:0".to_string())
+        );
+        assert_eq!(
+            AbcTune::parse("X:1\nK:C\n\"Xyz\"C|").unwrap_err(),
+            AbcParseError::InvalidChord("Xyz".to_string())
+        );
+        assert_eq!(
+            AbcTune::parse("X:1\nL:1/8\nK:C\nC/3|").unwrap_err(),
+            AbcParseError::InvalidLength("C/3".to_string())
+        );
+
+        for (body, error) in [
+            ("C\"", AbcParseError::Unclosed('"')),
+            ("C!p", AbcParseError::Unclosed('!')),
+            ("C+", AbcParseError::Unclosed('+')),
+            ("{g", AbcParseError::Unclosed('{')),
+            (
+                "C99999999",
+                AbcParseError::InvalidLength("C99999999".to_string()),
+            ),
+            ("C0/0", AbcParseError::InvalidLength("C0/0".to_string())),
+            (
+                "Z99999999",
+                AbcParseError::InvalidLength("Z99999999".to_string()),
+            ),
+        ] {
+            assert_eq!(
+                AbcTune::parse(&format!("X:1\nK:C\n{body}\n")).unwrap_err(),
+                error,
+                "{body}"
+            );
+        }
+
+        assert!(matches!(
+            AbcTune::parse(&format!("X:1\nK:C\nC{}|", ",".repeat(200))).unwrap_err(),
+            AbcParseError::InvalidNote(_)
+        ));
+        assert!(matches!(
+            AbcTune::parse("X:1\nM:200+100/4\nK:C\nC|").unwrap_err(),
+            AbcParseError::InvalidField(_)
+        ));
+    }
+}
@@ -1,16 +1,671 @@
-use crate::note::Note;
+use std::fmt::Display;
 
+use crate::{
+    chord::Chord,
+    fraction::Fraction,
+    note::{Attribute, Duration, DurationName, Note, PPQ, Tuplet},
+    pitch::Pitch,
+};
+
+/// Notes below middle C on average are written in bass clef.
+const BASS_CLEF_LIMIT: i64 = 4 * 12;
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Staff {
     pub measures: Vec<Measure>,
+    pub hairpins: Vec<Hairpin>,
 }
 
+#[derive(Clone, Debug, PartialEq)]
 pub struct Measure {
     pub time_signature: TimeSignature,
     pub notes: Vec<Note>,
+    pub chord_symbols: Vec<ChordSymbol>,
+    /// Ties, slurs, articulations and dynamics of the notes.
+    pub attributes: Vec<NoteAttribute>,
+    /// Incomplete first measure (anacrusis), it is allowed to be short.
+    pub pickup: bool,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct TimeSignature {
     /// Number of beats
     pub numerator: u8,
     pub denominator: u8,
 }
+
+/// Measure whose notes don't add up to its time signature.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MeasureError {
+    Overfull { filled: Fraction, length: Fraction },
+    Underfull { filled: Fraction, length: Fraction },
+}
+
+/// Notes which can't be put into measures.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RebarError {
+    /// No note values last the part of a note up to the barline, like the
+    /// 11/12 left of a 4/4 measure after a triplet eighth.
+    NoNoteValue(Fraction),
+}
+
+/// Chord symbol written above a note of the measure.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChordSymbol {
+    /// Index of the note in the measure where the chord starts.
+    pub note_index: usize,
+    pub chord: Chord,
+}
+
+/// Attribute of a note of the measure.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NoteAttribute {
+    pub note_index: usize,
+    pub attribute: Attribute,
+}
+
+/// Note of a staff by the index of its measure and its index in it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NotePosition {
+    pub measure: usize,
+    pub note: usize,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HairpinKind {
+    Crescendo,
+    Diminuendo,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Clef {
+    Treble,
+    Bass,
+}
+
+/// Crescendo or diminuendo over the notes from `start` to `end`, both
+/// included.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hairpin {
+    pub kind: HairpinKind,
+    pub start: NotePosition,
+    pub end: NotePosition,
+}
+
+impl Staff {
+    /// Staff of the measures without hairpins.
+    pub fn new(measures: Vec<Measure>) -> Self {
+        Staff {
+            measures,
+            hairpins: vec![],
+        }
+    }
+
+    /// Put the notes into measures one after the other. A note which
+    /// doesn't fit into the measure starts the next one, the last measure
+    /// may be incomplete.
+    pub fn from_notes(time_signature: TimeSignature, notes: Vec<Note>) -> Self {
+        let length = time_signature.to_ticks(PPQ);
+        let mut measures: Vec<Measure> = vec![];
+        let mut filled = length;
+
+        for note in notes {
+            let ticks = note.duration().to_ticks(PPQ);
+
+            match measures.last_mut() {
+                Some(measure) if filled + ticks <= length => measure.notes.push(note),
+                _ => {
+                    measures.push(Measure::new(time_signature, vec![note]));
+                    filled = 0;
+                }
+            }
+
+            filled += ticks;
+        }
+
+        Staff::new(measures)
+    }
+
+    /// Put the notes into full measures. Notes crossing a barline are split
+    /// and the parts are tied, rests are split without ties. The first
+    /// measure is a pickup of `pickup` length if it is not zero, and the
+    /// last measure is filled with rests. A time signature without length
+    /// like 0/4 gives no measures.
+    pub fn rebar(
+        time_signature: TimeSignature,
+        notes: &[Note],
+        pickup: Fraction,
+    ) -> Result<Self, RebarError> {
+        let bar = time_signature.length();
+
+        if bar <= Fraction::ZERO {
+            return Ok(Staff::new(vec![]));
+        }
+
+        let first = if pickup > Fraction::ZERO && pickup < bar {
+            pickup
+        } else {
+            bar
+        };
+        let mut measure = Measure::new(time_signature, vec![]);
+        let mut measures = vec![];
+        let mut room = first;
+
+        measure.pickup = first < bar;
+
+        for note in notes {
+            let mut left = note.duration().as_fraction();
+            let tuplet = note.duration().tuplet;
+
+            while left > Fraction::ZERO {
+                let part = left.min(room);
+
+                let durations = split(part, tuplet)
+                    .or_else(|| split(part, None))
+                    .ok_or(RebarError::NoNoteValue(part))?;
+
+                for duration in durations {
+                    measure.notes.push(match note {
+                        Note::Tone(pitch, _) => {
+                            measure.attributes.push(NoteAttribute {
+                                note_index: measure.notes.len(),
+                                attribute: Attribute::Tie,
+                            });
+                            Note::Tone(*pitch, duration)
+                        }
+                        Note::Rest(_) => Note::Rest(duration),
+                    });
+                }
+
+                left = left - part;
+                room = room - part;
+
+                if room == Fraction::ZERO {
+                    measures.push(std::mem::replace(
+                        &mut measure,
+                        Measure::new(time_signature, vec![]),
+                    ));
+                    room = bar;
+                }
+            }
+
+            // The last part of the note is not tied to the next note
+            if let Note::Tone(..) = note {
+                let last = if measure.notes.is_empty() {
+                    measures.last_mut()
+                } else {
+                    Some(&mut measure)
+                };
+
+                if let Some(last) = last
+                    && last.attributes.last().map(|a| a.note_index)
+                        == last.notes.len().checked_sub(1)
+                {
+                    last.attributes.pop();
+                }
+            }
+        }
+
+        if !measure.notes.is_empty() {
+            measure.fill_rests();
+            measures.push(measure);
+        }
+
+        Ok(Staff::new(measures))
+    }
+}
+
+impl Measure {
+    pub fn new(time_signature: TimeSignature, notes: Vec<Note>) -> Self {
+        Measure {
+            time_signature,
+            notes,
+            chord_symbols: vec![],
+            attributes: vec![],
+            pickup: false,
+        }
+    }
+
+    /// Attributes of the note at `note_index`.
+    pub fn note_attributes(&self, note_index: usize) -> impl Iterator<Item = &Attribute> {
+        self.attributes
+            .iter()
+            .filter(move |a| a.note_index == note_index)
+            .map(|a| &a.attribute)
+    }
+
+    pub fn has_attribute(&self, note_index: usize, attribute: Attribute) -> bool {
+        self.note_attributes(note_index).any(|a| *a == attribute)
+    }
+
+    /// Length of the notes in whole notes.
+    pub fn filled(&self) -> Fraction {
+        self.notes.iter().map(|n| n.duration().as_fraction()).sum()
+    }
+
+    /// Check that the notes fill the measure. A pickup measure may be
+    /// short but not longer than a full one.
+    pub fn validate(&self) -> Result<(), MeasureError> {
+        let filled = self.filled();
+        let length = self.time_signature.length();
+
+        if filled > length {
+            Err(MeasureError::Overfull { filled, length })
+        } else if filled < length && !self.pickup {
+            Err(MeasureError::Underfull { filled, length })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Fill a short measure with rests grouped by the beats: a rest does
+    /// not hide a beat unless it starts on a beat and covers whole beats.
+    /// After a quarter note in 4/4 the rests are a quarter and a half, and
+    /// a 16th note in 6/8 is followed by a 16th, two 8ths and a dotted
+    /// quarter. After a tuplet the rests up to the beat are in the tuplet
+    /// too. Pickup measures are left as they are, and so is the rest of a
+    /// measure which no rests can fill.
+    pub fn fill_rests(&mut self) {
+        if self.pickup {
+            return;
+        }
+
+        let length = self.time_signature.length();
+        let beat = self.time_signature.beat();
+        let mut position = self.filled();
+
+        // A whole measure of rest is one rest if it can be
+        if position == Fraction::ZERO
+            && let Some(rest) = single_duration(length)
+        {
+            self.notes.push(Note::Rest(rest));
+            return;
+        }
+
+        while position < length {
+            let in_beats = position / beat;
+            let whole_beats = ((length - position) / beat).floor();
+            let on_beat = in_beats.denominator() == 1;
+            let next_beat = (beat * Fraction::from_integer(in_beats.floor() + 1)).min(length);
+
+            if !on_grid(position) {
+                let tuplet = self.notes.iter().rev().find_map(|n| n.duration().tuplet);
+                let Some(rests) = split(next_beat - position, tuplet) else {
+                    return;
+                };
+
+                self.notes.extend(rests.into_iter().map(Note::Rest));
+                position = next_beat;
+                continue;
+            }
+
+            let rest = if on_beat && whole_beats >= 1 {
+                // Aligned groups of 1, 2 or 4 beats
+                [4, 2, 1]
+                    .into_iter()
+                    .filter(|k| *k <= whole_beats && in_beats.numerator() % k == 0)
+                    .find_map(|k| single_duration(beat * Fraction::from_integer(k)))
+            } else {
+                None
+            };
+            // Inside a beat the rests follow the halves of the beat
+            let Some(rest) = rest.or_else(|| {
+                DurationName::ALL.into_iter().map(Duration::new).find(|d| {
+                    let d = d.as_fraction();
+
+                    (position / d).denominator() == 1 && position + d <= next_beat
+                })
+            }) else {
+                return;
+            };
+
+            position = position + rest.as_fraction();
+            self.notes.push(Note::Rest(rest));
+        }
+    }
+}
+
+/// The single note value (with at most one dot) lasting `length`.
+fn single_duration(length: Fraction) -> Option<Duration> {
+    DurationName::ALL
+        .into_iter()
+        .flat_map(|name| [Duration::new(name), Duration::dotted(name, 1)])
+        .find(|d| d.as_fraction() == length)
+}
+
+/// Whether the position is a whole number of 64ths, so plain note values
+/// can reach it.
+fn on_grid(position: Fraction) -> bool {
+    (position * Fraction::from_integer(64)).denominator() == 1
+}
+
+/// Note values lasting `length` together, inside the tuplet if there is
+/// one. Returns `None` if the length (in the tuplet) is not a number of
+/// 64ths.
+fn split(length: Fraction, tuplet: Option<Tuplet>) -> Option<Vec<Duration>> {
+    let plain = match tuplet {
+        Some(t) => length * Fraction::new(t.actual as i64, t.normal.max(1) as i64),
+        None => length,
+    };
+
+    if !on_grid(plain) {
+        return None;
+    }
+
+    let ticks = (plain * Fraction::from_integer(4 * PPQ as i64)).floor() as u32;
+
+    Some(
+        Duration::fill(ticks, PPQ)
+            .into_iter()
+            .map(|d| match tuplet {
+                Some(t) => d.in_tuplet(t),
+                None => d,
+            })
+            .collect(),
+    )
+}
+
+impl TimeSignature {
+    pub const COMMON_TIME: TimeSignature = TimeSignature {
+        numerator: 4,
+        denominator: 4,
+    };
+
+    pub fn new(numerator: u8, denominator: u8) -> Self {
+        TimeSignature {
+            numerator,
+            denominator,
+        }
+    }
+
+    /// Length of a full measure in whole notes.
+    pub fn length(&self) -> Fraction {
+        Fraction::new(self.numerator as i64, self.denominator.max(1) as i64)
+    }
+
+    /// Length of a beat, a dotted note in compound meters like 6/8 and a
+    /// note of the denominator otherwise.
+    pub fn beat(&self) -> Fraction {
+        let beat = Fraction::new(1, self.denominator.max(1) as i64);
+
+        if self.numerator > 3 && self.numerator.is_multiple_of(3) {
+            beat * Fraction::from_integer(3)
+        } else {
+            beat
+        }
+    }
+
+    /// Length of a full measure in MIDI ticks.
+    pub fn to_ticks(&self, ticks_per_quarter: u32) -> u32 {
+        ticks_per_quarter * 4 * self.numerator as u32 / self.denominator.max(1) as u32
+    }
+}
+
+impl Clef {
+    /// Clef which fits the pitches best, treble when there are none.
+    pub fn for_pitches(pitches: &[Pitch]) -> Self {
+        let sum: i64 = pitches.iter().map(|p| p.to_half_notes() as i64).sum();
+
+        if !pitches.is_empty() && sum / (pitches.len() as i64) < BASS_CLEF_LIMIT {
+            Clef::Bass
+        } else {
+            Clef::Treble
+        }
+    }
+}
+
+impl Display for MeasureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeasureError::Overfull { filled, length } => {
+                write!(f, "overfull measure, {filled} of {length} whole notes")
+            }
+            MeasureError::Underfull { filled, length } => {
+                write!(f, "underfull measure, {filled} of {length} whole notes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for MeasureError {}
+
+impl Display for RebarError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RebarError::NoNoteValue(length) => {
+                write!(f, "no note values last {length} whole notes")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RebarError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_staff_from_notes() {
+        let half = Duration::new(DurationName::Half);
+        let dotted = Duration::dotted(DurationName::Half, 1);
+        let c = Pitch::parse("C4").unwrap();
+        let notes = vec![
+            Note::Tone(c, half),
+            Note::Rest(half),
+            Note::Tone(c, dotted),
+            Note::Tone(c, dotted),
+            Note::Tone(c, half),
+        ];
+
+        let staff = Staff::from_notes(TimeSignature::COMMON_TIME, notes);
+        let lengths: Vec<usize> = staff.measures.iter().map(|m| m.notes.len()).collect();
+
+        assert_eq!(lengths, vec![2, 1, 1, 1]);
+    }
+
+    fn names(measure: &Measure) -> Vec<String> {
+        measure
+            .notes
+            .iter()
+            .map(|note| {
+                let duration = note.duration();
+                let name = match note {
+                    Note::Tone(pitch, _) => format!("{pitch}{}", pitch.octave),
+                    Note::Rest(_) => "r".to_string(),
+                };
+
+                let tuplet = duration
+                    .tuplet
+                    .map_or(String::new(), |t| format!("({}:{})", t.actual, t.normal));
+
+                format!(
+                    "{name}/{}{}{tuplet}",
+                    duration.name as u8,
+                    ".".repeat(duration.dots as usize)
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_clef_for_pitches() {
+        let pitches = |p: &str| -> Vec<Pitch> {
+            p.split_whitespace()
+                .map(|p| Pitch::parse(p).unwrap())
+                .collect()
+        };
+
+        assert_eq!(Clef::for_pitches(&pitches("C3 G3 C4")), Clef::Bass);
+        assert_eq!(Clef::for_pitches(&pitches("G3 E4 G4")), Clef::Treble);
+        assert_eq!(Clef::for_pitches(&[]), Clef::Treble);
+        assert_eq!(Clef::for_pitches(&pitches("C8").repeat(800)), Clef::Treble);
+    }
+
+    #[test]
+    fn test_validate() {
+        let quarter = Duration::new(DurationName::Quarter);
+        let c = Pitch::parse("C4").unwrap();
+        let mut measure = Measure::new(TimeSignature::new(3, 4), vec![Note::Tone(c, quarter); 2]);
+
+        assert_eq!(
+            measure.validate(),
+            Err(MeasureError::Underfull {
+                filled: Fraction::new(1, 2),
+                length: Fraction::new(3, 4)
+            })
+        );
+
+        measure.pickup = true;
+
+        assert_eq!(measure.validate(), Ok(()));
+
+        measure
+            .notes
+            .extend([Note::Rest(quarter), Note::Rest(quarter)]);
+
+        assert_eq!(
+            measure.validate().unwrap_err().to_string(),
+            "overfull measure, 1 of 3/4 whole notes"
+        );
+
+        let triplet = Duration::new(DurationName::Eigth).in_tuplet(Tuplet::TRIPLET);
+        let measure = Measure::new(TimeSignature::new(2, 4), vec![Note::Tone(c, triplet); 6]);
+
+        assert_eq!(measure.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_fill_rests() {
+        let c = Pitch::parse("C4").unwrap();
+        let filled = |time_signature: TimeSignature, notes: Vec<Duration>| {
+            let notes = notes.into_iter().map(|d| Note::Tone(c, d)).collect();
+            let mut measure = Measure::new(time_signature, notes);
+
+            measure.fill_rests();
+            assert_eq!(measure.validate(), Ok(()));
+            names(&measure)
+        };
+
+        assert_eq!(
+            filled(
+                TimeSignature::COMMON_TIME,
+                vec![Duration::new(DurationName::Quarter)]
+            ),
+            vec!["C4/4", "r/4", "r/2"]
+        );
+        assert_eq!(
+            filled(
+                TimeSignature::COMMON_TIME,
+                vec![Duration::dotted(DurationName::Quarter, 1)]
+            ),
+            vec!["C4/4.", "r/8", "r/2"]
+        );
+        assert_eq!(
+            filled(
+                TimeSignature::new(6, 8),
+                vec![Duration::new(DurationName::Sixteenth)]
+            ),
+            vec!["C4/16", "r/16", "r/8", "r/8", "r/4."]
+        );
+        assert_eq!(filled(TimeSignature::new(3, 4), vec![]), vec!["r/2."]);
+        assert_eq!(
+            filled(
+                TimeSignature::new(3, 4),
+                vec![Duration::new(DurationName::Eigth)]
+            ),
+            vec!["C4/8", "r/8", "r/4", "r/4"]
+        );
+        assert_eq!(
+            filled(
+                TimeSignature::new(2, 4),
+                vec![Duration::new(DurationName::Eigth).in_tuplet(Tuplet::TRIPLET)]
+            ),
+            vec!["C4/8(3:2)", "r/4(3:2)", "r/4"]
+        );
+    }
+
+    #[test]
+    fn test_rebar() {
+        let c = Pitch::parse("C4").unwrap();
+        let d = Pitch::parse("D4").unwrap();
+        let half = Duration::new(DurationName::Half);
+        let notes = vec![
+            Note::Tone(c, Duration::new(DurationName::Quarter)),
+            Note::Tone(d, Duration::new(DurationName::Whole)),
+            Note::Rest(half),
+            Note::Tone(c, Duration::dotted(DurationName::Half, 1)),
+        ];
+
+        let staff = Staff::rebar(TimeSignature::new(3, 4), &notes, Fraction::ZERO).unwrap();
+        let measures: Vec<Vec<String>> = staff.measures.iter().map(names).collect();
+
+        assert_eq!(
+            measures,
+            vec![
+                vec!["C4/4", "D4/2"],
+                vec!["D4/2", "r/4"],
+                vec!["r/4", "C4/2"],
+                vec!["C4/4", "r/4", "r/4"]
+            ]
+        );
+        assert_eq!(
+            staff
+                .measures
+                .iter()
+                .map(|m| (0..m.notes.len())
+                    .filter(|i| m.has_attribute(*i, Attribute::Tie))
+                    .collect())
+                .collect::<Vec<Vec<usize>>>(),
+            vec![vec![1], vec![], vec![1], vec![]]
+        );
+        assert!(staff.measures.iter().all(|m| m.validate().is_ok()));
+
+        let staff = Staff::rebar(TimeSignature::COMMON_TIME, &notes, Fraction::new(1, 4)).unwrap();
+
+        assert!(staff.measures[0].pickup);
+        assert_eq!(names(&staff.measures[0]), vec!["C4/4"]);
+        assert_eq!(names(&staff.measures[1]), vec!["D4/1"]);
+        assert!(staff.measures[1].attributes.is_empty());
+        assert!(
+            Staff::rebar(TimeSignature::new(0, 4), &notes, Fraction::ZERO)
+                .unwrap()
+                .measures
+                .is_empty()
+        );
+
+        // The part of the triplet half note in the first measure is a
+        // dotted quarter of the triplet
+        let triplet = Duration::new(DurationName::Eigth).in_tuplet(Tuplet::TRIPLET);
+        let notes = vec![
+            Note::Tone(c, Duration::new(DurationName::Quarter)),
+            Note::Tone(d, half.in_tuplet(Tuplet::TRIPLET)),
+            Note::Tone(c, triplet),
+            Note::Tone(c, triplet),
+            Note::Tone(d, Duration::new(DurationName::Whole)),
+        ];
+        let staff = Staff::rebar(TimeSignature::new(2, 4), &notes, Fraction::ZERO).unwrap();
+
+        assert_eq!(
+            staff.measures.iter().map(names).collect::<Vec<_>>(),
+            vec![
+                vec!["C4/4", "D4/4.(3:2)"],
+                vec!["D4/8(3:2)", "C4/8(3:2)", "C4/8(3:2)", "D4/4"],
+                vec!["D4/2"],
+                vec!["D4/4", "r/4"],
+            ]
+        );
+        assert!(staff.measures.iter().all(|m| m.validate().is_ok()));
+        assert_eq!(
+            Staff::rebar(
+                TimeSignature::COMMON_TIME,
+                &[
+                    Note::Tone(c, triplet),
+                    Note::Tone(d, Duration::new(DurationName::Whole))
+                ],
+                Fraction::ZERO,
+            ),
+            Err(RebarError::NoNoteValue(Fraction::new(11, 12)))
+        );
+    }
+}
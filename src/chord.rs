@@ -1,59 +1,1175 @@
-use crate::interval::Interval;
+use std::fmt::Display;
 
-const MINOR: [Interval; 3] = [Interval::Unison, Interval::Minor3rd, Interval::Perfect5th];
-const MAJOR: [Interval; 3] = [Interval::Unison, Interval::Major3rd, Interval::Perfect5th];
-// Tritone is not expressing that this is a flat 5th (because it can be a sharp 4th as well)
-const DIMINISED: [Interval; 3] = [Interval::Unison, Interval::Minor3rd, Interval::Tritone];
+use crate::{
+    interval::{IntervalQuality, SpelledInterval},
+    pitch::{Accidental, Pitch, PitchClass, SpellingPreference},
+};
+
+const MINOR: [SpelledInterval; 3] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+];
+
+const MAJOR: [SpelledInterval; 3] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+];
+
+const DIMINISED: [SpelledInterval; 3] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::DIMINISHED_5TH,
+];
+
+const AUGMENTED: [SpelledInterval; 3] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::AUGMENTED_5TH,
+];
+
+// Csus2
+const SUSPENDED2: [SpelledInterval; 3] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_2ND,
+    SpelledInterval::PERFECT_5TH,
+];
+
+// Csus4
+const SUSPENDED4: [SpelledInterval; 3] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::PERFECT_4TH,
+    SpelledInterval::PERFECT_5TH,
+];
+
+// C6
+const MAJOR6: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MAJOR_6TH,
+];
+
+// Cm6
+const MINOR6: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MAJOR_6TH,
+];
 
 // This is Cmin7
-const MINOR7: [Interval; 4] = [
-    Interval::Unison,
-    Interval::Minor3rd,
-    Interval::Perfect5th,
-    Interval::Minor7th,
+const MINOR7: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MINOR_7TH,
 ];
 
 // This is Cmaj7
-const MAJOR7: [Interval; 4] = [
-    Interval::Unison,
-    Interval::Major3rd,
-    Interval::Perfect5th,
-    Interval::Major7th,
+const MAJOR7: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MAJOR_7TH,
 ];
 
 // This is C7
-const DOMINANT7: [Interval; 4] = [
-    Interval::Unison,
-    Interval::Major3rd,
-    Interval::Perfect5th,
-    Interval::Minor7th,
+const DOMINANT7: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MINOR_7TH,
+];
+
+// CminMaj7
+const MINOR_MAJOR7: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MAJOR_7TH,
 ];
 
 // Cmin7b5
-const HALF_DIMINISED: [Interval; 4] = [
-    Interval::Unison,
-    Interval::Minor3rd,
-    Interval::Tritone,
-    Interval::Minor7th,
+const HALF_DIMINISED: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::DIMINISHED_5TH,
+    SpelledInterval::MINOR_7TH,
 ];
 
-// Cdim7
-const DIMINISED7: [Interval; 4] = [
-    Interval::Unison,
-    Interval::Minor3rd,
-    Interval::Tritone,
-    // Double flat 7
-    Interval::Major6th,
+// Cdim7, the 7th is a double flat 7
+const DIMINISED7: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::DIMINISHED_5TH,
+    SpelledInterval::DIMINISHED_7TH,
 ];
 
-// Csus2
-const SUSPENDED2: [Interval; 3] = [Interval::Unison, Interval::Major2nd, Interval::Perfect5th];
+// Caug7, C7#5
+const AUGMENTED7: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::AUGMENTED_5TH,
+    SpelledInterval::MINOR_7TH,
+];
 
-// Csus4
-const SUSPENDED4: [Interval; 3] = [Interval::Unison, Interval::Perfect4th, Interval::Perfect5th];
+// CaugMaj7, Cmaj7#5
+const AUGMENTED_MAJOR7: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::AUGMENTED_5TH,
+    SpelledInterval::MAJOR_7TH,
+];
+
+// C7sus2
+const DOMINANT7_SUSPENDED2: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_2ND,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MINOR_7TH,
+];
+
+// C7sus4
+const DOMINANT7_SUSPENDED4: [SpelledInterval; 4] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::PERFECT_4TH,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MINOR_7TH,
+];
+
+// C6/9
+const SIX_NINE: [SpelledInterval; 5] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MAJOR_6TH,
+    SpelledInterval::MAJOR_9TH,
+];
+
+// Cm6/9
+const MINOR_SIX_NINE: [SpelledInterval; 5] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MAJOR_6TH,
+    SpelledInterval::MAJOR_9TH,
+];
+
+// C9
+const DOMINANT9: [SpelledInterval; 5] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MINOR_7TH,
+    SpelledInterval::MAJOR_9TH,
+];
+
+// Cmaj9
+const MAJOR9: [SpelledInterval; 5] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MAJOR_7TH,
+    SpelledInterval::MAJOR_9TH,
+];
+
+// Cm9
+const MINOR9: [SpelledInterval; 5] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MINOR_7TH,
+    SpelledInterval::MAJOR_9TH,
+];
+
+// C11
+const DOMINANT11: [SpelledInterval; 6] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MINOR_7TH,
+    SpelledInterval::MAJOR_9TH,
+    SpelledInterval::PERFECT_11TH,
+];
+
+// Cmaj11
+const MAJOR11: [SpelledInterval; 6] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MAJOR_7TH,
+    SpelledInterval::MAJOR_9TH,
+    SpelledInterval::PERFECT_11TH,
+];
+
+// Cm11
+const MINOR11: [SpelledInterval; 6] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MINOR_7TH,
+    SpelledInterval::MAJOR_9TH,
+    SpelledInterval::PERFECT_11TH,
+];
+
+// C13, the 11th is omitted since it clashes with the major 3rd
+const DOMINANT13: [SpelledInterval; 6] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MINOR_7TH,
+    SpelledInterval::MAJOR_9TH,
+    SpelledInterval::MAJOR_13TH,
+];
+
+// Cmaj13, without 11th as well
+const MAJOR13: [SpelledInterval; 6] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MAJOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MAJOR_7TH,
+    SpelledInterval::MAJOR_9TH,
+    SpelledInterval::MAJOR_13TH,
+];
+
+// Cm13
+const MINOR13: [SpelledInterval; 7] = [
+    SpelledInterval::UNISON,
+    SpelledInterval::MINOR_3RD,
+    SpelledInterval::PERFECT_5TH,
+    SpelledInterval::MINOR_7TH,
+    SpelledInterval::MAJOR_9TH,
+    SpelledInterval::PERFECT_11TH,
+    SpelledInterval::MAJOR_13TH,
+];
+
+/// Octave of the root when a chord is read from a symbol, the bass is
+/// written an octave lower.
+pub const SYMBOL_OCTAVE: i8 = 4;
 
-// TODO augmented and suspended chords
-//
 // https://www.all-guitar-chords.com/
-//
-// Chord can have major quality or minor quality
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChordQuality {
+    Major,
+    Minor,
+    Diminished,
+    Augmented,
+    Suspended2,
+    Suspended4,
+    Major6,
+    Minor6,
+    Dominant7,
+    Major7,
+    Minor7,
+    MinorMajor7,
+    HalfDiminished7,
+    Diminished7,
+    Augmented7,
+    AugmentedMajor7,
+    Dominant7Suspended2,
+    Dominant7Suspended4,
+    SixNine,
+    MinorSixNine,
+    Dominant9,
+    Major9,
+    Minor9,
+    Dominant11,
+    Major11,
+    Minor11,
+    Dominant13,
+    Major13,
+    Minor13,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chord {
+    pub root: Pitch,
+    pub quality: ChordQuality,
+    /// Added or altered tones on top of the quality, like the b9 and #11
+    /// of a C7b9#11. An alteration replaces the chord tone with the same
+    /// interval number, otherwise it is added.
+    pub alterations: Vec<SpelledInterval>,
+    /// Bass note of an inversion or a slash chord.
+    pub bass: Option<Pitch>,
+}
+
+/// One possible interpretation of a set of pitches as a chord.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChordMatch {
+    pub chord: Chord,
+    /// Position of the bass in the chord tones: 0 is root position, 1 is
+    /// first inversion, etc. `None` if the bass is not a chord tone.
+    pub inversion: Option<u8>,
+    pub missing_fifth: bool,
+    /// Lower is a better fit, used to rank the interpretations.
+    pub penalty: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChordParseError {
+    Empty,
+    InvalidRoot(String),
+    InvalidBass(String),
+    UnknownSymbol(String),
+    UnsupportedChord(String),
+}
+
+impl ChordQuality {
+    pub const ALL: [ChordQuality; 29] = [
+        ChordQuality::Major,
+        ChordQuality::Minor,
+        ChordQuality::Diminished,
+        ChordQuality::Augmented,
+        ChordQuality::Suspended2,
+        ChordQuality::Suspended4,
+        ChordQuality::Major6,
+        ChordQuality::Minor6,
+        ChordQuality::Dominant7,
+        ChordQuality::Major7,
+        ChordQuality::Minor7,
+        ChordQuality::MinorMajor7,
+        ChordQuality::HalfDiminished7,
+        ChordQuality::Diminished7,
+        ChordQuality::Augmented7,
+        ChordQuality::AugmentedMajor7,
+        ChordQuality::Dominant7Suspended2,
+        ChordQuality::Dominant7Suspended4,
+        ChordQuality::SixNine,
+        ChordQuality::MinorSixNine,
+        ChordQuality::Dominant9,
+        ChordQuality::Major9,
+        ChordQuality::Minor9,
+        ChordQuality::Dominant11,
+        ChordQuality::Major11,
+        ChordQuality::Minor11,
+        ChordQuality::Dominant13,
+        ChordQuality::Major13,
+        ChordQuality::Minor13,
+    ];
+
+    /// Intervals of the chord tones from the root.
+    pub fn intervals(&self) -> &'static [SpelledInterval] {
+        match self {
+            ChordQuality::Major => &MAJOR,
+            ChordQuality::Minor => &MINOR,
+            ChordQuality::Diminished => &DIMINISED,
+            ChordQuality::Augmented => &AUGMENTED,
+            ChordQuality::Suspended2 => &SUSPENDED2,
+            ChordQuality::Suspended4 => &SUSPENDED4,
+            ChordQuality::Major6 => &MAJOR6,
+            ChordQuality::Minor6 => &MINOR6,
+            ChordQuality::Dominant7 => &DOMINANT7,
+            ChordQuality::Major7 => &MAJOR7,
+            ChordQuality::Minor7 => &MINOR7,
+            ChordQuality::MinorMajor7 => &MINOR_MAJOR7,
+            ChordQuality::HalfDiminished7 => &HALF_DIMINISED,
+            ChordQuality::Diminished7 => &DIMINISED7,
+            ChordQuality::Augmented7 => &AUGMENTED7,
+            ChordQuality::AugmentedMajor7 => &AUGMENTED_MAJOR7,
+            ChordQuality::Dominant7Suspended2 => &DOMINANT7_SUSPENDED2,
+            ChordQuality::Dominant7Suspended4 => &DOMINANT7_SUSPENDED4,
+            ChordQuality::SixNine => &SIX_NINE,
+            ChordQuality::MinorSixNine => &MINOR_SIX_NINE,
+            ChordQuality::Dominant9 => &DOMINANT9,
+            ChordQuality::Major9 => &MAJOR9,
+            ChordQuality::Minor9 => &MINOR9,
+            ChordQuality::Dominant11 => &DOMINANT11,
+            ChordQuality::Major11 => &MAJOR11,
+            ChordQuality::Minor11 => &MINOR11,
+            ChordQuality::Dominant13 => &DOMINANT13,
+            ChordQuality::Major13 => &MAJOR13,
+            ChordQuality::Minor13 => &MINOR13,
+        }
+    }
+
+    /// Suffix of the quality in a chord symbol, like `m7` in Dm7.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ChordQuality::Major => "",
+            ChordQuality::Minor => "m",
+            ChordQuality::Diminished => "dim",
+            ChordQuality::Augmented => "aug",
+            ChordQuality::Suspended2 => "sus2",
+            ChordQuality::Suspended4 => "sus4",
+            ChordQuality::Major6 => "6",
+            ChordQuality::Minor6 => "m6",
+            ChordQuality::Dominant7 => "7",
+            ChordQuality::Major7 => "maj7",
+            ChordQuality::Minor7 => "m7",
+            ChordQuality::MinorMajor7 => "mMaj7",
+            ChordQuality::HalfDiminished7 => "m7b5",
+            ChordQuality::Diminished7 => "dim7",
+            ChordQuality::Augmented7 => "aug7",
+            ChordQuality::AugmentedMajor7 => "augMaj7",
+            ChordQuality::Dominant7Suspended2 => "7sus2",
+            ChordQuality::Dominant7Suspended4 => "7sus4",
+            ChordQuality::SixNine => "6/9",
+            ChordQuality::MinorSixNine => "m6/9",
+            ChordQuality::Dominant9 => "9",
+            ChordQuality::Major9 => "maj9",
+            ChordQuality::Minor9 => "m9",
+            ChordQuality::Dominant11 => "11",
+            ChordQuality::Major11 => "maj11",
+            ChordQuality::Minor11 => "m11",
+            ChordQuality::Dominant13 => "13",
+            ChordQuality::Major13 => "maj13",
+            ChordQuality::Minor13 => "m13",
+        }
+    }
+
+    /// Find the quality which has exactly these intervals from the root.
+    pub fn from_intervals(intervals: &[SpelledInterval]) -> Option<Self> {
+        ChordQuality::ALL
+            .into_iter()
+            .find(|quality| quality.intervals() == intervals)
+    }
+
+    pub fn has_seventh(&self) -> bool {
+        self.intervals().iter().any(|i| i.number == 7)
+    }
+
+    /// Alterations which turn this quality into the given intervals, or
+    /// `None` if some chord tone of the quality is missing.
+    fn alterations_to(&self, intervals: &[SpelledInterval]) -> Option<Vec<SpelledInterval>> {
+        let own = self.intervals();
+
+        if own
+            .iter()
+            .any(|i| !intervals.iter().any(|other| other.number == i.number))
+        {
+            return None;
+        }
+
+        Some(
+            intervals
+                .iter()
+                .filter(|i| !own.contains(i))
+                .copied()
+                .collect(),
+        )
+    }
+}
+
+impl Chord {
+    pub fn new(root: Pitch, quality: ChordQuality) -> Self {
+        Chord {
+            root,
+            quality,
+            alterations: vec![],
+            bass: None,
+        }
+    }
+
+    /// Chord with a different bass note, like C/E or D/C.
+    pub fn with_bass(root: Pitch, quality: ChordQuality, bass: Pitch) -> Self {
+        Chord {
+            root,
+            quality,
+            alterations: vec![],
+            bass: Some(bass),
+        }
+    }
+
+    /// Find the chord which has these intervals from the root. If no
+    /// quality matches exactly, the one which needs the least alterations
+    /// is chosen, so 1 3 b5 b7 becomes a C7b5.
+    pub fn from_intervals(root: Pitch, intervals: &[SpelledInterval]) -> Option<Self> {
+        let mut intervals = intervals.to_vec();
+
+        intervals.sort_by_key(|i| (i.number, i.half_notes()));
+        intervals.dedup();
+
+        // Two tones with the same number (like a b9 and a 9) cannot be
+        // expressed as alterations.
+        if intervals.windows(2).any(|w| w[0].number == w[1].number) {
+            return None;
+        }
+
+        let mut best: Option<Chord> = None;
+
+        for quality in ChordQuality::ALL {
+            let Some(alterations) = quality.alterations_to(&intervals) else {
+                continue;
+            };
+
+            if best
+                .as_ref()
+                .is_none_or(|b| alterations.len() < b.alterations.len())
+            {
+                best = Some(Chord {
+                    root,
+                    quality,
+                    alterations,
+                    bass: None,
+                });
+            }
+        }
+
+        best
+    }
+
+    /// Parse a chord symbol like `F#m7b5/C`, `Bbmaj9#11` or `C6/9`.
+    pub fn parse(s: &str) -> Result<Self, ChordParseError> {
+        let s = s.trim();
+
+        if s.is_empty() {
+            return Err(ChordParseError::Empty);
+        }
+
+        let (symbol, bass) = split_bass(s)?;
+
+        let mut chars = symbol.chars();
+        let class = chars
+            .next()
+            .and_then(PitchClass::parse)
+            .ok_or_else(|| ChordParseError::InvalidRoot(s.to_string()))?;
+        let (accidental, suffix) = Accidental::parse_prefix(chars.as_str());
+
+        let root = Pitch {
+            class,
+            accidental: accidental.unwrap_or(Accidental::Natural),
+            octave: SYMBOL_OCTAVE,
+        };
+
+        let intervals = parse_suffix(suffix)?;
+        let mut chord = Chord::from_intervals(root, &intervals)
+            .ok_or_else(|| ChordParseError::UnsupportedChord(s.to_string()))?;
+
+        chord.bass = bass;
+
+        Ok(chord)
+    }
+
+    /// Intervals of the chord tones with the alterations applied.
+    pub fn intervals(&self) -> Vec<SpelledInterval> {
+        let mut intervals = self.quality.intervals().to_vec();
+
+        for alteration in &self.alterations {
+            match intervals.iter_mut().find(|i| i.number == alteration.number) {
+                Some(interval) => *interval = *alteration,
+                None => intervals.push(*alteration),
+            }
+        }
+
+        intervals.sort_by_key(|i| (i.number, i.half_notes()));
+
+        intervals
+    }
+
+    /// Chord tones stacked from the root. A tone which would need more
+    /// than two accidentals, like the 7th of Fbdim7, is spelled in the
+    /// simplest way instead.
+    pub fn chord_tones(&self) -> Vec<Pitch> {
+        self.intervals()
+            .iter()
+            .map(|interval| {
+                self.root.checked_add_interval(interval).unwrap_or_else(|| {
+                    Pitch::from_half_notes(
+                        self.root.to_half_notes() + interval.half_notes() as i16,
+                        SpellingPreference::Simplest,
+                    )
+                })
+            })
+            .collect()
+    }
+
+    /// Pitches of the chord from the lowest one. The bass note (if any)
+    /// is put below the root and it isn't repeated among the upper tones.
+    pub fn pitches(&self) -> Vec<Pitch> {
+        let tones = self.chord_tones();
+
+        let Some(bass) = self.bass else {
+            return tones;
+        };
+
+        let mut bass = Pitch {
+            octave: self.root.octave,
+            ..bass
+        };
+
+        while bass.to_half_notes() >= self.root.to_half_notes() && bass.octave > i8::MIN {
+            bass.octave -= 1;
+        }
+
+        let mut result = vec![bass];
+
+        result.extend(
+            tones
+                .into_iter()
+                .filter(|p| p.class != bass.class || p.accidental != bass.accidental),
+        );
+
+        result
+    }
+}
+
+/// Find the chords which can be built from the pitches, the best fitting
+/// ones first. Doubled notes don't matter, the 5th of the chord may be
+/// missing, and the lowest pitch is the bass of the chord. If the bass is
+/// not a chord tone the match is a slash chord like G#m7b5/E.
+pub fn identify(pitches: &[Pitch]) -> Vec<ChordMatch> {
+    let Some(bass) = pitches.iter().min_by_key(|p| p.to_half_notes()) else {
+        return vec![];
+    };
+
+    let mut classes: Vec<i8> = pitches.iter().map(Pitch::chroma).collect();
+
+    classes.sort();
+    classes.dedup();
+
+    let upper_classes: Vec<i8> = classes
+        .iter()
+        .copied()
+        .filter(|c| *c != bass.chroma())
+        .collect();
+
+    // Candidate roots with the spelling of the input, the bass first
+    let mut roots: Vec<Pitch> = vec![];
+
+    for pitch in std::iter::once(bass).chain(pitches) {
+        if !roots
+            .iter()
+            .any(|r| r.class == pitch.class && r.accidental == pitch.accidental)
+        {
+            roots.push(*pitch);
+        }
+    }
+
+    let mut result = vec![];
+
+    for root in &roots {
+        for quality in ChordQuality::ALL {
+            let chord = Chord::new(*root, quality);
+            let Some(tones) = chord
+                .intervals()
+                .iter()
+                .map(|i| root.checked_add_interval(i))
+                .collect::<Option<Vec<Pitch>>>()
+            else {
+                continue;
+            };
+            let tone_classes: Vec<i8> = tones.iter().map(Pitch::chroma).collect();
+
+            let inversion = tone_classes
+                .iter()
+                .position(|c| *c == bass.chroma())
+                .map(|i| i as u8);
+            let covered = if inversion.is_some() {
+                &classes
+            } else {
+                &upper_classes
+            };
+
+            if covered.iter().any(|c| !tone_classes.contains(c)) {
+                continue;
+            }
+
+            let mut missing_fifth = false;
+            let mut complete = true;
+
+            for (interval, class) in quality.intervals().iter().zip(&tone_classes) {
+                if !covered.contains(class) {
+                    if interval.number == 5 {
+                        missing_fifth = true;
+                    } else {
+                        complete = false;
+                    }
+                }
+            }
+
+            if !complete {
+                continue;
+            }
+
+            let misspelled = pitches
+                .iter()
+                .filter(|p| {
+                    !tones
+                        .iter()
+                        .any(|t| t.class == p.class && t.accidental == p.accidental)
+                })
+                .count() as u8;
+            let extra_tones = quality.intervals().len().saturating_sub(4) as u8;
+            let unusual = matches!(
+                quality,
+                ChordQuality::Major6
+                    | ChordQuality::Minor6
+                    | ChordQuality::Suspended2
+                    | ChordQuality::Suspended4
+                    | ChordQuality::Dominant7Suspended2
+                    | ChordQuality::Dominant7Suspended4
+            ) as u8;
+
+            let penalty = match inversion {
+                Some(0) => 0,
+                Some(_) => 1,
+                None => 4,
+            } + 2 * missing_fifth as u8
+                + misspelled
+                + extra_tones
+                + unusual;
+
+            let bass = if bass.class == root.class && bass.accidental == root.accidental {
+                None
+            } else {
+                Some(*bass)
+            };
+
+            result.push(ChordMatch {
+                chord: Chord { bass, ..chord },
+                inversion,
+                missing_fifth,
+                penalty,
+            });
+        }
+    }
+
+    result.sort_by_key(|m| m.penalty);
+
+    result
+}
+
+/// Split the slash bass from the chord symbol. The slash of 6/9 chords
+/// is not a bass.
+fn split_bass(s: &str) -> Result<(&str, Option<Pitch>), ChordParseError> {
+    let Some((symbol, bass)) = s.rsplit_once('/') else {
+        return Ok((s, None));
+    };
+
+    if bass.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok((s, None));
+    }
+
+    let mut chars = bass.chars();
+    let class = chars
+        .next()
+        .and_then(PitchClass::parse)
+        .ok_or_else(|| ChordParseError::InvalidBass(s.to_string()))?;
+
+    let (accidental, rest) = Accidental::parse_prefix(chars.as_str());
+
+    if !rest.is_empty() {
+        return Err(ChordParseError::InvalidBass(s.to_string()));
+    }
+
+    let bass = Pitch {
+        class,
+        accidental: accidental.unwrap_or(Accidental::Natural),
+        octave: SYMBOL_OCTAVE - 1,
+    };
+
+    Ok((symbol, Some(bass)))
+}
+
+/// Reads the parts of a chord symbol after the root.
+struct SymbolCursor<'a> {
+    rest: &'a str,
+}
+
+impl SymbolCursor<'_> {
+    fn eat(&mut self, prefixes: &[&str]) -> bool {
+        for prefix in prefixes {
+            if let Some(rest) = self.rest.strip_prefix(prefix) {
+                self.rest = rest;
+
+                return true;
+            }
+        }
+
+        false
+    }
+
+    fn eat_number(&mut self) -> Option<u8> {
+        let end = self
+            .rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(self.rest.len());
+        let number = self.rest[..end].parse().ok()?;
+
+        self.rest = &self.rest[end..];
+
+        Some(number)
+    }
+}
+
+const MAJOR_MARKS: [&str; 5] = ["maj", "Maj", "M", "Δ", "^"];
+
+/// Parse the quality, extensions and alterations of a chord symbol into
+/// intervals from the root.
+fn parse_suffix(suffix: &str) -> Result<Vec<SpelledInterval>, ChordParseError> {
+    let unknown = || ChordParseError::UnknownSymbol(suffix.to_string());
+    let mut cursor = SymbolCursor { rest: suffix };
+
+    let mut third = SpelledInterval::MAJOR_3RD;
+    let mut fifth = SpelledInterval::PERFECT_5TH;
+    let mut seventh = None;
+    let mut major_seventh = false;
+    let mut diminished = false;
+
+    if cursor.eat(&MAJOR_MARKS) {
+        major_seventh = true;
+    } else if cursor.eat(&["min", "mi", "m", "-"]) {
+        third = SpelledInterval::MINOR_3RD;
+
+        if cursor.eat(&["(maj7)", "(Maj7)", "(M7)", "(Δ7)"]) {
+            seventh = Some(SpelledInterval::MAJOR_7TH);
+        } else if cursor.eat(&MAJOR_MARKS) {
+            major_seventh = true;
+        }
+    } else if cursor.eat(&["dim", "°", "o"]) {
+        third = SpelledInterval::MINOR_3RD;
+        fifth = SpelledInterval::DIMINISHED_5TH;
+        diminished = true;
+    } else if cursor.eat(&["ø", "Ø"]) {
+        third = SpelledInterval::MINOR_3RD;
+        fifth = SpelledInterval::DIMINISHED_5TH;
+        seventh = Some(SpelledInterval::MINOR_7TH);
+        cursor.eat(&["7"]);
+    } else if cursor.eat(&["aug", "+"]) {
+        fifth = SpelledInterval::AUGMENTED_5TH;
+
+        if cursor.eat(&MAJOR_MARKS) {
+            major_seventh = true;
+        }
+    }
+
+    let mut extensions = vec![];
+
+    if cursor.eat(&["6/9", "69"]) {
+        extensions.push(SpelledInterval::MAJOR_6TH);
+        extensions.push(SpelledInterval::MAJOR_9TH);
+    } else if cursor.rest.starts_with(|c: char| c.is_ascii_digit()) {
+        let number = cursor.eat_number().ok_or_else(unknown)?;
+
+        if number == 6 {
+            extensions.push(SpelledInterval::MAJOR_6TH);
+        } else {
+            if !matches!(number, 7 | 9 | 11 | 13) {
+                return Err(unknown());
+            }
+
+            seventh = Some(if major_seventh {
+                SpelledInterval::MAJOR_7TH
+            } else if diminished {
+                SpelledInterval::DIMINISHED_7TH
+            } else {
+                SpelledInterval::MINOR_7TH
+            });
+
+            if number >= 9 {
+                extensions.push(SpelledInterval::MAJOR_9TH);
+            }
+            if number == 11 || (number == 13 && third == SpelledInterval::MINOR_3RD) {
+                extensions.push(SpelledInterval::PERFECT_11TH);
+            }
+            if number == 13 {
+                extensions.push(SpelledInterval::MAJOR_13TH);
+            }
+        }
+    } else if major_seventh && (suffix.starts_with('Δ') || suffix.starts_with('^')) {
+        // A single triangle means major 7th
+        seventh = Some(SpelledInterval::MAJOR_7TH);
+    }
+
+    if cursor.eat(&["sus2"]) {
+        if third != SpelledInterval::MAJOR_3RD {
+            return Err(unknown());
+        }
+        third = SpelledInterval::MAJOR_2ND;
+    } else if cursor.eat(&["sus4", "sus"]) {
+        if third != SpelledInterval::MAJOR_3RD {
+            return Err(unknown());
+        }
+        third = SpelledInterval::PERFECT_4TH;
+    }
+
+    let mut intervals = vec![SpelledInterval::UNISON, third, fifth];
+
+    intervals.extend(seventh);
+    intervals.extend(extensions);
+
+    loop {
+        if cursor.eat(&["(", ")", ",", " "]) {
+            continue;
+        }
+
+        if cursor.rest.is_empty() {
+            break;
+        }
+
+        let added = cursor.eat(&["add"]);
+        let offset = if cursor.eat(&["bb", "♭♭"]) {
+            -2
+        } else if cursor.eat(&["b", "♭", "-"]) {
+            -1
+        } else if cursor.eat(&["##", "♯♯"]) {
+            2
+        } else if cursor.eat(&["#", "♯", "+"]) {
+            1
+        } else {
+            0
+        };
+
+        if added && offset != 0 {
+            return Err(unknown());
+        }
+
+        let number = cursor.eat_number().ok_or_else(unknown)?;
+        let natural = SpelledInterval::new(IntervalQuality::Perfect, number)
+            .or_else(|| SpelledInterval::new(IntervalQuality::Major, number))
+            .ok_or_else(unknown)?;
+        let alteration =
+            SpelledInterval::from_steps(natural.steps(), natural.half_notes() + offset)
+                .ok_or_else(unknown)?;
+
+        match intervals.iter_mut().find(|i| i.number == number) {
+            Some(interval) => *interval = alteration,
+            None => intervals.push(alteration),
+        }
+    }
+
+    Ok(intervals)
+}
+
+impl Display for ChordQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+impl Display for Chord {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "{}{}{}",
+            self.root.class, self.root.accidental, self.quality
+        ))?;
+
+        for alteration in &self.alterations {
+            let natural = SpelledInterval::new(IntervalQuality::Perfect, alteration.number)
+                .or_else(|| SpelledInterval::new(IntervalQuality::Major, alteration.number))
+                .unwrap_or(*alteration);
+
+            let prefix = match alteration.half_notes() - natural.half_notes() {
+                -2 => "bb",
+                -1 => "b",
+                1 => "#",
+                2 => "##",
+                _ => "add",
+            };
+
+            f.write_fmt(format_args!("{}{}", prefix, alteration.number))?;
+        }
+
+        if let Some(bass) = self.bass {
+            f.write_fmt(format_args!("/{}{}", bass.class, bass.accidental))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for ChordParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChordParseError::Empty => f.write_str("empty chord symbol"),
+            ChordParseError::InvalidRoot(s) => write!(f, "invalid root in chord symbol {s}"),
+            ChordParseError::InvalidBass(s) => write!(f, "invalid bass in chord symbol {s}"),
+            ChordParseError::UnknownSymbol(s) => write!(f, "unknown chord symbol suffix {s}"),
+            ChordParseError::UnsupportedChord(s) => write!(f, "unsupported chord {s}"),
+        }
+    }
+}
+
+impl std::error::Error for ChordParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scale::scale_from_string;
+
+    fn chord(root: &str, quality: ChordQuality) -> Chord {
+        Chord::new(Pitch::parse(root).unwrap(), quality)
+    }
+
+    #[test]
+    fn test_chord_pitches() {
+        let cases = vec![
+            ("Bb3", ChordQuality::Dominant7, "Bb3 D4 F4 Ab4"),
+            ("C4", ChordQuality::Diminished7, "C4 Eb4 Gb4 B𝄫4"),
+            ("F#3", ChordQuality::HalfDiminished7, "F#3 A3 C4 E4"),
+            ("Ab3", ChordQuality::Augmented, "Ab3 C4 E4"),
+            ("E4", ChordQuality::Minor6, "E4 G4 B4 C#5"),
+            ("D4", ChordQuality::Dominant7Suspended4, "D4 G4 A4 C5"),
+            ("Db4", ChordQuality::MinorMajor7, "Db4 Fb4 Ab4 C5"),
+        ];
+
+        for (root, quality, pitches) in cases {
+            assert_eq!(
+                chord(root, quality).pitches(),
+                scale_from_string(pitches),
+                "{root} {quality:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_chord_with_bass() {
+        let c_over_e = Chord::with_bass(
+            Pitch::parse("C4").unwrap(),
+            ChordQuality::Major,
+            Pitch::parse("E1").unwrap(),
+        );
+
+        assert_eq!(c_over_e.pitches(), scale_from_string("E3 C4 G4"));
+
+        let d_over_c = Chord::with_bass(
+            Pitch::parse("D4").unwrap(),
+            ChordQuality::Major,
+            Pitch::parse("C1").unwrap(),
+        );
+
+        assert_eq!(d_over_c.pitches(), scale_from_string("C4 D4 F#4 A4"));
+    }
+
+    #[test]
+    fn test_chord_tones_beyond_double_accidentals() {
+        let f_flat = Chord::parse("Fbdim7").unwrap();
+
+        assert_eq!(
+            f_flat.pitches(),
+            scale_from_string(&format!(
+                "Fb{0} A𝄫{0} C𝄫{1} C#{1}",
+                SYMBOL_OCTAVE,
+                SYMBOL_OCTAVE + 1
+            ))
+        );
+    }
+
+    #[test]
+    fn test_quality_from_intervals() {
+        for quality in ChordQuality::ALL {
+            assert_eq!(
+                ChordQuality::from_intervals(quality.intervals()),
+                Some(quality)
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_chord_symbols() {
+        let cases = vec![
+            ("C", "C4 E4 G4"),
+            ("Bb7", "Bb4 D5 F5 Ab5"),
+            ("F#m7b5/C", "C4 F#4 A4 E5"),
+            ("Bbmaj9#11", "Bb4 D5 F5 A5 C6 E6"),
+            ("Ebm6/9", "Eb4 Gb4 Bb4 C5 F5"),
+            ("CΔ", "C4 E4 G4 B4"),
+            ("C-7", "C4 Eb4 G4 Bb4"),
+            ("Cø7", "C4 Eb4 Gb4 Bb4"),
+            ("C°7", "C4 Eb4 Gb4 B𝄫4"),
+            ("C+", "C4 E4 G#4"),
+            ("Csus2", "C4 D4 G4"),
+            ("G7sus4", "G4 C5 D5 F5"),
+            ("Cadd9", "C4 E4 G4 D5"),
+            ("Am(maj7)", "A4 C5 E5 G#5"),
+            ("G7(b9,#11)", "G4 B4 D5 F5 Ab5 C#6"),
+            ("Dm13", "D4 F4 A4 C5 E5 G5 B5"),
+            ("A13b9", "A4 C#5 E5 G5 Bb5 F#6"),
+            ("E7#9", "E4 G#4 B4 D5 F𝄪5"),
+        ];
+
+        for (symbol, pitches) in cases {
+            assert_eq!(
+                Chord::parse(symbol).unwrap().pitches(),
+                scale_from_string(pitches),
+                "{symbol}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_chord_symbol_round_trip() {
+        let cases = vec![
+            ("Bbmaj7", "Bbmaj7"),
+            ("F#m7b5/C", "F#m7b5/C"),
+            ("F#ø", "F#m7b5"),
+            ("Cmi7", "Cm7"),
+            ("C7#5", "Caug7"),
+            ("C7b5", "C7b5"),
+            ("Bbmaj9#11", "Bbmaj9#11"),
+            ("C69", "C6/9"),
+            ("Cm(add9)", "Cmadd9"),
+            ("Ebdim7/A", "Ebdim7/A"),
+            ("G13#11", "G13#11"),
+            ("D9sus4", "D7sus4add9"),
+        ];
+
+        for (symbol, canonical) in cases {
+            let chord = Chord::parse(symbol).unwrap();
+
+            assert_eq!(chord.to_string(), canonical, "{symbol}");
+            assert_eq!(Chord::parse(canonical).unwrap(), chord, "{canonical}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_chord_symbols() {
+        assert_eq!(Chord::parse(" "), Err(ChordParseError::Empty));
+        assert_eq!(
+            Chord::parse("H7"),
+            Err(ChordParseError::InvalidRoot("H7".to_string()))
+        );
+        assert_eq!(
+            Chord::parse("C7/X"),
+            Err(ChordParseError::InvalidBass("C7/X".to_string()))
+        );
+        assert_eq!(
+            Chord::parse("Cqrs"),
+            Err(ChordParseError::UnknownSymbol("qrs".to_string()))
+        );
+        assert_eq!(
+            Chord::parse("Cadd99"),
+            Err(ChordParseError::UnknownSymbol("add99".to_string()))
+        );
+    }
+
+    fn identified(pitches: &str) -> Vec<String> {
+        identify(&scale_from_string(pitches))
+            .iter()
+            .map(|m| m.chord.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_identify_chords() {
+        assert_eq!(identified("E3 G#3 B3 D4")[0], "E7");
+        assert_eq!(identified("C3 G3 C4 E4 G4")[0], "C");
+        assert_eq!(identified("E3 G3 C4")[0], "C/E");
+        assert_eq!(identified("F3 B3 D4 Ab4")[0], "Bdim7/F");
+        assert_eq!(identified("Bb2 D3 Ab3")[0], "Bb7");
+        assert!(identified("C3 E3 G3 A3").contains(&"Am7/C".to_string()));
+
+        let ninth = identified("E3 G#3 B3 D4 F#4");
+
+        assert_eq!(ninth[0], "E9");
+        assert!(ninth.contains(&"G#m7b5/E".to_string()));
+    }
+
+    #[test]
+    fn test_identify_inversion() {
+        let matches = identify(&scale_from_string("G3 C4 E4 Bb4"));
+
+        assert_eq!(matches[0].chord.to_string(), "C7/G");
+        assert_eq!(matches[0].inversion, Some(2));
+        assert!(!matches[0].missing_fifth);
+
+        let matches = identify(&scale_from_string("C3 E3 Bb3"));
+
+        assert_eq!(matches[0].chord.to_string(), "C7");
+        assert!(matches[0].missing_fifth);
+        assert!(identify(&[]).is_empty());
+    }
+}
@@ -0,0 +1,167 @@
+//! Exact rational numbers for note lengths.
+use std::{
+    cmp::Ordering,
+    fmt::Display,
+    iter::Sum,
+    ops::{Add, Div, Mul, Sub},
+};
+
+/// Fraction kept in lowest terms with a positive denominator, so equal
+/// values compare equal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Fraction {
+    numerator: i64,
+    denominator: i64,
+}
+
+impl Fraction {
+    pub const ZERO: Fraction = Fraction {
+        numerator: 0,
+        denominator: 1,
+    };
+
+    /// Panics if the denominator is 0.
+    pub fn new(numerator: i64, denominator: i64) -> Self {
+        assert!(denominator != 0, "Fraction with a zero denominator");
+
+        let divisor = gcd(numerator, denominator) * denominator.signum();
+
+        Fraction {
+            numerator: numerator / divisor,
+            denominator: denominator / divisor,
+        }
+    }
+
+    pub fn from_integer(value: i64) -> Self {
+        Fraction::new(value, 1)
+    }
+
+    pub fn numerator(&self) -> i64 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i64 {
+        self.denominator
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.numerator == 0
+    }
+
+    /// Largest integer not greater than the fraction.
+    pub fn floor(&self) -> i64 {
+        self.numerator.div_euclid(self.denominator)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+}
+
+fn gcd(a: i64, b: i64) -> i64 {
+    let (mut a, mut b) = (a.abs(), b.abs());
+
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+
+    a.max(1)
+}
+
+impl Add for Fraction {
+    type Output = Fraction;
+
+    fn add(self, other: Fraction) -> Fraction {
+        Fraction::new(
+            self.numerator * other.denominator + other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+impl Sub for Fraction {
+    type Output = Fraction;
+
+    fn sub(self, other: Fraction) -> Fraction {
+        Fraction::new(
+            self.numerator * other.denominator - other.numerator * self.denominator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+impl Mul for Fraction {
+    type Output = Fraction;
+
+    fn mul(self, other: Fraction) -> Fraction {
+        Fraction::new(
+            self.numerator * other.numerator,
+            self.denominator * other.denominator,
+        )
+    }
+}
+
+/// Panics when dividing by zero.
+impl Div for Fraction {
+    type Output = Fraction;
+
+    fn div(self, other: Fraction) -> Fraction {
+        Fraction::new(
+            self.numerator * other.denominator,
+            self.denominator * other.numerator,
+        )
+    }
+}
+
+impl Sum for Fraction {
+    fn sum<I: Iterator<Item = Fraction>>(iter: I) -> Fraction {
+        iter.fold(Fraction::ZERO, Add::add)
+    }
+}
+
+impl PartialOrd for Fraction {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Fraction {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.numerator * other.denominator).cmp(&(other.numerator * self.denominator))
+    }
+}
+
+impl Display for Fraction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.denominator == 1 {
+            write!(f, "{}", self.numerator)
+        } else {
+            write!(f, "{}/{}", self.numerator, self.denominator)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let third = Fraction::new(1, 3);
+
+        assert_eq!(Fraction::new(6, -8), Fraction::new(-3, 4));
+        assert_eq!(third + Fraction::new(1, 6), Fraction::new(1, 2));
+        assert_eq!(third - Fraction::new(1, 2), Fraction::new(-1, 6));
+        assert_eq!(third * Fraction::new(3, 4), Fraction::new(1, 4));
+        assert_eq!(third / Fraction::new(2, 3), Fraction::new(1, 2));
+        assert!(third < Fraction::new(3, 8));
+        assert_eq!(Fraction::new(7, 2).floor(), 3);
+        assert_eq!(Fraction::new(-1, 2).floor(), -1);
+        assert_eq!(Fraction::new(0, 5), Fraction::ZERO);
+        assert_eq!(Fraction::new(3, 8).to_string(), "3/8");
+        assert_eq!(
+            [third, third, third].into_iter().sum::<Fraction>(),
+            Fraction::from_integer(1)
+        );
+    }
+}
@@ -1,3 +1,5 @@
+use std::fmt::Display;
+
 #[derive(Clone, Copy, Debug)]
 pub enum Interval {
     Unison = 0,
@@ -37,3 +39,335 @@ impl From<u8> for Interval {
         }
     }
 }
+
+/// Quality of a spelled interval. Unisons, fourths, fifths and octaves are
+/// perfect intervals, the rest are major or minor ones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum IntervalQuality {
+    DoublyDiminished,
+    Diminished,
+    Minor,
+    Perfect,
+    Major,
+    Augmented,
+    DoublyAugmented,
+}
+
+/// Interval with a diatonic number and a quality, so an augmented 4th and
+/// a diminished 5th are different intervals even if both are 6 half notes.
+///
+/// The number is 1 for unison, 8 for octave, 9 for ninth and so on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct SpelledInterval {
+    pub quality: IntervalQuality,
+    pub number: u8,
+}
+
+/// Half notes of the perfect or major intervals of a simple interval number.
+const NATURAL_HALF_NOTES: [i8; 7] = [0, 2, 4, 5, 7, 9, 11];
+
+/// Widest interval, ten octaves, so the half notes still fit into `i8`.
+const MAX_NUMBER: u8 = 71;
+
+impl IntervalQuality {
+    /// The opposite quality, used when an interval is inverted.
+    pub fn invert(&self) -> Self {
+        match self {
+            IntervalQuality::DoublyDiminished => IntervalQuality::DoublyAugmented,
+            IntervalQuality::Diminished => IntervalQuality::Augmented,
+            IntervalQuality::Minor => IntervalQuality::Major,
+            IntervalQuality::Perfect => IntervalQuality::Perfect,
+            IntervalQuality::Major => IntervalQuality::Minor,
+            IntervalQuality::Augmented => IntervalQuality::Diminished,
+            IntervalQuality::DoublyAugmented => IntervalQuality::DoublyDiminished,
+        }
+    }
+
+    /// Half note offset from the perfect or major interval.
+    fn offset(&self, perfect: bool) -> i8 {
+        match (self, perfect) {
+            (IntervalQuality::DoublyDiminished, true) => -2,
+            (IntervalQuality::DoublyDiminished, false) => -3,
+            (IntervalQuality::Diminished, true) => -1,
+            (IntervalQuality::Diminished, false) => -2,
+            (IntervalQuality::Minor, _) => -1,
+            (IntervalQuality::Perfect, _) => 0,
+            (IntervalQuality::Major, _) => 0,
+            (IntervalQuality::Augmented, _) => 1,
+            (IntervalQuality::DoublyAugmented, _) => 2,
+        }
+    }
+
+    fn from_offset(offset: i8, perfect: bool) -> Option<Self> {
+        let quality = match (offset, perfect) {
+            (-3, false) => IntervalQuality::DoublyDiminished,
+            (-2, true) => IntervalQuality::DoublyDiminished,
+            (-2, false) => IntervalQuality::Diminished,
+            (-1, true) => IntervalQuality::Diminished,
+            (-1, false) => IntervalQuality::Minor,
+            (0, true) => IntervalQuality::Perfect,
+            (0, false) => IntervalQuality::Major,
+            (1, _) => IntervalQuality::Augmented,
+            (2, _) => IntervalQuality::DoublyAugmented,
+            _ => return None,
+        };
+
+        Some(quality)
+    }
+}
+
+impl SpelledInterval {
+    pub const UNISON: Self = Self::of(IntervalQuality::Perfect, 1);
+    pub const MINOR_2ND: Self = Self::of(IntervalQuality::Minor, 2);
+    pub const MAJOR_2ND: Self = Self::of(IntervalQuality::Major, 2);
+    pub const MINOR_3RD: Self = Self::of(IntervalQuality::Minor, 3);
+    pub const MAJOR_3RD: Self = Self::of(IntervalQuality::Major, 3);
+    pub const PERFECT_4TH: Self = Self::of(IntervalQuality::Perfect, 4);
+    pub const AUGMENTED_4TH: Self = Self::of(IntervalQuality::Augmented, 4);
+    pub const DIMINISHED_5TH: Self = Self::of(IntervalQuality::Diminished, 5);
+    pub const PERFECT_5TH: Self = Self::of(IntervalQuality::Perfect, 5);
+    pub const AUGMENTED_5TH: Self = Self::of(IntervalQuality::Augmented, 5);
+    pub const MINOR_6TH: Self = Self::of(IntervalQuality::Minor, 6);
+    pub const MAJOR_6TH: Self = Self::of(IntervalQuality::Major, 6);
+    pub const DIMINISHED_7TH: Self = Self::of(IntervalQuality::Diminished, 7);
+    pub const MINOR_7TH: Self = Self::of(IntervalQuality::Minor, 7);
+    pub const MAJOR_7TH: Self = Self::of(IntervalQuality::Major, 7);
+    pub const OCTAVE: Self = Self::of(IntervalQuality::Perfect, 8);
+    pub const MINOR_9TH: Self = Self::of(IntervalQuality::Minor, 9);
+    pub const MAJOR_9TH: Self = Self::of(IntervalQuality::Major, 9);
+    pub const AUGMENTED_9TH: Self = Self::of(IntervalQuality::Augmented, 9);
+    pub const PERFECT_11TH: Self = Self::of(IntervalQuality::Perfect, 11);
+    pub const AUGMENTED_11TH: Self = Self::of(IntervalQuality::Augmented, 11);
+    pub const MINOR_13TH: Self = Self::of(IntervalQuality::Minor, 13);
+    pub const MAJOR_13TH: Self = Self::of(IntervalQuality::Major, 13);
+
+    const fn of(quality: IntervalQuality, number: u8) -> Self {
+        SpelledInterval { quality, number }
+    }
+
+    /// Create an interval, returns `None` if the quality doesn't fit the
+    /// number (like a major 5th or a perfect 3rd) or the interval is wider
+    /// than ten octaves.
+    pub fn new(quality: IntervalQuality, number: u8) -> Option<Self> {
+        if number == 0 || number > MAX_NUMBER {
+            return None;
+        }
+
+        let interval = SpelledInterval { quality, number };
+
+        match (quality, interval.is_perfect_kind()) {
+            (IntervalQuality::Perfect, false) => None,
+            (IntervalQuality::Major | IntervalQuality::Minor, true) => None,
+            _ => Some(interval),
+        }
+    }
+
+    /// Find the interval which spans `steps` letter names and `half_notes`
+    /// half notes, like 2 steps and 3 half notes is a minor 3rd.
+    pub fn from_steps(steps: u8, half_notes: i8) -> Option<Self> {
+        if steps >= MAX_NUMBER {
+            return None;
+        }
+
+        let number = steps + 1;
+        let octaves = (steps / 7) as i8;
+        let natural = NATURAL_HALF_NOTES[(steps % 7) as usize] + 12 * octaves;
+        let perfect = matches!(steps % 7, 0 | 3 | 4);
+
+        IntervalQuality::from_offset(half_notes.checked_sub(natural)?, perfect)
+            .map(|quality| SpelledInterval { quality, number })
+    }
+
+    /// Parse interval from short names like `P5`, `m3`, `A4`, `dd7` or `M9`.
+    pub fn parse(s: &str) -> Option<Self> {
+        let digits = s.find(|c: char| c.is_ascii_digit())?;
+        let (quality, number) = s.split_at(digits);
+
+        let quality = match quality {
+            "dd" => IntervalQuality::DoublyDiminished,
+            "d" => IntervalQuality::Diminished,
+            "m" => IntervalQuality::Minor,
+            "P" => IntervalQuality::Perfect,
+            "M" => IntervalQuality::Major,
+            "A" => IntervalQuality::Augmented,
+            "AA" => IntervalQuality::DoublyAugmented,
+            _ => return None,
+        };
+
+        SpelledInterval::new(quality, number.parse().ok()?)
+    }
+
+    /// Chord degree `number` without alteration: the perfect 1st, 4th and
+    /// 5th, the minor 7th and the major others, so the 9th is a major 9th.
+    pub fn unaltered_degree(number: u8) -> Option<Self> {
+        let quality = match number.checked_sub(1)? % 7 + 1 {
+            1 | 4 | 5 => IntervalQuality::Perfect,
+            7 => IntervalQuality::Minor,
+            _ => IntervalQuality::Major,
+        };
+
+        SpelledInterval::new(quality, number)
+    }
+
+    /// Half notes of a chord tone above or below its unaltered degree, so
+    /// a minor 9th is -1 and a major 7th is 1.
+    pub fn alteration(&self) -> i8 {
+        SpelledInterval::unaltered_degree(self.number)
+            .map_or(0, |unaltered| self.half_notes() - unaltered.half_notes())
+    }
+
+    /// Number of letter names the interval moves up, unison is 0 step.
+    pub fn steps(&self) -> u8 {
+        self.number - 1
+    }
+
+    /// Number of whole octaves in a compound interval.
+    pub fn octaves(&self) -> u8 {
+        self.steps() / 7
+    }
+
+    pub fn is_compound(&self) -> bool {
+        self.number > 8
+    }
+
+    /// Reduce a compound interval to the simple one, a major 9th becomes
+    /// a major 2nd. Octave remains octave.
+    pub fn simple(&self) -> Self {
+        if self.number <= 8 {
+            *self
+        } else {
+            SpelledInterval {
+                quality: self.quality,
+                number: self.steps() % 7 + 1,
+            }
+        }
+    }
+
+    pub fn half_notes(&self) -> i8 {
+        let natural = NATURAL_HALF_NOTES[(self.steps() % 7) as usize] + 12 * self.octaves() as i8;
+
+        natural + self.quality.offset(self.is_perfect_kind())
+    }
+
+    /// Invert the interval, a major 3rd becomes a minor 6th. Compound
+    /// intervals are inverted by their simple part.
+    pub fn invert(&self) -> Self {
+        let simple = self.simple();
+
+        SpelledInterval {
+            quality: self.quality.invert(),
+            number: 9 - simple.number,
+        }
+    }
+
+    /// Add the same interval an octave higher, returns `None` if it gets
+    /// wider than ten octaves.
+    pub fn add_octave(&self) -> Option<Self> {
+        SpelledInterval::new(self.quality, self.number.saturating_add(7))
+    }
+
+    fn is_perfect_kind(&self) -> bool {
+        matches!(self.steps() % 7, 0 | 3 | 4)
+    }
+}
+
+impl Display for IntervalQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IntervalQuality::DoublyDiminished => f.write_str("dd"),
+            IntervalQuality::Diminished => f.write_str("d"),
+            IntervalQuality::Minor => f.write_str("m"),
+            IntervalQuality::Perfect => f.write_str("P"),
+            IntervalQuality::Major => f.write_str("M"),
+            IntervalQuality::Augmented => f.write_str("A"),
+            IntervalQuality::DoublyAugmented => f.write_str("AA"),
+        }
+    }
+}
+
+impl Display for SpelledInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!("{}{}", self.quality, self.number))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_half_notes() {
+        let pairs = vec![
+            ("P1", 0),
+            ("A1", 1),
+            ("m2", 1),
+            ("M3", 4),
+            ("A4", 6),
+            ("d5", 6),
+            ("P5", 7),
+            ("d7", 9),
+            ("P8", 12),
+            ("m9", 13),
+            ("A11", 18),
+            ("M13", 21),
+        ];
+
+        for (name, half_notes) in pairs {
+            assert_eq!(
+                SpelledInterval::parse(name).unwrap().half_notes(),
+                half_notes,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_alteration() {
+        let pairs = vec![
+            ("P5", 0),
+            ("d5", -1),
+            ("m7", 0),
+            ("M7", 1),
+            ("m9", -1),
+            ("A11", 1),
+        ];
+
+        for (name, alteration) in pairs {
+            assert_eq!(
+                SpelledInterval::parse(name).unwrap().alteration(),
+                alteration,
+                "{name}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invert() {
+        let pairs = vec![
+            ("M3", "m6"),
+            ("P5", "P4"),
+            ("A4", "d5"),
+            ("P1", "P8"),
+            ("d7", "A2"),
+            ("M9", "m7"),
+        ];
+
+        for (interval, inverted) in pairs {
+            assert_eq!(
+                SpelledInterval::parse(interval).unwrap().invert(),
+                SpelledInterval::parse(inverted).unwrap(),
+                "{interval}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_intervals() {
+        assert_eq!(SpelledInterval::parse("M5"), None);
+        assert_eq!(SpelledInterval::parse("P3"), None);
+        assert_eq!(SpelledInterval::parse("m11"), None);
+        assert_eq!(SpelledInterval::parse("P0"), None);
+        assert_eq!(SpelledInterval::parse("M99"), None);
+        assert_eq!(SpelledInterval::from_steps(98, 0), None);
+    }
+}
@@ -0,0 +1,600 @@
+//! Key signatures and the circle of fifths.
+use std::fmt::Display;
+
+use crate::{
+    beat::Staff,
+    interval::{IntervalQuality, SpelledInterval},
+    note::{Note, PPQ},
+    pitch::{Accidental, Pitch, PitchClass, SpellingPreference},
+    scale::{Scale, ScaleDegree, ScaleType},
+    scales::modes,
+};
+
+/// Order of sharps in key signatures, flats go the other way around.
+const SHARP_ORDER: [PitchClass; 7] = [
+    PitchClass::F,
+    PitchClass::C,
+    PitchClass::G,
+    PitchClass::D,
+    PitchClass::A,
+    PitchClass::E,
+    PitchClass::B,
+];
+
+/// Octave of the tonics and accidentals returned by a key signature.
+const KEY_OCTAVE: i8 = 4;
+
+/// Krumhansl and Kessler probe tone ratings from the tonic up.
+const KRUMHANSL_MAJOR: [f64; 12] = [
+    6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88,
+];
+const KRUMHANSL_MINOR: [f64; 12] = [
+    6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17,
+];
+
+/// Temperley's profiles from the Kostka and Payne corpus.
+const TEMPERLEY_MAJOR: [f64; 12] = [
+    0.748, 0.060, 0.488, 0.082, 0.670, 0.460, 0.096, 0.715, 0.104, 0.366, 0.057, 0.400,
+];
+const TEMPERLEY_MINOR: [f64; 12] = [
+    0.712, 0.084, 0.474, 0.618, 0.049, 0.460, 0.105, 0.747, 0.404, 0.067, 0.133, 0.330,
+];
+
+/// Church modes besides ionian and aeolian, and whether their profile is
+/// made from the minor one.
+const OTHER_MODES: [(&str, [ScaleDegree; 7], bool); 5] = [
+    ("dorian", modes::DORIAN, true),
+    ("phrygian", modes::PHRYGIAN, true),
+    ("lydian", modes::LYDIAN, false),
+    ("mixolydian", modes::MIXOLYDIAN, false),
+    ("locrian", modes::LOCRIAN, true),
+];
+
+/// Key signature with a number of sharps, or flats if it is negative.
+/// Theoretical keys beyond 7 get double sharps or flats, up to 14 where
+/// every letter is doubly altered.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct KeySignature {
+    count: i8,
+}
+
+impl KeySignature {
+    pub const MAX: i8 = 14;
+
+    /// Returns `None` if the key would need triple accidentals.
+    pub fn new(count: i8) -> Option<Self> {
+        (-Self::MAX..=Self::MAX)
+            .contains(&count)
+            .then_some(KeySignature { count })
+    }
+
+    /// Number of sharps, negative for flats.
+    pub fn count(&self) -> i8 {
+        self.count
+    }
+
+    /// Key signature of the major key on `tonic`, so D gives 2 sharps and
+    /// G# gives the theoretical 8 sharps.
+    pub fn major(tonic: &Pitch) -> Option<Self> {
+        let position = SHARP_ORDER.iter().position(|c| *c == tonic.class)? as i8;
+
+        KeySignature::new(position - 1 + 7 * tonic.accidental as i8)
+    }
+
+    /// Key signature of the minor key on `tonic`, which is the one of its
+    /// relative major a minor third above.
+    pub fn minor(tonic: &Pitch) -> Option<Self> {
+        KeySignature::new(KeySignature::major(tonic)?.count - 3)
+    }
+
+    /// Key signature of the scale. The seven note scales whose pitches fit
+    /// a key signature, like the modes, get the one of their parent major
+    /// key, so D dorian has none. Other scales get the one of the major or
+    /// the minor key on the root depending on the third, so A harmonic
+    /// minor has none either.
+    pub fn from_scale(scale: &Scale) -> Option<Self> {
+        let mut letters: Vec<u8> = scale.pitches.iter().map(|p| p.class.index()).collect();
+
+        letters.sort();
+        letters.dedup();
+
+        if scale.pitches.len() == 7 && letters.len() == 7 {
+            let count: i8 = scale.pitches.iter().map(|p| p.accidental as i8).sum();
+
+            if let Some(key) = KeySignature::new(count)
+                && scale
+                    .pitches
+                    .iter()
+                    .all(|p| p.accidental == key.accidental(p.class))
+            {
+                return Some(key);
+            }
+        }
+
+        let root = scale.root_note;
+        let minor_third = SpelledInterval::new(IntervalQuality::Minor, 3)
+            .and_then(|third| root.checked_add_interval(&third));
+        let is_minor = scale.pitches.iter().any(|p| {
+            minor_third
+                .is_some_and(|third| p.class == third.class && p.accidental == third.accidental)
+        });
+
+        if is_minor {
+            KeySignature::minor(&root)
+        } else {
+            KeySignature::major(&root)
+        }
+    }
+
+    /// Accidental of a letter in the key signature.
+    pub fn accidental(&self, class: PitchClass) -> Accidental {
+        let position = SHARP_ORDER.iter().position(|c| *c == class).unwrap_or(0) as i8;
+        let (position, sign) = if self.count < 0 {
+            (6 - position, -1)
+        } else {
+            (position, 1)
+        };
+        let count = self.count.abs();
+        let offset = count / 7 + (position < count % 7) as i8;
+
+        Accidental::from_offset((sign * offset).clamp(-2, 2)).unwrap_or(Accidental::Natural)
+    }
+
+    /// Altered letters in the order they are written, F# C# G# for sharps
+    /// and Bb Eb Ab for flats. The pitches are in the 4th octave.
+    pub fn accidentals(&self) -> Vec<Pitch> {
+        let mut order = SHARP_ORDER.to_vec();
+
+        if self.count < 0 {
+            order.reverse();
+        }
+
+        order
+            .into_iter()
+            .map(|class| Pitch {
+                class,
+                accidental: self.accidental(class),
+                octave: KEY_OCTAVE,
+            })
+            .filter(|p| p.accidental != Accidental::Natural)
+            .collect()
+    }
+
+    /// Tonic of the major key with this key signature, in the 4th octave.
+    pub fn major_tonic(&self) -> Pitch {
+        tonic(self.count)
+    }
+
+    /// Tonic of the relative minor key, in the 4th octave.
+    pub fn minor_tonic(&self) -> Pitch {
+        tonic(self.count + 3)
+    }
+
+    /// Key signature of the parallel minor, the minor key on the tonic of
+    /// the major one: D major with 2 sharps gives D minor with 1 flat.
+    pub fn parallel_minor(&self) -> Option<Self> {
+        KeySignature::new(self.count - 3)
+    }
+
+    /// Key signature of the parallel major, the major key on the tonic of
+    /// the minor one.
+    pub fn parallel_major(&self) -> Option<Self> {
+        KeySignature::new(self.count + 3)
+    }
+
+    /// The twelve keys of the circle of fifths, starting from this one and
+    /// going up a fifth each step. Keys after the first one are spelled
+    /// with at most 6 sharps or 5 flats, so from C it goes up to F# and
+    /// continues from Db.
+    pub fn circle_of_fifths(&self) -> CircleOfFifths {
+        CircleOfFifths {
+            next: Some(*self),
+            remaining: 12,
+        }
+    }
+}
+
+/// Tonic of the major key `fifths` fifths above C.
+fn tonic(fifths: i8) -> Pitch {
+    let class = PitchClass::from_index((fifths as i16 * 4).rem_euclid(7) as u8);
+    let offset = (fifths as i16 * 7 - class as i16).rem_euclid(12);
+    let offset = if offset > 6 { offset - 12 } else { offset };
+
+    Pitch {
+        class,
+        accidental: Accidental::from_offset(offset as i8).unwrap_or(Accidental::Natural),
+        octave: KEY_OCTAVE,
+    }
+}
+
+/// Iterator over the circle of fifths, see `KeySignature::circle_of_fifths`.
+#[derive(Clone, Debug)]
+pub struct CircleOfFifths {
+    next: Option<KeySignature>,
+    remaining: u8,
+}
+
+impl Iterator for CircleOfFifths {
+    type Item = KeySignature;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let current = self.next?;
+        let count = (current.count as i16 + 1 + 5).rem_euclid(12) - 5;
+
+        self.remaining -= 1;
+        self.next = KeySignature::new(count as i8);
+
+        Some(current)
+    }
+}
+
+/// Key profiles correlated with the notes in `detect_key`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyProfile {
+    KrumhanslKessler,
+    Temperley,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyDetectionOptions {
+    pub profile: KeyProfile,
+    /// Rate the other church modes besides major and minor too. Their
+    /// profiles put the weights of the major or the minor degrees on the
+    /// degrees of the mode.
+    pub church_modes: bool,
+}
+
+impl Default for KeyDetectionOptions {
+    fn default() -> Self {
+        KeyDetectionOptions {
+            profile: KeyProfile::KrumhanslKessler,
+            church_modes: false,
+        }
+    }
+}
+
+/// The 24 major and minor keys rated for the staff, the best first. The
+/// rating is the correlation of the Krumhansl-Kessler key profile with
+/// the time each pitch class sounds, from -1 to 1.
+pub fn detect_key(staff: &Staff) -> Vec<(Scale, f64)> {
+    detect_key_with(staff, &KeyDetectionOptions::default())
+}
+
+/// Same as `detect_key` with a choice of the profiles and the modes. The
+/// keys are spelled with the smaller key signature, so F# major wins over
+/// Gb major only on a tie. A staff without notes gives no keys.
+pub fn detect_key_with(staff: &Staff, options: &KeyDetectionOptions) -> Vec<(Scale, f64)> {
+    let mut weights = [0.0; 12];
+
+    for note in staff.measures.iter().flat_map(|measure| &measure.notes) {
+        if let Note::Tone(pitch, duration) = note {
+            weights[pitch.chroma() as usize] += duration.to_ticks(PPQ) as f64;
+        }
+    }
+
+    if weights.iter().all(|w| *w == 0.0) {
+        return vec![];
+    }
+
+    let (major, minor) = match options.profile {
+        KeyProfile::KrumhanslKessler => (KRUMHANSL_MAJOR, KRUMHANSL_MINOR),
+        KeyProfile::Temperley => (TEMPERLEY_MAJOR, TEMPERLEY_MINOR),
+    };
+    let mut profiles = vec![
+        (scale_type("ionian", &modes::IONIAN), major),
+        (scale_type("aeolian", &modes::AEOLIAN), minor),
+    ];
+
+    if options.church_modes {
+        for (name, degrees, from_minor) in OTHER_MODES {
+            let (base, base_degrees) = if from_minor {
+                (&minor, &modes::AEOLIAN)
+            } else {
+                (&major, &modes::IONIAN)
+            };
+
+            profiles.push((
+                scale_type(name, &degrees),
+                mode_profile(base, base_degrees, &degrees),
+            ));
+        }
+    }
+
+    let mut keys = vec![];
+
+    for (scale_type, profile) in &profiles {
+        for tonic in 0..12 {
+            let rotated: Vec<f64> = (0..12).map(|pc| profile[(pc + 12 - tonic) % 12]).collect();
+
+            if let Some(scale) = spelled_scale(tonic as i8, scale_type) {
+                keys.push((scale, correlation(&weights, &rotated)));
+            }
+        }
+    }
+
+    keys.sort_by(|a, b| b.1.total_cmp(&a.1));
+    keys
+}
+
+fn scale_type(name: &str, degrees: &[ScaleDegree]) -> ScaleType {
+    ScaleType {
+        name: name.to_string(),
+        scale_degrees: degrees.to_vec(),
+    }
+}
+
+/// Profile of a mode with the weights of the base scale degrees moved to
+/// the degrees of the mode. The other pitch classes get the average of the
+/// ones out of the base scale.
+fn mode_profile(
+    base: &[f64; 12],
+    base_degrees: &[ScaleDegree],
+    degrees: &[ScaleDegree],
+) -> [f64; 12] {
+    let base_half_notes = Scale::to_numeric_degrees(base_degrees);
+    let chromatic: Vec<f64> = (0..12)
+        .filter(|pc| !base_half_notes.contains(&(*pc as i8)))
+        .map(|pc| base[pc])
+        .collect();
+    let mut profile = [chromatic.iter().sum::<f64>() / chromatic.len() as f64; 12];
+
+    for (to, from) in Scale::to_numeric_degrees(degrees)
+        .iter()
+        .zip(base_half_notes)
+    {
+        profile[*to as usize] = base[from as usize];
+    }
+
+    profile
+}
+
+/// Scale on the pitch class with the tonic spelling giving the smaller key
+/// signature, sharps win a tie.
+fn spelled_scale(chroma: i8, scale_type: &ScaleType) -> Option<Scale> {
+    let natural = Pitch::from_half_notes(
+        KEY_OCTAVE as i16 * 12 + chroma as i16,
+        SpellingPreference::Sharps,
+    );
+
+    natural
+        .enharmonics()
+        .into_iter()
+        .filter(|p| (p.accidental as i8).abs() <= 1)
+        .filter_map(|tonic| Scale::checked_new(&tonic, scale_type))
+        .filter_map(|scale| Some((KeySignature::from_scale(&scale)?.count, scale)))
+        .min_by_key(|(count, _)| (count.abs(), *count < 0))
+        .map(|(_, scale)| scale)
+}
+
+/// Pearson correlation, 0 if either has no variance.
+fn correlation(a: &[f64], b: &[f64]) -> f64 {
+    let mean = |v: &[f64]| v.iter().sum::<f64>() / v.len() as f64;
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let mut covariance = 0.0;
+    let mut variance_a = 0.0;
+    let mut variance_b = 0.0;
+
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a).powi(2);
+        variance_b += (y - mean_b).powi(2);
+    }
+
+    if variance_a == 0.0 || variance_b == 0.0 {
+        0.0
+    } else {
+        covariance / (variance_a * variance_b).sqrt()
+    }
+}
+
+impl Display for KeySignature {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.count {
+            0 => f.write_str("no accidentals"),
+            1 => f.write_str("1 sharp"),
+            -1 => f.write_str("1 flat"),
+            count if count > 0 => write!(f, "{count} sharps"),
+            count => write!(f, "{} flats", -count),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        beat::TimeSignature,
+        note::{Duration, DurationName},
+        scale::scale_from_string,
+        scales::harmonic_minor,
+    };
+
+    fn names(pitches: &[Pitch]) -> Vec<String> {
+        pitches.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn staff(notes: &[(&str, DurationName, u8)]) -> Staff {
+        let notes = notes
+            .iter()
+            .map(|(pitch, name, dots)| {
+                Note::Tone(Pitch::parse(pitch).unwrap(), Duration::dotted(*name, *dots))
+            })
+            .collect();
+
+        Staff::from_notes(TimeSignature::COMMON_TIME, notes)
+    }
+
+    fn key_name(key: &(Scale, f64)) -> String {
+        format!("{} {}", key.0.root_note, key.0.scale_type.name)
+    }
+
+    fn key_of(root: &str, name: &str, degrees: &[ScaleDegree]) -> i8 {
+        let scale = Scale::from_pitch_string(
+            root,
+            &ScaleType {
+                name: name.to_string(),
+                scale_degrees: degrees.to_vec(),
+            },
+        );
+
+        KeySignature::from_scale(&scale).unwrap().count()
+    }
+
+    #[test]
+    fn test_from_scale() {
+        assert_eq!(key_of("C4", "ionian", &modes::IONIAN), 0);
+        assert_eq!(key_of("D4", "dorian", &modes::DORIAN), 0);
+        assert_eq!(key_of("E4", "dorian", &modes::DORIAN), 2);
+        assert_eq!(key_of("F#4", "phrygian", &modes::PHRYGIAN), 2);
+        assert_eq!(key_of("Bb4", "lydian", &modes::LYDIAN), -1);
+        assert_eq!(key_of("G#4", "ionian", &modes::IONIAN), 8);
+        assert_eq!(key_of("C4", "aeolian", &modes::AEOLIAN), -3);
+        assert_eq!(key_of("B4", "locrian", &modes::LOCRIAN), 0);
+        assert_eq!(
+            key_of("A4", "harmonic minor", &harmonic_minor::AEOLIAN_HARMONIC),
+            0
+        );
+        assert_eq!(
+            key_of(
+                "E4",
+                "phrygian dominant",
+                &harmonic_minor::PHRYGIAN_DOMINANT
+            ),
+            4
+        );
+    }
+
+    #[test]
+    fn test_accidentals() {
+        let key = |count| KeySignature::new(count).unwrap();
+
+        assert!(key(0).accidentals().is_empty());
+        assert_eq!(names(&key(3).accidentals()), vec!["F#", "C#", "G#"]);
+        assert_eq!(names(&key(-4).accidentals()), vec!["Bb", "Eb", "Ab", "Db"]);
+        assert_eq!(
+            names(&key(9).accidentals()),
+            vec!["F𝄪", "C𝄪", "G#", "D#", "A#", "E#", "B#"]
+        );
+        assert_eq!(KeySignature::new(15), None);
+    }
+
+    #[test]
+    fn test_relative_and_parallel() {
+        let d_major = KeySignature::major(&Pitch::parse("D4").unwrap()).unwrap();
+
+        assert_eq!(d_major.count(), 2);
+        assert_eq!(d_major.major_tonic(), Pitch::parse("D4").unwrap());
+        assert_eq!(d_major.minor_tonic(), Pitch::parse("B4").unwrap());
+        assert_eq!(d_major.parallel_minor().unwrap().count(), -1);
+
+        let c_minor = KeySignature::minor(&Pitch::parse("C4").unwrap()).unwrap();
+
+        assert_eq!(c_minor.major_tonic().to_string(), "Eb");
+        assert_eq!(c_minor.parallel_major(), KeySignature::new(0));
+        assert_eq!(
+            KeySignature::new(-7).unwrap().minor_tonic().to_string(),
+            "Ab"
+        );
+        assert_eq!(
+            KeySignature::new(12).unwrap().major_tonic().to_string(),
+            "B#"
+        );
+        assert_eq!(c_minor.to_string(), "3 flats");
+    }
+
+    #[test]
+    fn test_circle_of_fifths() {
+        let tonics = |start: i8| -> Vec<String> {
+            KeySignature::new(start)
+                .unwrap()
+                .circle_of_fifths()
+                .map(|key| key.major_tonic().to_string())
+                .collect()
+        };
+
+        assert_eq!(
+            tonics(0),
+            vec![
+                "C", "G", "D", "A", "E", "B", "F#", "Db", "Ab", "Eb", "Bb", "F"
+            ]
+        );
+        assert_eq!(tonics(-6)[..3], ["Gb", "Db", "Ab"]);
+    }
+
+    #[test]
+    fn test_detect_key() {
+        use DurationName::*;
+
+        // Ode to Joy in D major
+        let ode = staff(&[
+            ("F#4", Quarter, 0),
+            ("F#4", Quarter, 0),
+            ("G4", Quarter, 0),
+            ("A4", Quarter, 0),
+            ("A4", Quarter, 0),
+            ("G4", Quarter, 0),
+            ("F#4", Quarter, 0),
+            ("E4", Quarter, 0),
+            ("D4", Quarter, 0),
+            ("D4", Quarter, 0),
+            ("E4", Quarter, 0),
+            ("F#4", Quarter, 0),
+            ("E4", Quarter, 1),
+            ("D4", Eigth, 0),
+            ("D4", Half, 0),
+        ]);
+        let keys = detect_key(&ode);
+
+        assert_eq!(keys.len(), 24);
+        assert_eq!(key_name(&keys[0]), "D ionian");
+        assert!(keys[0].1 > keys[1].1);
+
+        // A minor arpeggio with the leading tone held long
+        let minor = staff(&[
+            ("A3", Half, 0),
+            ("C4", Quarter, 0),
+            ("E4", Quarter, 0),
+            ("G#4", Half, 1),
+            ("A4", Whole, 0),
+        ]);
+        let options = KeyDetectionOptions {
+            profile: KeyProfile::Temperley,
+            church_modes: false,
+        };
+
+        assert_eq!(key_name(&detect_key_with(&minor, &options)[0]), "A aeolian");
+        assert!(detect_key(&Staff::new(vec![])).is_empty());
+    }
+
+    #[test]
+    fn test_detect_church_modes() {
+        let quarter = Duration::new(DurationName::Quarter);
+        let whole = Duration::new(DurationName::Whole);
+        let pitches = scale_from_string("D4 E4 F4 G4 A4 B4 C5 D5 A4 F4 D4");
+        let last = pitches.len() - 1;
+        let notes = pitches
+            .into_iter()
+            .enumerate()
+            .map(|(i, pitch)| {
+                let duration = if i == 0 || i == last { whole } else { quarter };
+
+                Note::Tone(pitch, duration)
+            })
+            .collect();
+        let options = KeyDetectionOptions {
+            church_modes: true,
+            ..Default::default()
+        };
+        let keys = detect_key_with(
+            &Staff::from_notes(TimeSignature::COMMON_TIME, notes),
+            &options,
+        );
+
+        assert_eq!(keys.len(), 84);
+        assert_eq!(key_name(&keys[0]), "D dorian");
+    }
+}
@@ -0,0 +1,14 @@
+pub mod abc;
+pub mod beat;
+pub mod chord;
+pub mod fraction;
+pub mod interval;
+pub mod key;
+pub mod lilypond;
+pub mod midi;
+pub mod musicxml;
+pub mod note;
+pub mod pitch;
+pub mod roman;
+pub mod scale;
+pub mod scales;
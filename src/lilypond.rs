@@ -0,0 +1,689 @@
+//! LilyPond notation of scales, chords and staves.
+use crate::{
+    beat::{Clef, HairpinKind, Measure, NotePosition, Staff, TimeSignature},
+    chord::{Chord, ChordQuality},
+    interval::SpelledInterval,
+    note::{Articulation, Attribute, Duration, Note, PPQ, Tuplet},
+    pitch::{Accidental, Pitch},
+    scale::{Direction, Scale},
+};
+
+const VERSION: &str = "2.24.0";
+
+/// Articulations written after a note.
+const ARTICULATIONS: [(Articulation, &str); 6] = [
+    (Articulation::Staccato, "-."),
+    (Articulation::Staccatissimo, "-!"),
+    (Articulation::Tenuto, "--"),
+    (Articulation::Accent, "->"),
+    (Articulation::Marcato, "-^"),
+    (Articulation::Fermata, "\\fermata"),
+];
+
+/// How the octaves of the notes are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OctaveEntry {
+    /// Every note has its octave marks, c' is the middle C.
+    Absolute,
+    /// Notes are in the octave closest to the previous note, marks show
+    /// the leaps bigger than a fourth.
+    Relative,
+}
+
+/// Writes pitches keeping track of the previous one for relative mode.
+struct PitchWriter {
+    entry: OctaveEntry,
+    previous: Option<Pitch>,
+}
+
+impl PitchWriter {
+    fn new(entry: OctaveEntry) -> Self {
+        PitchWriter {
+            entry,
+            previous: None,
+        }
+    }
+
+    /// Opening of the music expression.
+    fn open(&self) -> &'static str {
+        match self.entry {
+            OctaveEntry::Absolute => "{",
+            OctaveEntry::Relative => "\\relative {",
+        }
+    }
+
+    fn pitch(&mut self, pitch: &Pitch) -> String {
+        let steps = letter_steps(pitch);
+        let octaves = match (self.entry, self.previous) {
+            (OctaveEntry::Relative, Some(previous)) => {
+                // Closest note is at most 3 letter steps away
+                (steps - letter_steps(&previous) + 3).div_euclid(7)
+            }
+            // In relative mode the first note is absolute
+            _ => pitch.octave as i16 - 3,
+        };
+
+        self.previous = Some(*pitch);
+
+        format!("{}{}", pitch_name(pitch), octave_marks(octaves))
+    }
+
+    /// Notes of a chord in angle brackets, the first note of the chord is
+    /// the reference of the next one.
+    fn chord(&mut self, pitches: &[Pitch]) -> String {
+        let names: Vec<String> = pitches.iter().map(|p| self.pitch(p)).collect();
+
+        if let Some(first) = pitches.first() {
+            self.previous = Some(*first);
+        }
+
+        format!("<{}>", names.join(" "))
+    }
+}
+
+fn letter_steps(pitch: &Pitch) -> i16 {
+    pitch.octave as i16 * 7 + pitch.class.index() as i16
+}
+
+fn octave_marks(octaves: i16) -> String {
+    if octaves >= 0 {
+        "'".repeat(octaves as usize)
+    } else {
+        ",".repeat(-octaves as usize)
+    }
+}
+
+/// Dutch note name without octave, like cis, bes or eeses.
+pub fn pitch_name(pitch: &Pitch) -> String {
+    let suffix = match pitch.accidental {
+        Accidental::DoubleFlat => "eses",
+        Accidental::Flat => "es",
+        Accidental::Natural => "",
+        Accidental::Sharp => "is",
+        Accidental::DoubleSharp => "isis",
+    };
+
+    format!("{}{suffix}", pitch.class.to_string().to_lowercase())
+}
+
+impl Duration {
+    /// Like 4 for a quarter or 8.. for a double dotted eighth.
+    pub fn to_lilypond(&self) -> String {
+        format!("{}{}", self.name as u8, ".".repeat(self.dots as usize))
+    }
+}
+
+impl TimeSignature {
+    pub fn to_lilypond(&self) -> String {
+        format!("\\time {}/{}", self.numerator, self.denominator)
+    }
+}
+
+fn clef(pitches: &[Pitch]) -> &'static str {
+    match Clef::for_pitches(pitches) {
+        Clef::Treble => "\\clef treble",
+        Clef::Bass => "\\clef bass",
+    }
+}
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+impl Scale {
+    /// The scale ascending through an octave in quarter notes without
+    /// barlines, with its name above the first note.
+    pub fn to_lilypond(&self, entry: OctaveEntry) -> String {
+        let pitches = self.run(1, Direction::Ascending);
+        let mut writer = PitchWriter::new(entry);
+        let mut notes = vec![];
+
+        for (i, pitch) in pitches.iter().enumerate() {
+            let mut note = format!("{}4", writer.pitch(pitch));
+
+            if i == 0 {
+                note.push_str(&format!(
+                    "^{}",
+                    quote(&format!("{} {}", self.root_note, self.scale_type.name))
+                ));
+            }
+
+            notes.push(note);
+        }
+
+        format!(
+            "{} {} \\cadenzaOn {} \\cadenzaOff \\bar \"|\" }}",
+            writer.open(),
+            clef(&pitches),
+            notes.join(" ")
+        )
+    }
+}
+
+/// Modifiers of the qualities in chord mode.
+fn chord_modifier(quality: ChordQuality) -> &'static str {
+    match quality {
+        ChordQuality::Major => "",
+        ChordQuality::Minor => "m",
+        ChordQuality::Diminished => "dim",
+        ChordQuality::Augmented => "aug",
+        ChordQuality::Suspended2 => "sus2",
+        ChordQuality::Suspended4 => "sus4",
+        ChordQuality::Major6 => "6",
+        ChordQuality::Minor6 => "m6",
+        ChordQuality::Dominant7 => "7",
+        ChordQuality::Major7 => "maj7",
+        ChordQuality::Minor7 => "m7",
+        ChordQuality::MinorMajor7 => "m7+",
+        ChordQuality::HalfDiminished7 => "m7.5-",
+        ChordQuality::Diminished7 => "dim7",
+        ChordQuality::Augmented7 => "aug7",
+        ChordQuality::AugmentedMajor7 => "maj7.5+",
+        ChordQuality::Dominant7Suspended2 => "7sus2",
+        ChordQuality::Dominant7Suspended4 => "7sus4",
+        ChordQuality::SixNine => "6.9",
+        ChordQuality::MinorSixNine => "m6.9",
+        ChordQuality::Dominant9 => "9",
+        ChordQuality::Major9 => "maj9",
+        ChordQuality::Minor9 => "m9",
+        ChordQuality::Dominant11 => "11",
+        ChordQuality::Major11 => "maj11",
+        ChordQuality::Minor11 => "m11",
+        // 13 chords have no 11th in LilyPond
+        ChordQuality::Dominant13 => "13",
+        ChordQuality::Major13 => "maj13",
+        ChordQuality::Minor13 => "m13.11",
+    }
+}
+
+/// Step of an alteration like 9- or 11+, compared to the major (or
+/// perfect) interval and to the minor 7th.
+fn step_modifier(interval: &SpelledInterval) -> String {
+    let alter = interval.alteration();
+    let sign = if alter < 0 { "-" } else { "+" };
+
+    format!(
+        "{}{}",
+        interval.number,
+        sign.repeat(alter.unsigned_abs() as usize)
+    )
+}
+
+impl Chord {
+    /// Chord in chord mode, like bes1:m7.5-/fes for a whole note.
+    pub fn to_lilypond(&self, duration: &Duration) -> String {
+        let mut modifiers = vec![chord_modifier(self.quality).to_string()];
+
+        // A number after the colon stacks thirds up to it, so added tones
+        // of triads need the 5, like c:5.9 (c:9 has a 7th)
+        if !self.alterations.is_empty() && !modifiers[0].contains(char::is_numeric) {
+            modifiers[0].push('5');
+        }
+
+        modifiers.extend(self.alterations.iter().map(step_modifier));
+        modifiers.retain(|m| !m.is_empty());
+
+        let mut result = format!("{}{}", pitch_name(&self.root), duration.to_lilypond());
+
+        if !modifiers.is_empty() {
+            result.push(':');
+            result.push_str(&modifiers.join("."));
+        }
+
+        if let Some(bass) = self.bass {
+            result.push('/');
+            result.push_str(&pitch_name(&bass));
+        }
+
+        result
+    }
+}
+
+/// Chord names in `\chordmode` above the voicings of the chords, each
+/// chord lasting for `duration`.
+pub fn progression_to_lilypond(
+    chords: &[Chord],
+    duration: &Duration,
+    entry: OctaveEntry,
+) -> String {
+    let names: Vec<String> = chords.iter().map(|c| c.to_lilypond(duration)).collect();
+    let mut writer = PitchWriter::new(entry);
+    let voicings: Vec<String> = chords
+        .iter()
+        .map(|c| format!("{}{}", writer.chord(&c.pitches()), duration.to_lilypond()))
+        .collect();
+    let pitches: Vec<Pitch> = chords.iter().flat_map(Chord::pitches).collect();
+
+    format!(
+        "<<\n  \\new ChordNames \\chordmode {{ {} }}\n  \\new Staff {} {} {} }}\n>>",
+        names.join(" "),
+        writer.open(),
+        clef(&pitches),
+        voicings.join(" ")
+    )
+}
+
+impl Staff {
+    /// Notes of the staff with bar checks and time signatures where they
+    /// change. Chord symbols are written in a `ChordNames` context above.
+    pub fn to_lilypond(&self, entry: OctaveEntry) -> String {
+        let mut writer = PitchWriter::new(entry);
+        let pitches: Vec<Pitch> = self
+            .measures
+            .iter()
+            .flat_map(|m| &m.notes)
+            .filter_map(|note| match note {
+                Note::Tone(pitch, _) => Some(*pitch),
+                Note::Rest(_) => None,
+            })
+            .collect();
+        let mut music = format!("{}\n  {}\n", writer.open(), clef(&pitches));
+        let mut previous = None;
+
+        for (i, measure) in self.measures.iter().enumerate() {
+            if previous != Some(measure.time_signature) {
+                music.push_str(&format!("  {}\n", measure.time_signature.to_lilypond()));
+                previous = Some(measure.time_signature);
+            }
+
+            if measure.pickup {
+                music.push_str(&format!("  \\partial 1*{}\n", measure.filled()));
+            }
+
+            let mut notes: Vec<String> = vec![];
+            let mut tuplet = None;
+
+            for (j, note) in measure.notes.iter().enumerate() {
+                // Notes of the same tuplet are grouped in a bracket
+                if note.duration().tuplet != tuplet {
+                    if tuplet.is_some() {
+                        notes.push("}".to_string());
+                    }
+
+                    tuplet = note.duration().tuplet;
+
+                    if let Some(Tuplet { actual, normal }) = tuplet {
+                        notes.push(format!("\\tuplet {actual}/{normal} {{"));
+                    }
+                }
+
+                let note = match note {
+                    Note::Tone(pitch, duration) => {
+                        format!("{}{}", writer.pitch(pitch), duration.to_lilypond())
+                    }
+                    Note::Rest(duration) => format!("r{}", duration.to_lilypond()),
+                };
+
+                notes.push(
+                    note + &self.note_marks(
+                        measure,
+                        NotePosition {
+                            measure: i,
+                            note: j,
+                        },
+                    ),
+                );
+            }
+
+            if tuplet.is_some() {
+                notes.push("}".to_string());
+            }
+
+            music.push_str(&format!("  {} |\n", notes.join(" ")));
+        }
+
+        music.push('}');
+
+        if self.measures.iter().all(|m| m.chord_symbols.is_empty()) {
+            return music;
+        }
+
+        let names: Vec<String> = self.measures.iter().flat_map(chord_names).collect();
+
+        format!(
+            "<<\n\\new ChordNames \\chordmode {{ {} }}\n\\new Staff {}\n>>",
+            names.join(" "),
+            music
+        )
+    }
+
+    /// Tie, articulations, dynamics, hairpins and slurs written after the
+    /// note, in this order. A hairpin ends with \\! unless a dynamic ends
+    /// it.
+    fn note_marks(&self, measure: &Measure, position: NotePosition) -> String {
+        let has = |attribute| measure.has_attribute(position.note, attribute);
+        let mut marks = String::new();
+
+        if has(Attribute::Tie) {
+            marks.push('~');
+        }
+
+        for attribute in measure.note_attributes(position.note) {
+            if let Attribute::Articulation(articulation) = attribute
+                && let Some((_, mark)) = ARTICULATIONS.iter().find(|(a, _)| a == articulation)
+            {
+                marks.push_str(mark);
+            }
+        }
+
+        let mut dynamic = false;
+
+        for attribute in measure.note_attributes(position.note) {
+            if let Attribute::Dynamic(d) = attribute {
+                marks.push_str(&format!("\\{}", d.name()));
+                dynamic = true;
+            }
+        }
+
+        for hairpin in &self.hairpins {
+            if hairpin.end == position && hairpin.start != position && !dynamic {
+                marks.push_str("\\!");
+            }
+        }
+
+        for hairpin in self.hairpins.iter().filter(|h| h.start == position) {
+            marks.push_str(match hairpin.kind {
+                HairpinKind::Crescendo => "\\<",
+                HairpinKind::Diminuendo => "\\>",
+            });
+        }
+
+        if has(Attribute::SlurStop) {
+            marks.push(')');
+        }
+
+        if has(Attribute::SlurStart) {
+            marks.push('(');
+        }
+
+        marks
+    }
+}
+
+/// Chord names of the measure, each lasting until the next one, with
+/// skips where there is no chord.
+fn chord_names(measure: &Measure) -> Vec<String> {
+    let starts: Vec<u32> = measure
+        .notes
+        .iter()
+        .scan(0, |tick, note| {
+            let start = *tick;
+
+            *tick += note.duration().to_ticks(PPQ);
+
+            Some(start)
+        })
+        .collect();
+    let end = measure.time_signature.to_ticks(PPQ);
+    let start_of = |index: usize| starts.get(index).copied().unwrap_or(end);
+    let mut symbols: Vec<_> = measure.chord_symbols.iter().collect();
+    let mut names = vec![];
+    let mut tick = 0;
+
+    symbols.sort_by_key(|s| s.note_index);
+
+    for (i, symbol) in symbols.iter().enumerate() {
+        let start = start_of(symbol.note_index).min(end);
+        let until = symbols
+            .get(i + 1)
+            .map_or(end, |next| start_of(next.note_index).min(end));
+
+        if start > tick {
+            names.extend(skips(start - tick, PPQ));
+        }
+
+        for (j, duration) in Duration::fill(until - start, PPQ).iter().enumerate() {
+            names.push(if j == 0 {
+                symbol.chord.to_lilypond(duration)
+            } else {
+                format!("s{}", duration.to_lilypond())
+            });
+        }
+
+        tick = until.max(tick);
+    }
+
+    if tick < end {
+        names.extend(skips(end - tick, PPQ));
+    }
+
+    names
+}
+
+fn skips(ticks: u32, ppq: u32) -> Vec<String> {
+    Duration::fill(ticks, ppq)
+        .iter()
+        .map(|d| format!("s{}", d.to_lilypond()))
+        .collect()
+}
+
+/// Complete LilyPond file with a title, every music expression in its own
+/// score.
+pub fn document(title: &str, music: &[String]) -> String {
+    let mut document = format!(
+        "\\version \"{VERSION}\"\n\n\\header {{\n  title = {}\n}}\n",
+        quote(title)
+    );
+
+    for expression in music {
+        document.push_str("\n\\score {\n");
+
+        for line in expression.lines() {
+            document.push_str(&format!("  {line}\n"));
+        }
+
+        document.push_str("}\n");
+    }
+
+    document
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        beat::{ChordSymbol, Hairpin, NoteAttribute},
+        fraction::Fraction,
+        note::{DurationName, Dynamic},
+        scale::{ScaleType, scale_from_string},
+        scales::modes,
+    };
+
+    #[test]
+    fn test_pitch_names() {
+        let names: Vec<String> = scale_from_string("C#4 Bb3 C##4 Bbb3 Eb4 E4")
+            .iter()
+            .map(pitch_name)
+            .collect();
+
+        assert_eq!(names, vec!["cis", "bes", "cisis", "beses", "ees", "e"]);
+    }
+
+    #[test]
+    fn test_scale_to_lilypond() {
+        let dorian = ScaleType {
+            name: "dorian".to_string(),
+            scale_degrees: modes::DORIAN.to_vec(),
+        };
+        let scale = Scale::from_pitch_string("E2", &dorian);
+
+        assert_eq!(
+            scale.to_lilypond(OctaveEntry::Absolute),
+            "{ \\clef bass \\cadenzaOn e,4^\"E dorian\" fis,4 g,4 a,4 b,4 cis4 d4 e4 \\cadenzaOff \\bar \"|\" }"
+        );
+        assert_eq!(
+            scale.to_lilypond(OctaveEntry::Relative),
+            "\\relative { \\clef bass \\cadenzaOn e,4^\"E dorian\" fis4 g4 a4 b4 cis4 d4 e4 \\cadenzaOff \\bar \"|\" }"
+        );
+    }
+
+    #[test]
+    fn test_chord_mode() {
+        let whole = Duration::new(DurationName::Whole);
+        let names: Vec<String> = [
+            "C",
+            "Bbm7b5/Fb",
+            "G7b9#11",
+            "Ebmaj9",
+            "F#m6/9",
+            "Cadd9",
+            "Cmadd9",
+        ]
+        .iter()
+        .map(|s| Chord::parse(s).unwrap().to_lilypond(&whole))
+        .collect();
+
+        assert_eq!(
+            names,
+            vec![
+                "c1",
+                "bes1:m7.5-/fes",
+                "g1:7.9-.11+",
+                "ees1:maj9",
+                "fis1:m6.9",
+                "c1:5.9",
+                "c1:m5.9"
+            ]
+        );
+
+        let chords = vec![Chord::parse("C").unwrap(), Chord::parse("G7/B").unwrap()];
+
+        assert_eq!(
+            progression_to_lilypond(
+                &chords,
+                &Duration::new(DurationName::Half),
+                OctaveEntry::Relative
+            ),
+            "<<\n  \\new ChordNames \\chordmode { c2 g2:7/b }\n  \\new Staff \\relative { \\clef treble <c' e g>2 <b g' d' f>2 }\n>>"
+        );
+    }
+
+    #[test]
+    fn test_staff_to_lilypond() {
+        let pitches = scale_from_string("F#4 Bb4 C5");
+        let mut first = Measure::new(
+            TimeSignature::new(3, 4),
+            vec![
+                Note::Tone(pitches[0], Duration::dotted(DurationName::Quarter, 1)),
+                Note::Tone(pitches[1], Duration::new(DurationName::Eigth)),
+                Note::Rest(Duration::new(DurationName::Quarter)),
+            ],
+        );
+
+        first.chord_symbols.push(ChordSymbol {
+            note_index: 1,
+            chord: Chord::parse("Bb").unwrap(),
+        });
+
+        let staff = Staff::new(vec![
+            first,
+            Measure::new(
+                TimeSignature::new(2, 4),
+                vec![Note::Tone(pitches[2], Duration::new(DurationName::Half))],
+            ),
+        ]);
+
+        assert_eq!(
+            staff.to_lilypond(OctaveEntry::Absolute),
+            "<<\n\\new ChordNames \\chordmode { s4. bes4. s2 }\n\\new Staff {\n  \\clef treble\n  \\time 3/4\n  fis'4. bes'8 r4 |\n  \\time 2/4\n  c''2 |\n}\n>>"
+        );
+
+        let document = document(
+            "Worksheet \"1\"",
+            &[staff.to_lilypond(OctaveEntry::Relative)],
+        );
+
+        assert!(document.starts_with("\\version \"2.24.0\""));
+        assert!(document.contains("title = \"Worksheet \\\"1\\\"\""));
+        assert!(document.contains("  fis'4. bes8 r4 |\n"));
+
+        let pickup = Staff::rebar(
+            TimeSignature::new(3, 4),
+            &[Note::Tone(pitches[2], Duration::new(DurationName::Eigth))],
+            Fraction::new(1, 8),
+        )
+        .unwrap();
+
+        assert!(
+            pickup
+                .to_lilypond(OctaveEntry::Absolute)
+                .contains("  \\time 3/4\n  \\partial 1*1/8\n  c''8 |\n")
+        );
+    }
+
+    #[test]
+    fn test_attributes_to_lilypond() {
+        let pitches = scale_from_string("C4 D4");
+        let quarter = Duration::new(DurationName::Quarter);
+        let attribute = |note_index, attribute| NoteAttribute {
+            note_index,
+            attribute,
+        };
+        let mut first = Measure::new(
+            TimeSignature::new(2, 4),
+            vec![
+                Note::Tone(pitches[0], quarter),
+                Note::Tone(pitches[1], quarter),
+            ],
+        );
+        let mut second = Measure::new(
+            TimeSignature::new(2, 4),
+            vec![Note::Tone(pitches[1], quarter), Note::Rest(quarter)],
+        );
+
+        first.attributes = vec![
+            attribute(0, Attribute::SlurStart),
+            attribute(0, Attribute::Dynamic(Dynamic::P)),
+            attribute(0, Attribute::Articulation(Articulation::Staccato)),
+            attribute(1, Attribute::Tie),
+        ];
+        second.attributes = vec![
+            attribute(0, Attribute::SlurStop),
+            attribute(0, Attribute::Articulation(Articulation::Accent)),
+            attribute(1, Attribute::Articulation(Articulation::Fermata)),
+        ];
+
+        let mut staff = Staff::new(vec![first, second]);
+
+        staff.hairpins.push(Hairpin {
+            kind: HairpinKind::Crescendo,
+            start: NotePosition {
+                measure: 0,
+                note: 0,
+            },
+            end: NotePosition {
+                measure: 1,
+                note: 0,
+            },
+        });
+
+        assert_eq!(
+            staff.to_lilypond(OctaveEntry::Absolute),
+            "{\n  \\clef treble\n  \\time 2/4\n  c'4-.\\p\\<( d'4~ |\n  d'4->\\!) r4\\fermata |\n}"
+        );
+    }
+
+    #[test]
+    fn test_tuplets_to_lilypond() {
+        let pitches = scale_from_string("C5 D5 E5 F5 G5 A5 B5 C6");
+        let triplet = Duration::new(DurationName::Eigth).in_tuplet(Tuplet::TRIPLET);
+        let quintuplet = Duration::new(DurationName::Sixteenth).in_tuplet(Tuplet::new(5, 4));
+        let mut notes: Vec<Note> = pitches[..3]
+            .iter()
+            .map(|pitch| Note::Tone(*pitch, triplet))
+            .collect();
+
+        notes.extend(
+            pitches[3..]
+                .iter()
+                .map(|pitch| Note::Tone(*pitch, quintuplet)),
+        );
+
+        let staff = Staff::new(vec![Measure::new(TimeSignature::new(2, 4), notes)]);
+
+        assert_eq!(
+            staff.to_lilypond(OctaveEntry::Absolute),
+            "{\n  \\clef treble\n  \\time 2/4\n  \\tuplet 3/2 { c''8 d''8 e''8 } \\tuplet 5/4 { f''16 g''16 a''16 b''16 c'''16 } |\n}"
+        );
+    }
+}
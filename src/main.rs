@@ -1,37 +1,6 @@
-use std::collections::HashMap;
-
-use crate::scale::{Scale, ScaleType};
-use crate::scales::modes;
-
-pub mod beat;
-pub mod chord;
-pub mod interval;
-pub mod note;
-pub mod pitch;
-pub mod scale;
-pub mod scales;
-
-fn init_modes(scales: &mut HashMap<String, ScaleType>) {
-    let pairs = vec![
-        ("ionian", modes::IONIAN.to_vec()),
-        ("dorian", modes::DORIAN.to_vec()),
-        ("phrygian", modes::PHRYGIAN.to_vec()),
-        ("lydian", modes::LYDIAN.to_vec()),
-        ("mixolydian", modes::MIXOLYDIAN.to_vec()),
-        ("aeolian", modes::AEOLIAN.to_vec()),
-        ("locrian", modes::LOCRIAN.to_vec()),
-    ];
-
-    for (name, degrees) in pairs {
-        scales.insert(
-            name.to_string(),
-            ScaleType {
-                name: name.to_string(),
-                scale_degrees: degrees,
-            },
-        );
-    }
-}
+use scales::pitch::Pitch;
+use scales::scale::Scale;
+use scales::scales::ScaleRegistry;
 
 fn main() {
     let modes = vec![
@@ -43,14 +12,21 @@ fn main() {
         "aeolian",
         "locrian",
     ];
-    let mut scales = HashMap::new();
-
-    init_modes(&mut scales);
+    let registry = ScaleRegistry::new();
+    let root = Pitch::parse("E2").unwrap();
 
     for mode in modes {
-        println!(
-            "{}",
-            Scale::from_pitch_string("E2", scales.get(mode).unwrap())
-        );
+        let scale_type = match registry.lookup(mode) {
+            Ok(scale_type) => scale_type,
+            Err(err) => {
+                eprintln!("{err}");
+                std::process::exit(1);
+            }
+        };
+
+        match Scale::checked_new(&root, scale_type) {
+            Some(scale) => println!("{}", scale),
+            None => eprintln!("{mode} cannot be spelled from {root}"),
+        }
     }
 }
@@ -0,0 +1,414 @@
+//! Building MIDI files from scales, chords and staves.
+use std::fmt::Display;
+
+use crate::{
+    beat::{Staff, TimeSignature},
+    chord::Chord,
+    fraction::Fraction,
+    note::{Attribute, Duration, DurationName, Dynamic, Note},
+    pitch::Pitch,
+    scale::{Direction, Scale},
+};
+
+use super::{DEFAULT_VELOCITY, MidiEventKind, MidiFile, MidiFormat, MidiTrack};
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiExportOptions {
+    pub format: MidiFormat,
+    pub ticks_per_quarter: u16,
+    /// Quarter notes per minute.
+    pub tempo: u32,
+    pub velocity: u8,
+    pub channel: u8,
+}
+
+impl Default for MidiExportOptions {
+    fn default() -> Self {
+        MidiExportOptions {
+            format: MidiFormat::MultiTrack,
+            ticks_per_quarter: 480,
+            tempo: 120,
+            velocity: DEFAULT_VELOCITY,
+            channel: 0,
+        }
+    }
+}
+
+const COMMON_TIME: TimeSignature = TimeSignature {
+    numerator: 4,
+    denominator: 4,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MidiExportError {
+    /// MIDI time signatures only have powers of two as denominators.
+    UnsupportedTimeSignature(TimeSignature),
+}
+
+/// Pitches sounding together from a tick for a number of ticks. Rests
+/// have no pitches.
+struct Sound {
+    tick: u32,
+    length: u32,
+    pitches: Vec<Pitch>,
+    velocity: u8,
+}
+
+impl MidiFile {
+    /// Scale played through `octaves` octaves in the `direction`, the
+    /// durations of the notes are taken from `rhythm` repeatedly.
+    pub fn from_scale(
+        scale: &Scale,
+        octaves: u8,
+        direction: Direction,
+        rhythm: &[Duration],
+        options: &MidiExportOptions,
+    ) -> Self {
+        let quarter = [Duration::new(DurationName::Quarter)];
+        let rhythm = if rhythm.is_empty() { &quarter } else { rhythm };
+        let ppq = options.ticks_per_quarter as u32;
+        let mut position = Fraction::from_integer(0);
+        let mut sounds = vec![];
+
+        for (pitch, duration) in scale
+            .run(octaves, direction)
+            .into_iter()
+            .zip(rhythm.iter().cycle())
+        {
+            let tick = to_tick(position, ppq);
+
+            position = position + duration.as_fraction();
+            sounds.push(Sound {
+                tick,
+                length: to_tick(position, ppq) - tick,
+                pitches: vec![pitch],
+                velocity: options.velocity,
+            });
+        }
+
+        build(options, &[(0, COMMON_TIME)], &sounds)
+    }
+
+    /// Chords played one after the other, each one for `duration`.
+    pub fn from_chords(chords: &[Chord], duration: Duration, options: &MidiExportOptions) -> Self {
+        let ppq = options.ticks_per_quarter as u32;
+        let tick = |i: usize| {
+            to_tick(
+                duration.as_fraction() * Fraction::from_integer(i as i64),
+                ppq,
+            )
+        };
+        let sounds: Vec<Sound> = chords
+            .iter()
+            .enumerate()
+            .map(|(i, chord)| Sound {
+                tick: tick(i),
+                length: tick(i + 1) - tick(i),
+                pitches: chord.pitches(),
+                velocity: options.velocity,
+            })
+            .collect();
+
+        build(options, &[(0, COMMON_TIME)], &sounds)
+    }
+
+    /// Notes of the staff with a time signature event wherever the time
+    /// signature changes. Tied notes are played as one note and the
+    /// dynamics set the velocity, the other attributes and the hairpins
+    /// are left out.
+    pub fn from_staff(staff: &Staff, options: &MidiExportOptions) -> Result<Self, MidiExportError> {
+        let ppq = options.ticks_per_quarter as u32;
+        let mut position = Fraction::from_integer(0);
+        let mut sounds: Vec<Sound> = vec![];
+        let mut time_signatures: Vec<(u32, TimeSignature)> = vec![];
+        let mut velocity = options.velocity;
+        let mut tied = false;
+
+        for measure in &staff.measures {
+            if time_signatures
+                .last()
+                .is_none_or(|(_, ts)| *ts != measure.time_signature)
+            {
+                if !measure.time_signature.denominator.is_power_of_two() {
+                    return Err(MidiExportError::UnsupportedTimeSignature(
+                        measure.time_signature,
+                    ));
+                }
+
+                time_signatures.push((to_tick(position, ppq), measure.time_signature));
+            }
+
+            for (i, note) in measure.notes.iter().enumerate() {
+                let tick = to_tick(position, ppq);
+
+                position = position + note.duration().as_fraction();
+
+                let length = to_tick(position, ppq) - tick;
+                let mut stressed = None;
+
+                for attribute in measure.note_attributes(i) {
+                    match attribute {
+                        Attribute::Dynamic(Dynamic::Sfz) => {
+                            stressed = Some(Dynamic::Sfz.velocity())
+                        }
+                        Attribute::Dynamic(dynamic) => velocity = dynamic.velocity(),
+                        _ => {}
+                    }
+                }
+
+                if let Note::Tone(pitch, _) = note {
+                    match sounds.last_mut() {
+                        Some(last)
+                            if tied
+                                && last.tick + last.length == tick
+                                && last.pitches[0].to_half_notes() == pitch.to_half_notes() =>
+                        {
+                            last.length += length;
+                        }
+                        _ => sounds.push(Sound {
+                            tick,
+                            length,
+                            pitches: vec![*pitch],
+                            velocity: stressed.unwrap_or(velocity),
+                        }),
+                    }
+                }
+
+                tied = measure.has_attribute(i, Attribute::Tie);
+            }
+        }
+
+        Ok(build(options, &time_signatures, &sounds))
+    }
+}
+
+/// Tick of a position in whole notes from the beginning. The notes start
+/// at their exact position rounded down, so tuplets don't drift.
+fn to_tick(position: Fraction, ticks_per_quarter: u32) -> u32 {
+    (position * Fraction::from_integer(4 * ticks_per_quarter as i64)).floor() as u32
+}
+
+/// Put the tempo, the time signatures and the notes into tracks depending
+/// on the format. Pitches out of the MIDI range are left out.
+fn build(
+    options: &MidiExportOptions,
+    time_signatures: &[(u32, TimeSignature)],
+    sounds: &[Sound],
+) -> MidiFile {
+    let mut conductor = MidiTrack::default();
+
+    conductor.push(0, MidiEventKind::Tempo(60_000_000 / options.tempo.max(1)));
+
+    for (tick, ts) in time_signatures {
+        conductor.push(
+            *tick,
+            MidiEventKind::TimeSignature {
+                numerator: ts.numerator,
+                denominator: ts.denominator,
+            },
+        );
+    }
+
+    let mut notes = MidiTrack::default();
+
+    for sound in sounds {
+        for key in sound.pitches.iter().filter_map(Pitch::to_midi) {
+            notes.push(
+                sound.tick,
+                MidiEventKind::NoteOn {
+                    channel: options.channel,
+                    key,
+                    velocity: sound.velocity,
+                },
+            );
+            notes.push(
+                sound.tick + sound.length,
+                MidiEventKind::NoteOff {
+                    channel: options.channel,
+                    key,
+                    velocity: 0,
+                },
+            );
+        }
+    }
+
+    let mut file = MidiFile::new(options.format, options.ticks_per_quarter);
+
+    match options.format {
+        MidiFormat::SingleTrack => {
+            conductor.events.extend(notes.events);
+            file.tracks.push(conductor);
+        }
+        MidiFormat::MultiTrack => {
+            file.tracks.push(conductor);
+            file.tracks.push(notes);
+        }
+    }
+
+    file
+}
+
+impl Display for MidiExportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MidiExportError::UnsupportedTimeSignature(ts) => write!(
+                f,
+                "time signature {}/{} has no MIDI denominator",
+                ts.numerator, ts.denominator
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MidiExportError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        beat::Measure,
+        midi::MidiEvent,
+        note::Tuplet,
+        scale::{ScaleType, scale_from_string},
+        scales::modes,
+    };
+
+    fn note_ons(track: &MidiTrack) -> Vec<(u32, u8)> {
+        track
+            .events
+            .iter()
+            .filter_map(|e| match e.kind {
+                MidiEventKind::NoteOn { key, .. } => Some((e.tick, key)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_scale_to_midi() {
+        let c_major = Scale::from_pitch_string(
+            "C4",
+            &ScaleType {
+                name: "ionian".to_string(),
+                scale_degrees: modes::IONIAN.to_vec(),
+            },
+        );
+        let rhythm = [
+            Duration::dotted(DurationName::Quarter, 1),
+            Duration::new(DurationName::Eigth),
+        ];
+        let options = MidiExportOptions {
+            ticks_per_quarter: 96,
+            ..Default::default()
+        };
+
+        let file = MidiFile::from_scale(&c_major, 1, Direction::Ascending, &rhythm, &options);
+
+        assert_eq!(file.tracks.len(), 2);
+        assert_eq!(
+            note_ons(&file.tracks[1]),
+            vec![
+                (0, 60),
+                (144, 62),
+                (192, 64),
+                (336, 65),
+                (384, 67),
+                (528, 69),
+                (576, 71),
+                (720, 72)
+            ]
+        );
+        assert_eq!(file.tracks[0].events[0].kind, MidiEventKind::Tempo(500_000));
+
+        // Seven 32nds in the time of eight end on the beat
+        let septuplet = [Duration {
+            tuplet: Some(Tuplet::new(7, 8)),
+            ..Duration::new(DurationName::ThirtySecond)
+        }];
+        let file = MidiFile::from_scale(
+            &c_major,
+            1,
+            Direction::Ascending,
+            &septuplet,
+            &MidiExportOptions::default(),
+        );
+        let ticks: Vec<u32> = note_ons(&file.tracks[1]).iter().map(|(t, _)| *t).collect();
+
+        assert_eq!(ticks, vec![0, 68, 137, 205, 274, 342, 411, 480]);
+    }
+
+    #[test]
+    fn test_chords_to_midi() {
+        let chords = vec![Chord::parse("C").unwrap(), Chord::parse("G7/B").unwrap()];
+        let options = MidiExportOptions {
+            format: MidiFormat::SingleTrack,
+            ticks_per_quarter: 100,
+            ..Default::default()
+        };
+
+        let file = MidiFile::from_chords(&chords, Duration::new(DurationName::Half), &options);
+
+        assert_eq!(file.tracks.len(), 1);
+        assert_eq!(
+            note_ons(&file.tracks[0]),
+            vec![
+                (0, 60),
+                (0, 64),
+                (0, 67),
+                (200, 59),
+                (200, 67),
+                (200, 74),
+                (200, 77)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_staff_to_midi() {
+        let quarter = Duration::new(DurationName::Quarter);
+        let pitches = scale_from_string("E4 F#4 G4");
+        let staff = Staff::new(vec![
+            Measure::new(
+                TimeSignature::new(2, 4),
+                vec![Note::Tone(pitches[0], quarter), Note::Rest(quarter)],
+            ),
+            Measure::new(
+                TimeSignature::new(3, 8),
+                vec![
+                    Note::Tone(pitches[1], Duration::new(DurationName::Eigth)),
+                    Note::Tone(pitches[2], quarter),
+                ],
+            ),
+        ]);
+
+        let file = MidiFile::from_staff(&staff, &MidiExportOptions::default()).unwrap();
+        let bytes = file.to_bytes();
+
+        assert_eq!(
+            note_ons(&file.tracks[1]),
+            vec![(0, 64), (960, 66), (1200, 67)]
+        );
+        assert_eq!(
+            file.tracks[0].events[2],
+            MidiEvent {
+                tick: 960,
+                kind: MidiEventKind::TimeSignature {
+                    numerator: 3,
+                    denominator: 8
+                }
+            }
+        );
+        assert_eq!(&bytes[..4], b"MThd");
+
+        let sixths = TimeSignature {
+            numerator: 3,
+            denominator: 6,
+        };
+        let staff = Staff::new(vec![Measure::new(sixths, vec![])]);
+
+        assert_eq!(
+            MidiFile::from_staff(&staff, &MidiExportOptions::default()),
+            Err(MidiExportError::UnsupportedTimeSignature(sixths))
+        );
+    }
+}
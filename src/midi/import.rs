@@ -0,0 +1,549 @@
+//! Reading MIDI files into staves.
+use crate::{
+    beat::{Measure, NoteAttribute, Staff, TimeSignature},
+    key::{self, KeySignature},
+    note::{Attribute, Duration, DurationName, Dynamic, Note},
+    pitch::{Pitch, SpellingPreference},
+};
+
+use super::{DEFAULT_VELOCITY, MidiEventKind, MidiFile};
+
+/// The tenth channel is for percussion, its note numbers are not pitches.
+const PERCUSSION_CHANNEL: u8 = 9;
+
+/// Change of velocity which starts a new dynamic, a step between two
+/// dynamics.
+const DYNAMIC_STEP: u8 = 16;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiImportOptions {
+    /// Shortest note value, the starts and the ends of the notes are
+    /// rounded to it.
+    pub grid: DurationName,
+    /// Spelling of the notes. If it is `None` the key signature of the file
+    /// is used, or the key detected from the notes if there is none.
+    pub spelling: Option<SpellingPreference>,
+}
+
+impl Default for MidiImportOptions {
+    fn default() -> Self {
+        MidiImportOptions {
+            grid: DurationName::Sixteenth,
+            spelling: None,
+        }
+    }
+}
+
+/// Note played from tick `start` until `end`.
+#[derive(Clone, Copy)]
+struct Played {
+    start: u32,
+    end: u32,
+    key: u8,
+    velocity: u8,
+}
+
+impl MidiFile {
+    /// Notes of all the tracks as a staff. Since a staff has one voice,
+    /// from notes starting together the highest one is kept, and a note is
+    /// cut short when the next one starts. Gaps become rests and notes
+    /// crossing a barline are split into two tied notes. Dynamics are
+    /// marked where the velocity changes by a step, a file played at the
+    /// default velocity of the export has none.
+    pub fn to_staff(&self, options: &MidiImportOptions) -> Staff {
+        let ppq = self.ticks_per_quarter as u32;
+        let grid = Duration::new(options.grid).to_ticks(ppq).max(1);
+        let quantize = |tick: u32| tick.saturating_add(grid / 2) / grid * grid;
+        let measure_ticks =
+            |ts: &TimeSignature| ppq * 4 * ts.numerator as u32 / ts.denominator.max(1) as u32;
+
+        let mut time_signatures: Vec<(u32, TimeSignature)> = vec![];
+        let mut key_signature = None;
+        let mut played = vec![];
+
+        for track in &self.tracks {
+            let mut events: Vec<_> = track.events.iter().collect();
+
+            events.sort_by_key(|e| (e.tick, e.kind.order()));
+
+            let mut sounding: Vec<(u8, u8, u32, u8)> = vec![];
+
+            for event in events {
+                match event.kind {
+                    MidiEventKind::NoteOn {
+                        channel,
+                        key,
+                        velocity,
+                    } if channel != PERCUSSION_CHANNEL => {
+                        sounding.push((channel, key, event.tick, velocity));
+                    }
+                    MidiEventKind::NoteOff { channel, key, .. } => {
+                        if let Some(i) = sounding
+                            .iter()
+                            .position(|(c, k, _, _)| *c == channel && *k == key)
+                        {
+                            let (_, _, start, velocity) = sounding.remove(i);
+                            let start = quantize(start);
+
+                            played.push(Played {
+                                start,
+                                end: quantize(event.tick).max(start.saturating_add(grid)),
+                                key,
+                                velocity,
+                            });
+                        }
+                    }
+                    MidiEventKind::TimeSignature {
+                        numerator,
+                        denominator,
+                    } if numerator > 0 => {
+                        time_signatures.push((
+                            quantize(event.tick),
+                            TimeSignature {
+                                numerator,
+                                denominator,
+                            },
+                        ));
+                    }
+                    MidiEventKind::KeySignature { sharps, .. } => {
+                        key_signature.get_or_insert(sharps);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let melody = melody(played);
+        let spelling = options
+            .spelling
+            .or(key_signature.map(SpellingPreference::KeySignature));
+
+        time_signatures.sort_by_key(|(tick, _)| *tick);
+
+        let end = melody.last().map_or(0, |note| note.end);
+        let mut dynamic: Option<Dynamic> = None;
+        let mut measures = vec![];
+        let mut notes = melody.iter().peekable();
+        let mut tick = 0;
+
+        while tick < end {
+            // A time signature shorter than a tick, like 1/255, would never
+            // move to the next measure
+            let time_signature = time_signatures
+                .iter()
+                .rev()
+                .find(|(t, ts)| *t <= tick && measure_ticks(ts) > 0)
+                .map_or(
+                    TimeSignature {
+                        numerator: 4,
+                        denominator: 4,
+                    },
+                    |(_, ts)| *ts,
+                );
+            let length = measure_ticks(&time_signature);
+
+            if length == 0 {
+                // No ticks per quarter note
+                break;
+            }
+
+            let measure_end = time_signatures
+                .iter()
+                .map(|(t, _)| *t)
+                .find(|t| *t > tick)
+                .map_or(tick.saturating_add(length), |change| {
+                    change.min(tick.saturating_add(length))
+                });
+            let mut measure = Measure::new(time_signature, vec![]);
+
+            while let Some(&&note) = notes.peek().filter(|note| note.start < measure_end) {
+                if note.start > tick {
+                    push_notes(&mut measure, None, note.start - tick, ppq, grid);
+                }
+
+                let until = note.end.min(measure_end);
+                let pitch =
+                    Pitch::from_midi(note.key, spelling.unwrap_or(SpellingPreference::Sharps));
+
+                let changed = match dynamic {
+                    // Before the first dynamic the notes play at the default
+                    None => Dynamic::from_velocity(note.velocity).velocity() != DEFAULT_VELOCITY,
+                    Some(d) => d.velocity().abs_diff(note.velocity) >= DYNAMIC_STEP,
+                };
+
+                if note.start >= tick && changed {
+                    let level = Dynamic::from_velocity(note.velocity);
+
+                    measure.attributes.push(NoteAttribute {
+                        note_index: measure.notes.len(),
+                        attribute: Attribute::Dynamic(level),
+                    });
+                    dynamic = Some(level);
+                }
+
+                push_notes(
+                    &mut measure,
+                    Some(pitch),
+                    until - tick.max(note.start),
+                    ppq,
+                    grid,
+                );
+
+                if note.end > measure_end
+                    && let Some(last) = measure.notes.len().checked_sub(1)
+                {
+                    measure.attributes.push(NoteAttribute {
+                        note_index: last,
+                        attribute: Attribute::Tie,
+                    });
+                }
+
+                tick = until;
+
+                if note.end > measure_end {
+                    // The rest of the note goes into the next measure
+                    break;
+                }
+
+                notes.next();
+            }
+
+            if tick < measure_end {
+                push_notes(&mut measure, None, measure_end - tick, ppq, grid);
+            }
+
+            if !measure.notes.is_empty() {
+                measures.push(measure);
+            }
+
+            tick = measure_end;
+        }
+
+        let mut staff = Staff::new(measures);
+
+        if spelling.is_none() {
+            respell_in_detected_key(&mut staff);
+        }
+
+        staff
+    }
+}
+
+/// Spell the notes in the key which fits them best.
+fn respell_in_detected_key(staff: &mut Staff) {
+    let Some(key) = key::detect_key(staff)
+        .first()
+        .and_then(|(scale, _)| KeySignature::from_scale(scale))
+    else {
+        return;
+    };
+
+    for note in staff.measures.iter_mut().flat_map(|m| &mut m.notes) {
+        if let Note::Tone(pitch, _) = note {
+            *pitch = pitch.respell(SpellingPreference::KeySignature(key.count()));
+        }
+    }
+}
+
+/// One voice from the played notes, the highest of the notes starting
+/// together is kept and a note ends when the next one starts.
+fn melody(mut played: Vec<Played>) -> Vec<Played> {
+    played.sort_by_key(|note| (note.start, u8::MAX - note.key));
+    played.dedup_by_key(|note| note.start);
+
+    for i in 1..played.len() {
+        let start = played[i].start;
+
+        played[i - 1].end = played[i - 1].end.min(start);
+    }
+
+    played
+}
+
+/// Fill `ticks` with the longest possible notes (or rests if there is no
+/// pitch), dotted ones included, the notes are tied.
+fn push_notes(measure: &mut Measure, pitch: Option<Pitch>, mut ticks: u32, ppq: u32, grid: u32) {
+    let mut durations = vec![];
+
+    for name in DurationName::ALL {
+        for dots in 0..=2 {
+            let duration = Duration::dotted(name, dots);
+            let shortest = Duration::new(name).to_ticks(ppq) >> dots;
+
+            if shortest >= grid {
+                durations.push((duration.to_ticks(ppq), duration));
+            }
+        }
+    }
+
+    durations.sort_by_key(|(length, _)| u32::MAX - length);
+
+    let mut first = true;
+
+    while let Some((length, duration)) = durations.iter().find(|(length, _)| *length <= ticks) {
+        if !first && pitch.is_some() {
+            measure.attributes.push(NoteAttribute {
+                note_index: measure.notes.len() - 1,
+                attribute: Attribute::Tie,
+            });
+        }
+
+        measure.notes.push(match pitch {
+            Some(pitch) => Note::Tone(pitch, *duration),
+            None => Note::Rest(*duration),
+        });
+
+        ticks -= length;
+        first = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        midi::{MidiExportOptions, MidiFormat, MidiTrack},
+        scale::scale_from_string,
+    };
+
+    fn names(staff: &Staff) -> Vec<Vec<String>> {
+        staff
+            .measures
+            .iter()
+            .map(|measure| {
+                measure
+                    .notes
+                    .iter()
+                    .map(|note| match note {
+                        Note::Tone(pitch, duration) => {
+                            format!(
+                                "{pitch}{}/{}{}",
+                                pitch.octave,
+                                duration.name as u8,
+                                ".".repeat(duration.dots as usize)
+                            )
+                        }
+                        Note::Rest(duration) => {
+                            format!(
+                                "r/{}{}",
+                                duration.name as u8,
+                                ".".repeat(duration.dots as usize)
+                            )
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn note(track: &mut MidiTrack, start: u32, end: u32, key: u8) {
+        track.push(
+            start,
+            MidiEventKind::NoteOn {
+                channel: 0,
+                key,
+                velocity: 80,
+            },
+        );
+        track.push(
+            end,
+            MidiEventKind::NoteOff {
+                channel: 0,
+                key,
+                velocity: 0,
+            },
+        );
+    }
+
+    #[test]
+    fn test_staff_round_trip() {
+        let quarter = Duration::new(DurationName::Quarter);
+        let pitches = scale_from_string("E4 F#4 G4");
+        let staff = Staff::new(vec![
+            Measure::new(
+                TimeSignature::new(2, 4),
+                vec![Note::Tone(pitches[0], quarter), Note::Rest(quarter)],
+            ),
+            Measure::new(
+                TimeSignature::new(3, 8),
+                vec![
+                    Note::Tone(pitches[1], Duration::new(DurationName::Eigth)),
+                    Note::Tone(pitches[2], quarter),
+                ],
+            ),
+        ]);
+
+        let bytes = MidiFile::from_staff(&staff, &MidiExportOptions::default())
+            .unwrap()
+            .to_bytes();
+        let file = MidiFile::parse(&bytes).unwrap();
+
+        assert_eq!(file.to_staff(&MidiImportOptions::default()), staff);
+    }
+
+    #[test]
+    fn test_ties_and_dynamics() {
+        let quarter = Duration::new(DurationName::Quarter);
+        let pitches = scale_from_string("C4 E4");
+        let attribute = |note_index, attribute| NoteAttribute {
+            note_index,
+            attribute,
+        };
+        let mut first = Measure::new(
+            TimeSignature::new(2, 4),
+            vec![Note::Tone(pitches[0], quarter); 2],
+        );
+        let mut second = Measure::new(
+            TimeSignature::new(2, 4),
+            vec![
+                Note::Tone(pitches[0], quarter),
+                Note::Tone(pitches[1], quarter),
+            ],
+        );
+
+        first.attributes = vec![
+            attribute(0, Attribute::Dynamic(Dynamic::P)),
+            attribute(1, Attribute::Tie),
+        ];
+        second.attributes = vec![attribute(1, Attribute::Dynamic(Dynamic::F))];
+
+        let staff = Staff::new(vec![first, second]);
+        let file = MidiFile::from_staff(&staff, &MidiExportOptions::default()).unwrap();
+        let note_ons: Vec<(u32, u8)> = file.tracks[1]
+            .events
+            .iter()
+            .filter_map(|e| match e.kind {
+                MidiEventKind::NoteOn { velocity, .. } => Some((e.tick, velocity)),
+                _ => None,
+            })
+            .collect();
+
+        // The tied notes are played as one
+        assert_eq!(note_ons, vec![(0, 48), (480, 48), (1440, 96)]);
+        assert_eq!(file.to_staff(&MidiImportOptions::default()), staff);
+
+        let mut forte = Measure::new(
+            TimeSignature::new(2, 4),
+            vec![Note::Tone(pitches[0], quarter); 2],
+        );
+
+        forte.attributes = vec![attribute(0, Attribute::Dynamic(Dynamic::F))];
+
+        let staff = Staff::new(vec![forte]);
+        let file = MidiFile::from_staff(&staff, &MidiExportOptions::default()).unwrap();
+
+        assert_eq!(file.to_staff(&MidiImportOptions::default()), staff);
+    }
+
+    #[test]
+    fn test_recording_to_staff() {
+        let mut track = MidiTrack::default();
+
+        // Slightly late and short notes, a chord and a note over the barline
+        note(&mut track, 3, 470, 61);
+        note(&mut track, 962, 1430, 66);
+        note(&mut track, 958, 1420, 70);
+        note(&mut track, 1441, 2160, 68);
+        note(&mut track, 2160, 2400, 63);
+        note(&mut track, 2400, 2880, 73);
+        track.push(
+            0,
+            MidiEventKind::TimeSignature {
+                numerator: 3,
+                denominator: 4,
+            },
+        );
+
+        let file = MidiFile {
+            format: MidiFormat::SingleTrack,
+            ticks_per_quarter: 480,
+            tracks: vec![track],
+        };
+
+        assert_eq!(
+            names(&file.to_staff(&MidiImportOptions::default())),
+            vec![
+                vec!["Db4/4", "r/4", "Bb4/4"],
+                vec!["Ab4/4.", "Eb4/8", "Db5/4"],
+            ]
+        );
+
+        track = file.tracks[0].clone();
+        track.push(
+            0,
+            MidiEventKind::KeySignature {
+                sharps: 6,
+                minor: false,
+            },
+        );
+
+        let file = MidiFile {
+            tracks: vec![track],
+            ..file
+        };
+        let staff = file.to_staff(&MidiImportOptions {
+            grid: DurationName::Eigth,
+            spelling: None,
+        });
+
+        assert_eq!(
+            names(&staff),
+            vec![
+                vec!["C#4/4", "r/4", "A#4/4"],
+                vec!["G#4/4.", "D#4/8", "C#5/4"],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_at_barline() {
+        let mut track = MidiTrack::default();
+
+        note(&mut track, 1440, 2400, 60);
+
+        let file = MidiFile {
+            format: MidiFormat::SingleTrack,
+            ticks_per_quarter: 480,
+            tracks: vec![track],
+        };
+
+        assert_eq!(
+            names(&file.to_staff(&MidiImportOptions::default())),
+            vec![vec!["r/2.", "C4/4"], vec!["C4/4", "r/2."]]
+        );
+    }
+
+    #[test]
+    fn test_time_signature_shorter_than_a_tick() {
+        let mut track = MidiTrack::default();
+
+        track.push(
+            0,
+            MidiEventKind::TimeSignature {
+                numerator: 1,
+                denominator: 8,
+            },
+        );
+        note(&mut track, 0, 4, 60);
+
+        let file = MidiFile {
+            format: MidiFormat::SingleTrack,
+            ticks_per_quarter: 1,
+            tracks: vec![track],
+        };
+
+        assert_eq!(
+            names(&file.to_staff(&MidiImportOptions::default())),
+            vec![vec!["C4/1"]]
+        );
+        assert!(
+            MidiFile {
+                ticks_per_quarter: 0,
+                ..file
+            }
+            .to_staff(&MidiImportOptions::default())
+            .measures
+            .is_empty()
+        );
+    }
+}
@@ -0,0 +1,573 @@
+//! Standard MIDI File (SMF) model and binary serialization.
+use std::{
+    fmt::Display,
+    io::{self, Write},
+};
+
+pub mod export;
+pub mod import;
+
+pub use export::{MidiExportError, MidiExportOptions};
+pub use import::MidiImportOptions;
+
+/// Velocity of the notes without a dynamic, mezzo forte.
+const DEFAULT_VELOCITY: u8 = 80;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MidiFormat {
+    /// Format 0, everything in one track.
+    SingleTrack = 0,
+    /// Format 1, tracks played at the same time.
+    MultiTrack = 1,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MidiEventKind {
+    NoteOn {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    NoteOff {
+        channel: u8,
+        key: u8,
+        velocity: u8,
+    },
+    /// Microseconds per quarter note, written as at most 0xffffff (about
+    /// 4 quarter notes per minute).
+    Tempo(u32),
+    /// Denominator is the note value, like 4 in 3/4.
+    TimeSignature {
+        numerator: u8,
+        denominator: u8,
+    },
+    /// Number of sharps, negative for flats.
+    KeySignature {
+        sharps: i8,
+        minor: bool,
+    },
+    TrackName(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiEvent {
+    /// Time of the event in ticks from the beginning of the track.
+    pub tick: u32,
+    pub kind: MidiEventKind,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MidiTrack {
+    pub events: Vec<MidiEvent>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct MidiFile {
+    pub format: MidiFormat,
+    pub ticks_per_quarter: u16,
+    pub tracks: Vec<MidiTrack>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MidiParseError {
+    /// The file ends in the middle of a chunk or an event.
+    UnexpectedEnd,
+    InvalidHeader,
+    InvalidTrack,
+    /// Only non-zero ticks per quarter note are supported, SMPTE time is
+    /// not.
+    UnsupportedDivision,
+    /// Data byte without a preceding status byte.
+    MissingStatus,
+}
+
+impl MidiEventKind {
+    /// Order of events at the same tick: meta events first, then the note
+    /// offs and the note ons at last.
+    fn order(&self) -> u8 {
+        match self {
+            MidiEventKind::NoteOff { .. } => 1,
+            MidiEventKind::NoteOn { .. } => 2,
+            _ => 0,
+        }
+    }
+
+    fn write_to(&self, bytes: &mut Vec<u8>) {
+        match self {
+            MidiEventKind::NoteOn {
+                channel,
+                key,
+                velocity,
+            } => bytes.extend([0x90 | (channel & 0x0f), *key, *velocity]),
+            MidiEventKind::NoteOff {
+                channel,
+                key,
+                velocity,
+            } => bytes.extend([0x80 | (channel & 0x0f), *key, *velocity]),
+            MidiEventKind::Tempo(micros) => {
+                bytes.extend([0xff, 0x51, 0x03]);
+                bytes.extend(&(*micros).min(0xff_ffff).to_be_bytes()[1..]);
+            }
+            MidiEventKind::TimeSignature {
+                numerator,
+                denominator,
+            } => {
+                let power = denominator.max(&1).trailing_zeros() as u8;
+
+                // 24 MIDI clocks per metronome click, 8 32nds per quarter
+                bytes.extend([0xff, 0x58, 0x04, *numerator, power, 24, 8]);
+            }
+            MidiEventKind::KeySignature { sharps, minor } => {
+                bytes.extend([0xff, 0x59, 0x02, *sharps as u8, *minor as u8]);
+            }
+            MidiEventKind::TrackName(name) => {
+                bytes.extend([0xff, 0x03]);
+                write_variable_length(bytes, name.len() as u32);
+                bytes.extend(name.as_bytes());
+            }
+        }
+    }
+}
+
+impl MidiTrack {
+    pub fn push(&mut self, tick: u32, kind: MidiEventKind) {
+        self.events.push(MidiEvent { tick, kind });
+    }
+
+    /// Track chunk with delta times and the end of track event.
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut events: Vec<&MidiEvent> = self.events.iter().collect();
+
+        events.sort_by_key(|e| (e.tick, e.kind.order()));
+
+        let mut data = vec![];
+        let mut last_tick = 0;
+
+        for event in &events {
+            write_variable_length(&mut data, event.tick - last_tick);
+            event.kind.write_to(&mut data);
+
+            last_tick = event.tick;
+        }
+
+        data.extend([0x00, 0xff, 0x2f, 0x00]);
+
+        let mut bytes = b"MTrk".to_vec();
+
+        bytes.extend((data.len() as u32).to_be_bytes());
+        bytes.extend(data);
+
+        bytes
+    }
+}
+
+impl MidiFile {
+    pub fn new(format: MidiFormat, ticks_per_quarter: u16) -> Self {
+        MidiFile {
+            format,
+            ticks_per_quarter,
+            tracks: vec![],
+        }
+    }
+
+    /// Binary content of the `.mid` file.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = b"MThd".to_vec();
+
+        bytes.extend(6u32.to_be_bytes());
+        bytes.extend((self.format as u16).to_be_bytes());
+        bytes.extend((self.tracks.len() as u16).to_be_bytes());
+        bytes.extend(self.ticks_per_quarter.to_be_bytes());
+
+        for track in &self.tracks {
+            bytes.extend(track.to_bytes());
+        }
+
+        bytes
+    }
+
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+}
+
+impl MidiFile {
+    /// Read the content of a `.mid` file. Events which are not part of
+    /// `MidiEventKind` (controllers, sysex, other meta events) are skipped.
+    pub fn parse(bytes: &[u8]) -> Result<Self, MidiParseError> {
+        let mut reader = ByteReader { bytes, position: 0 };
+
+        if reader.take(4)? != b"MThd" {
+            return Err(MidiParseError::InvalidHeader);
+        }
+
+        let header_length = reader.u32()? as usize;
+
+        if header_length < 6 {
+            return Err(MidiParseError::InvalidHeader);
+        }
+
+        let format = match reader.u16()? {
+            0 => MidiFormat::SingleTrack,
+            // Format 2 (independent sequences) is read as format 1
+            1 | 2 => MidiFormat::MultiTrack,
+            _ => return Err(MidiParseError::InvalidHeader),
+        };
+        let track_count = reader.u16()?;
+        let division = reader.u16()?;
+
+        if division == 0 || division & 0x8000 != 0 {
+            return Err(MidiParseError::UnsupportedDivision);
+        }
+
+        reader.take(header_length - 6)?;
+
+        let mut file = MidiFile::new(format, division);
+
+        while file.tracks.len() < track_count as usize {
+            let id = reader.take(4)?;
+            let length = reader.u32()? as usize;
+            let data = reader.take(length)?;
+
+            // Unknown chunks have to be ignored
+            if id == b"MTrk" {
+                file.tracks.push(parse_track(data)?);
+            }
+        }
+
+        Ok(file)
+    }
+}
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> ByteReader<'a> {
+    fn is_empty(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    fn take(&mut self, count: usize) -> Result<&'a [u8], MidiParseError> {
+        let end = self.position + count;
+        let slice = self
+            .bytes
+            .get(self.position..end)
+            .ok_or(MidiParseError::UnexpectedEnd)?;
+
+        self.position = end;
+
+        Ok(slice)
+    }
+
+    fn u8(&mut self) -> Result<u8, MidiParseError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, MidiParseError> {
+        Ok(u16::from_be_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn u32(&mut self) -> Result<u32, MidiParseError> {
+        Ok(u32::from_be_bytes([
+            self.u8()?,
+            self.u8()?,
+            self.u8()?,
+            self.u8()?,
+        ]))
+    }
+
+    fn variable_length(&mut self) -> Result<u32, MidiParseError> {
+        let mut value = 0u32;
+
+        for _ in 0..4 {
+            let byte = self.u8()?;
+
+            value = (value << 7) | (byte & 0x7f) as u32;
+
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+
+        Err(MidiParseError::InvalidTrack)
+    }
+}
+
+/// Events of a track chunk with absolute ticks, running status is
+/// supported. Note on with zero velocity is read as note off.
+fn parse_track(data: &[u8]) -> Result<MidiTrack, MidiParseError> {
+    let mut reader = ByteReader {
+        bytes: data,
+        position: 0,
+    };
+    let mut track = MidiTrack::default();
+    let mut tick = 0u32;
+    let mut running_status = None;
+
+    while !reader.is_empty() {
+        tick = tick
+            .checked_add(reader.variable_length()?)
+            .ok_or(MidiParseError::InvalidTrack)?;
+
+        let mut status = reader.u8()?;
+
+        if status < 0x80 {
+            // Running status, the byte was the first data byte
+            reader.position -= 1;
+            status = running_status.ok_or(MidiParseError::MissingStatus)?;
+        }
+
+        match status {
+            0xff => {
+                let kind = reader.u8()?;
+                let length = reader.variable_length()? as usize;
+                let data = reader.take(length)?;
+
+                match (kind, data) {
+                    (0x2f, _) => break,
+                    (0x03, name) => track.push(
+                        tick,
+                        MidiEventKind::TrackName(String::from_utf8_lossy(name).into_owned()),
+                    ),
+                    (0x51, [a, b, c]) => track.push(
+                        tick,
+                        MidiEventKind::Tempo(u32::from_be_bytes([0, *a, *b, *c])),
+                    ),
+                    (0x58, [numerator, power, ..]) if *power < 8 => track.push(
+                        tick,
+                        MidiEventKind::TimeSignature {
+                            numerator: *numerator,
+                            denominator: 1 << power,
+                        },
+                    ),
+                    (0x59, [sharps, minor]) => track.push(
+                        tick,
+                        MidiEventKind::KeySignature {
+                            sharps: *sharps as i8,
+                            minor: *minor != 0,
+                        },
+                    ),
+                    _ => {}
+                }
+            }
+            0xf0 | 0xf7 => {
+                let length = reader.variable_length()? as usize;
+
+                reader.take(length)?;
+            }
+            0x80..=0xef => {
+                running_status = Some(status);
+
+                let channel = status & 0x0f;
+
+                match status & 0xf0 {
+                    0x80 | 0x90 => {
+                        let key = reader.u8()?;
+                        let velocity = reader.u8()?;
+
+                        if status & 0xf0 == 0x90 && velocity > 0 {
+                            track.push(
+                                tick,
+                                MidiEventKind::NoteOn {
+                                    channel,
+                                    key,
+                                    velocity,
+                                },
+                            );
+                        } else {
+                            track.push(
+                                tick,
+                                MidiEventKind::NoteOff {
+                                    channel,
+                                    key,
+                                    velocity,
+                                },
+                            );
+                        }
+                    }
+                    // Program change and channel pressure have one data byte
+                    0xc0 | 0xd0 => {
+                        reader.take(1)?;
+                    }
+                    _ => {
+                        reader.take(2)?;
+                    }
+                }
+            }
+            _ => return Err(MidiParseError::InvalidTrack),
+        }
+    }
+
+    Ok(track)
+}
+
+impl Display for MidiParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MidiParseError::UnexpectedEnd => f.write_str("unexpected end of MIDI file"),
+            MidiParseError::InvalidHeader => f.write_str("invalid MIDI file header"),
+            MidiParseError::InvalidTrack => f.write_str("invalid MIDI track"),
+            MidiParseError::UnsupportedDivision => {
+                f.write_str("only non-zero ticks per quarter note are supported")
+            }
+            MidiParseError::MissingStatus => f.write_str("MIDI event without status byte"),
+        }
+    }
+}
+
+impl std::error::Error for MidiParseError {}
+
+/// Write a number with 7 bits per byte, the highest bit shows that more
+/// bytes follow.
+fn write_variable_length(bytes: &mut Vec<u8>, mut value: u32) {
+    let mut buffer = vec![(value & 0x7f) as u8];
+
+    value >>= 7;
+
+    while value > 0 {
+        buffer.push((value & 0x7f) as u8 | 0x80);
+        value >>= 7;
+    }
+
+    bytes.extend(buffer.iter().rev());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_variable_length() {
+        let pairs: Vec<(u32, Vec<u8>)> = vec![
+            (0, vec![0x00]),
+            (0x40, vec![0x40]),
+            (0x7f, vec![0x7f]),
+            (0x80, vec![0x81, 0x00]),
+            (0x2000, vec![0xc0, 0x00]),
+            (0x0fffffff, vec![0xff, 0xff, 0xff, 0x7f]),
+        ];
+
+        for (value, expected) in pairs {
+            let mut bytes = vec![];
+
+            write_variable_length(&mut bytes, value);
+
+            assert_eq!(bytes, expected, "{value:x}");
+        }
+    }
+
+    #[test]
+    fn test_file_bytes() {
+        let mut track = MidiTrack::default();
+
+        track.push(
+            0,
+            MidiEventKind::NoteOn {
+                channel: 0,
+                key: 60,
+                velocity: 64,
+            },
+        );
+        track.push(
+            96,
+            MidiEventKind::NoteOff {
+                channel: 0,
+                key: 60,
+                velocity: 0,
+            },
+        );
+        track.push(0, MidiEventKind::Tempo(500_000));
+
+        let file = MidiFile {
+            format: MidiFormat::SingleTrack,
+            ticks_per_quarter: 96,
+            tracks: vec![track],
+        };
+
+        assert_eq!(
+            file.to_bytes(),
+            vec![
+                b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96, // header
+                b'M', b'T', b'r', b'k', 0, 0, 0, 19, // track header
+                0x00, 0xff, 0x51, 0x03, 0x07, 0xa1, 0x20, // tempo
+                0x00, 0x90, 60, 64, // note on
+                0x60, 0x80, 60, 0, // note off
+                0x00, 0xff, 0x2f, 0x00, // end of track
+            ]
+        );
+
+        let parsed = MidiFile::parse(&file.to_bytes()).unwrap();
+
+        assert_eq!(parsed.tracks[0].events.len(), 3);
+        assert_eq!(parsed.to_bytes(), file.to_bytes());
+
+        let mut slow = MidiTrack::default();
+
+        slow.push(0, MidiEventKind::Tempo(60_000_000));
+
+        assert_eq!(
+            &slow.to_bytes()[8..15],
+            [0x00, 0xff, 0x51, 0x03, 0xff, 0xff, 0xff]
+        );
+    }
+
+    #[test]
+    fn test_parse_running_status() {
+        let bytes = [
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 96, // header
+            b'M', b'T', b'r', b'k', 0, 0, 0, 20, // track header
+            0x00, 0xc0, 0x05, // program change
+            0x00, 0x90, 60, 64, // note on
+            0x00, 64, 64, // note on with running status
+            0x60, 60, 0, // note off as zero velocity note on
+            0x00, 64, 0, // note off
+            0x00, 0xff, 0x2f, 0x00, // end of track
+        ];
+
+        let file = MidiFile::parse(&bytes).unwrap();
+        let events: Vec<(u32, u8)> = file.tracks[0]
+            .events
+            .iter()
+            .map(|e| match e.kind {
+                MidiEventKind::NoteOn { key, .. } => (e.tick, key),
+                MidiEventKind::NoteOff { key, .. } => (e.tick, key + 100),
+                _ => (e.tick, 0),
+            })
+            .collect();
+
+        assert_eq!(events, vec![(0, 60), (0, 64), (96, 160), (96, 164)]);
+        assert_eq!(
+            MidiFile::parse(&bytes[..30]),
+            Err(MidiParseError::UnexpectedEnd)
+        );
+        assert_eq!(MidiFile::parse(b"RIFF"), Err(MidiParseError::InvalidHeader));
+    }
+
+    #[test]
+    fn test_parse_invalid_timing() {
+        let mut bytes = vec![
+            b'M', b'T', b'h', b'd', 0, 0, 0, 6, 0, 0, 0, 1, 0, 0, // header
+            b'M', b'T', b'r', b'k', 0, 0, 0, 4, // track header
+            0x00, 0xff, 0x2f, 0x00, // end of track
+        ];
+
+        assert_eq!(
+            MidiFile::parse(&bytes),
+            Err(MidiParseError::UnsupportedDivision)
+        );
+
+        bytes[13] = 96;
+        bytes.truncate(22);
+
+        // Delta times which add up beyond the last tick
+        for _ in 0..17 {
+            bytes.extend([0xff, 0xff, 0xff, 0x7f, 0xff, 0x01, 0x00]);
+        }
+
+        bytes[21] = 17 * 7;
+
+        assert_eq!(MidiFile::parse(&bytes), Err(MidiParseError::InvalidTrack));
+    }
+}
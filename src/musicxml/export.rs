@@ -0,0 +1,591 @@
+//! Writing staves, scales and chord progressions as MusicXML.
+use crate::{
+    beat::{ChordSymbol, Clef, HairpinKind, Measure, Staff, TimeSignature},
+    chord::Chord,
+    note::{Articulation, Attribute, Duration, DurationName, Dynamic, Note},
+    pitch::Pitch,
+    scale::{Direction, Scale},
+};
+
+use super::{ARTICULATIONS, DIVISIONS, DegreeType, harmony_kind, type_name, xml::XmlWriter};
+
+/// Note or chord (several pitches) or rest (no pitches) of a measure.
+struct XmlNote {
+    pitches: Vec<Pitch>,
+    divisions: u32,
+    duration: Option<Duration>,
+    attributes: Vec<Attribute>,
+    /// The previous note is tied to this one.
+    tied_from_previous: bool,
+}
+
+struct XmlMeasure {
+    time_signature: TimeSignature,
+    /// Text above the first note, like the name of a scale.
+    words: Option<String>,
+    new_system: bool,
+    /// Short first measure, numbered 0.
+    pickup: bool,
+    chord_symbols: Vec<ChordSymbol>,
+    /// Wedges starting before a note, or stopping after it if there is no
+    /// kind.
+    wedges: Vec<(usize, Option<HairpinKind>)>,
+    notes: Vec<XmlNote>,
+}
+
+impl XmlMeasure {
+    /// Notes of the measure with `divisions` per quarter note.
+    fn from_measure(measure: &Measure, divisions: u32) -> Self {
+        XmlMeasure {
+            time_signature: measure.time_signature,
+            words: None,
+            new_system: false,
+            pickup: measure.pickup,
+            chord_symbols: measure.chord_symbols.clone(),
+            wedges: vec![],
+            notes: measure
+                .notes
+                .iter()
+                .enumerate()
+                .map(|(i, note)| XmlNote {
+                    pitches: match note {
+                        Note::Tone(pitch, _) => vec![*pitch],
+                        Note::Rest(_) => vec![],
+                    },
+                    divisions: note.duration().to_ticks(divisions),
+                    duration: Some(*note.duration()),
+                    attributes: measure.note_attributes(i).copied().collect(),
+                    tied_from_previous: false,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Single part score of the staff with its chord symbols, the attributes
+/// of the notes and the hairpins.
+pub fn staff_to_string(staff: &Staff, title: &str) -> String {
+    let divisions = staff_divisions(staff);
+    let mut measures: Vec<XmlMeasure> = staff
+        .measures
+        .iter()
+        .map(|measure| XmlMeasure::from_measure(measure, divisions))
+        .collect();
+    let mut tied = false;
+
+    for note in measures.iter_mut().flat_map(|m| &mut m.notes) {
+        note.tied_from_previous = tied;
+        tied = note.attributes.contains(&Attribute::Tie);
+    }
+
+    for hairpin in &staff.hairpins {
+        if let Some(measure) = measures.get_mut(hairpin.start.measure) {
+            measure
+                .wedges
+                .push((hairpin.start.note, Some(hairpin.kind)));
+        }
+
+        if let Some(measure) = measures.get_mut(hairpin.end.measure) {
+            measure.wedges.push((hairpin.end.note, None));
+        }
+    }
+
+    write_score(title, &measures, divisions)
+}
+
+/// Divisions per quarter note which can also hold the tuplet notes of the
+/// staff, the least common multiple of `DIVISIONS` and the tuplet sizes.
+fn staff_divisions(staff: &Staff) -> u32 {
+    staff
+        .measures
+        .iter()
+        .flat_map(|m| &m.notes)
+        .filter_map(|note| note.duration().tuplet)
+        .map(|tuplet| tuplet.actual.max(1) as u32)
+        .fold(DIVISIONS, |divisions, size| {
+            let (mut a, mut b) = (divisions, size);
+
+            while b != 0 {
+                (a, b) = (b, a % b);
+            }
+
+            divisions / a * size
+        })
+}
+
+/// Every scale played in quarter notes in 4/4 from a new system, with the
+/// name of the scale above.
+pub fn scales_to_string(scales: &[Scale], direction: Direction, title: &str) -> String {
+    let quarter = Duration::new(DurationName::Quarter);
+    let mut measures = vec![];
+
+    for (i, scale) in scales.iter().enumerate() {
+        let notes = scale
+            .run(1, direction)
+            .into_iter()
+            .map(|pitch| Note::Tone(pitch, quarter))
+            .collect();
+        let staff = Staff::from_notes(TimeSignature::COMMON_TIME, notes);
+        let first = measures.len();
+
+        measures.extend(
+            staff
+                .measures
+                .iter()
+                .map(|measure| XmlMeasure::from_measure(measure, DIVISIONS)),
+        );
+
+        if let Some(last) = measures.last_mut() {
+            let filled: u32 = last.notes.iter().map(|note| note.divisions).sum();
+            let length = last.time_signature.to_ticks(DIVISIONS);
+
+            for rest in Duration::fill(length.saturating_sub(filled), DIVISIONS) {
+                last.notes.push(XmlNote {
+                    pitches: vec![],
+                    divisions: rest.to_ticks(DIVISIONS),
+                    duration: Some(rest),
+                    attributes: vec![],
+                    tied_from_previous: false,
+                });
+            }
+        }
+
+        if let Some(measure) = measures.get_mut(first) {
+            measure.words = Some(format!("{} {}", scale.root_note, scale.scale_type.name));
+            measure.new_system = i > 0;
+        }
+    }
+
+    write_score(title, &measures, DIVISIONS)
+}
+
+/// Every chord fills a measure, with its chord symbol above.
+pub fn progression_to_string(
+    chords: &[Chord],
+    time_signature: TimeSignature,
+    title: &str,
+) -> String {
+    let divisions = time_signature.to_ticks(DIVISIONS);
+    let duration = Duration::fill(divisions, DIVISIONS);
+    let measures: Vec<XmlMeasure> = chords
+        .iter()
+        .map(|chord| XmlMeasure {
+            time_signature,
+            words: None,
+            new_system: false,
+            pickup: false,
+            wedges: vec![],
+            chord_symbols: vec![ChordSymbol {
+                note_index: 0,
+                chord: chord.clone(),
+            }],
+            notes: vec![XmlNote {
+                pitches: chord.pitches(),
+                divisions,
+                // Measures like 5/4 have no single note value
+                duration: (duration.len() == 1).then(|| duration[0]),
+                attributes: vec![],
+                tied_from_previous: false,
+            }],
+        })
+        .collect();
+
+    write_score(title, &measures, DIVISIONS)
+}
+
+fn write_score(title: &str, measures: &[XmlMeasure], divisions: u32) -> String {
+    let mut xml = XmlWriter::new();
+
+    xml.declaration(r#"<?xml version="1.0" encoding="UTF-8" standalone="no"?>"#);
+    xml.declaration(
+        r#"<!DOCTYPE score-partwise PUBLIC "-//Recordare//DTD MusicXML 4.0 Partwise//EN" "http://www.musicxml.org/dtds/partwise.dtd">"#,
+    );
+    xml.open_with("score-partwise", &[("version", "4.0")]);
+    xml.open("work");
+    xml.text("work-title", title);
+    xml.close("work");
+    xml.open("part-list");
+    xml.open_with("score-part", &[("id", "P1")]);
+    xml.text("part-name", "Music");
+    xml.close("score-part");
+    xml.close("part-list");
+    xml.open_with("part", &[("id", "P1")]);
+
+    let mut previous: Option<TimeSignature> = None;
+    let first = if measures.first().is_some_and(|m| m.pickup) {
+        0
+    } else {
+        1
+    };
+
+    for (i, measure) in measures.iter().enumerate() {
+        let number = (i + first).to_string();
+
+        if measure.pickup {
+            xml.open_with("measure", &[("number", &number), ("implicit", "yes")]);
+        } else {
+            xml.open_with("measure", &[("number", &number)]);
+        }
+
+        if measure.new_system {
+            xml.empty_with("print", &[("new-system", "yes")]);
+        }
+
+        if previous != Some(measure.time_signature) {
+            xml.open("attributes");
+
+            if previous.is_none() {
+                xml.text("divisions", &divisions.to_string());
+            }
+
+            xml.open("time");
+            xml.text("beats", &measure.time_signature.numerator.to_string());
+            xml.text("beat-type", &measure.time_signature.denominator.to_string());
+            xml.close("time");
+
+            if previous.is_none() {
+                write_clef(&mut xml, measures);
+            }
+
+            xml.close("attributes");
+            previous = Some(measure.time_signature);
+        }
+
+        if let Some(words) = &measure.words {
+            xml.open_with("direction", &[("placement", "above")]);
+            xml.open("direction-type");
+            xml.text("words", words);
+            xml.close("direction-type");
+            xml.close("direction");
+        }
+
+        for (j, note) in measure.notes.iter().enumerate() {
+            for symbol in measure.chord_symbols.iter().filter(|s| s.note_index == j) {
+                write_harmony(&mut xml, &symbol.chord);
+            }
+
+            for attribute in &note.attributes {
+                if let Attribute::Dynamic(dynamic) = attribute {
+                    write_dynamic(&mut xml, *dynamic);
+                }
+            }
+
+            for (_, kind) in measure.wedges.iter().filter(|(k, _)| *k == j) {
+                match kind {
+                    Some(HairpinKind::Crescendo) => write_wedge(&mut xml, "crescendo"),
+                    Some(HairpinKind::Diminuendo) => write_wedge(&mut xml, "diminuendo"),
+                    None => {}
+                }
+            }
+
+            write_note(&mut xml, note);
+
+            for (_, kind) in measure.wedges.iter().filter(|(k, _)| *k == j) {
+                if kind.is_none() {
+                    write_wedge(&mut xml, "stop");
+                }
+            }
+        }
+
+        for symbol in &measure.chord_symbols {
+            if symbol.note_index >= measure.notes.len() {
+                write_harmony(&mut xml, &symbol.chord);
+            }
+        }
+
+        xml.close("measure");
+    }
+
+    xml.close("part");
+    xml.close("score-partwise");
+
+    xml.finish()
+}
+
+fn write_clef(xml: &mut XmlWriter, measures: &[XmlMeasure]) {
+    let pitches: Vec<Pitch> = measures
+        .iter()
+        .flat_map(|m| &m.notes)
+        .flat_map(|n| n.pitches.iter().copied())
+        .collect();
+    let (sign, line) = match Clef::for_pitches(&pitches) {
+        Clef::Treble => ("G", "2"),
+        Clef::Bass => ("F", "4"),
+    };
+
+    xml.open("clef");
+    xml.text("sign", sign);
+    xml.text("line", line);
+    xml.close("clef");
+}
+
+fn write_note(xml: &mut XmlWriter, note: &XmlNote) {
+    let rest = [None];
+    let pitches: Vec<Option<&Pitch>> = if note.pitches.is_empty() {
+        rest.to_vec()
+    } else {
+        note.pitches.iter().map(Some).collect()
+    };
+
+    for (i, pitch) in pitches.into_iter().enumerate() {
+        xml.open("note");
+
+        if i > 0 {
+            xml.empty("chord");
+        }
+
+        match pitch {
+            Some(pitch) => {
+                xml.open("pitch");
+                xml.text("step", &pitch.class.to_string());
+
+                if pitch.accidental as i8 != 0 {
+                    xml.text("alter", &(pitch.accidental as i8).to_string());
+                }
+
+                xml.text("octave", &pitch.octave.to_string());
+                xml.close("pitch");
+            }
+            None => xml.empty("rest"),
+        }
+
+        xml.text("duration", &note.divisions.to_string());
+
+        if note.tied_from_previous {
+            xml.empty_with("tie", &[("type", "stop")]);
+        }
+
+        if note.attributes.contains(&Attribute::Tie) {
+            xml.empty_with("tie", &[("type", "start")]);
+        }
+
+        if let Some(duration) = note.duration {
+            xml.text("type", type_name(duration.name));
+
+            for _ in 0..duration.dots {
+                xml.empty("dot");
+            }
+
+            if let Some(tuplet) = duration.tuplet {
+                xml.open("time-modification");
+                xml.text("actual-notes", &tuplet.actual.to_string());
+                xml.text("normal-notes", &tuplet.normal.to_string());
+                xml.close("time-modification");
+            }
+        }
+
+        if i == 0 {
+            write_notations(xml, note);
+        }
+
+        xml.close("note");
+    }
+}
+
+/// Ties, slurs and articulations of the note, if it has any.
+fn write_notations(xml: &mut XmlWriter, note: &XmlNote) {
+    let has = |attribute| note.attributes.contains(&attribute);
+    let articulations: Vec<&str> = ARTICULATIONS
+        .iter()
+        .filter(|(a, _)| *a != Articulation::Fermata && has(Attribute::Articulation(*a)))
+        .map(|(_, element)| *element)
+        .collect();
+    let fermata = has(Attribute::Articulation(Articulation::Fermata));
+
+    if !note.tied_from_previous
+        && !has(Attribute::Tie)
+        && !has(Attribute::SlurStart)
+        && !has(Attribute::SlurStop)
+        && articulations.is_empty()
+        && !fermata
+    {
+        return;
+    }
+
+    xml.open("notations");
+
+    if note.tied_from_previous {
+        xml.empty_with("tied", &[("type", "stop")]);
+    }
+
+    if has(Attribute::Tie) {
+        xml.empty_with("tied", &[("type", "start")]);
+    }
+
+    if has(Attribute::SlurStop) {
+        xml.empty_with("slur", &[("type", "stop"), ("number", "1")]);
+    }
+
+    if has(Attribute::SlurStart) {
+        xml.empty_with("slur", &[("type", "start"), ("number", "1")]);
+    }
+
+    if !articulations.is_empty() {
+        xml.open("articulations");
+
+        for element in articulations {
+            xml.empty(element);
+        }
+
+        xml.close("articulations");
+    }
+
+    if fermata {
+        xml.empty("fermata");
+    }
+
+    xml.close("notations");
+}
+
+fn write_dynamic(xml: &mut XmlWriter, dynamic: Dynamic) {
+    xml.open_with("direction", &[("placement", "below")]);
+    xml.open("direction-type");
+    xml.open("dynamics");
+    xml.empty(dynamic.name());
+    xml.close("dynamics");
+    xml.close("direction-type");
+    xml.close("direction");
+}
+
+/// Wedge of the type crescendo, diminuendo or stop.
+fn write_wedge(xml: &mut XmlWriter, wedge_type: &str) {
+    xml.open_with("direction", &[("placement", "below")]);
+    xml.open("direction-type");
+    xml.empty_with("wedge", &[("type", wedge_type)]);
+    xml.close("direction-type");
+    xml.close("direction");
+}
+
+fn write_harmony(xml: &mut XmlWriter, chord: &Chord) {
+    let (kind, degrees) = harmony_kind(chord);
+
+    xml.open("harmony");
+    xml.open("root");
+    xml.text("root-step", &chord.root.class.to_string());
+
+    if chord.root.accidental as i8 != 0 {
+        xml.text("root-alter", &(chord.root.accidental as i8).to_string());
+    }
+
+    xml.close("root");
+    xml.text_with("kind", &[("text", chord.quality.symbol())], kind);
+
+    if let Some(bass) = chord.bass {
+        xml.open("bass");
+        xml.text("bass-step", &bass.class.to_string());
+
+        if bass.accidental as i8 != 0 {
+            xml.text("bass-alter", &(bass.accidental as i8).to_string());
+        }
+
+        xml.close("bass");
+    }
+
+    for degree in degrees {
+        xml.open("degree");
+        xml.text("degree-value", &degree.value.to_string());
+        xml.text("degree-alter", &degree.alter.to_string());
+        xml.text(
+            "degree-type",
+            match degree.degree_type {
+                DegreeType::Add => "add",
+                DegreeType::Alter => "alter",
+                DegreeType::Subtract => "subtract",
+            },
+        );
+        xml.close("degree");
+    }
+
+    xml.close("harmony");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scale::{ScaleType, scale_from_string};
+    use crate::scales::modes;
+
+    #[test]
+    fn test_staff_to_musicxml() {
+        let pitches = scale_from_string("F#4 Bb4");
+        let mut measure = Measure::new(
+            TimeSignature::new(3, 4),
+            vec![
+                Note::Tone(pitches[0], Duration::dotted(DurationName::Quarter, 1)),
+                Note::Tone(pitches[1], Duration::new(DurationName::Eigth)),
+                Note::Rest(Duration::new(DurationName::Quarter)),
+            ],
+        );
+
+        measure.chord_symbols.push(ChordSymbol {
+            note_index: 1,
+            chord: Chord::parse("Bbm7/F").unwrap(),
+        });
+
+        let xml = staff_to_string(&Staff::new(vec![measure]), "Exercise <1>");
+        let xml: String = xml.lines().map(str::trim).collect();
+
+        assert!(xml.contains("<work-title>Exercise &lt;1&gt;</work-title>"));
+        assert!(xml.contains("<beats>3</beats>"));
+        assert!(xml.contains("<sign>G</sign>"));
+        assert!(xml.contains("<step>F</step><alter>1</alter><octave>4</octave>"));
+        assert!(xml.contains("<duration>96</duration><type>quarter</type><dot/>"));
+        assert!(xml.contains("<rest/>"));
+        assert!(xml.contains("<root-step>B</root-step><root-alter>-1</root-alter>"));
+        assert!(xml.contains(r#"<kind text="m7">minor-seventh</kind>"#));
+        assert!(xml.contains("<bass-step>F</bass-step>"));
+
+        // The harmony is written before the note it belongs to
+        let harmony = xml.find("<harmony>").unwrap();
+        let b_flat = xml.find("<step>B</step>").unwrap();
+        let f_sharp = xml.find("<step>F</step>").unwrap();
+
+        assert!(f_sharp < harmony && harmony < b_flat);
+    }
+
+    #[test]
+    fn test_clef_of_long_staff() {
+        let c8 = Pitch::parse("C8").unwrap();
+        let measure = Measure::new(
+            TimeSignature::COMMON_TIME,
+            vec![Note::Tone(c8, Duration::new(DurationName::Quarter)); 4],
+        );
+        let xml = staff_to_string(&Staff::new(vec![measure; 200]), "High");
+
+        assert!(xml.contains("<sign>G</sign>"));
+    }
+
+    #[test]
+    fn test_scales_to_musicxml() {
+        let dorian = ScaleType {
+            name: "dorian".to_string(),
+            scale_degrees: modes::DORIAN.to_vec(),
+        };
+        let scales = vec![
+            Scale::from_pitch_string("E2", &dorian),
+            Scale::from_pitch_string("F#2", &dorian),
+        ];
+
+        let xml = scales_to_string(&scales, Direction::AscendingDescending, "Modes");
+
+        assert_eq!(xml.matches("<measure ").count(), 8);
+        assert_eq!(xml.matches("<rest/>").count(), 2);
+        assert_eq!(xml.matches(r#"<print new-system="yes"/>"#).count(), 1);
+        assert!(xml.contains("<words>E dorian</words>"));
+        assert!(xml.contains("<words>F# dorian</words>"));
+        assert!(xml.contains("<sign>F</sign>"));
+    }
+
+    #[test]
+    fn test_progression_to_musicxml() {
+        let chords = vec![Chord::parse("Dm7").unwrap(), Chord::parse("G7b9").unwrap()];
+
+        let xml = progression_to_string(&chords, TimeSignature::COMMON_TIME, "ii-V");
+
+        assert_eq!(xml.matches("<harmony>").count(), 2);
+        assert_eq!(xml.matches("<chord/>").count(), 3 + 4);
+        assert_eq!(xml.matches("<type>whole</type>").count(), 9);
+        assert!(xml.contains("<degree-value>9</degree-value>"));
+        assert!(xml.contains("<degree-alter>-1</degree-alter>"));
+    }
+}
@@ -0,0 +1,875 @@
+//! Reading partwise MusicXML into staves.
+use std::fmt::Display;
+
+use crate::{
+    beat::{
+        ChordSymbol, Hairpin, HairpinKind, Measure, NoteAttribute, NotePosition, Staff,
+        TimeSignature,
+    },
+    chord::{Chord, SYMBOL_OCTAVE},
+    interval::SpelledInterval,
+    note::{Articulation, Attribute, Duration, DurationName, Dynamic, Note, Tuplet},
+    pitch::{Accidental, Pitch, PitchClass},
+};
+
+use super::{
+    ARTICULATIONS, DIVISIONS, DegreeType, HARMONY_KINDS, type_name,
+    xml::{self, Element, XmlError},
+};
+
+/// Longest `<duration>` which is read, 64 whole notes in `DIVISIONS`.
+const MAX_TICKS: u32 = DIVISIONS * 4 * 64;
+
+/// Most dots read on a note, the ones after them are ignored.
+const MAX_DOTS: usize = 4;
+
+/// The staff of the first part and what could not be represented in it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Import {
+    pub staff: Staff,
+    pub unsupported: Vec<Unsupported>,
+}
+
+/// Notation left out of the staff, measures are indices in the staff.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Unsupported {
+    /// Only the highest note of chords is kept.
+    Chord {
+        measure: usize,
+    },
+    /// Only the first voice of the part is kept.
+    Voice {
+        measure: usize,
+        voice: String,
+    },
+    GraceNote {
+        measure: usize,
+    },
+    /// Note without a type which is shorter than a 64th.
+    ShortNote {
+        measure: usize,
+    },
+    /// Harmony which is not a chord of the crate, like a power chord.
+    Harmony {
+        measure: usize,
+        kind: String,
+    },
+    /// Only the first part is read.
+    Part(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MusicXmlError {
+    Xml(XmlError),
+    /// The root element is not `<score-partwise>`, timewise scores are
+    /// not supported.
+    NotPartwise(String),
+    NoPart,
+    InvalidPitch {
+        measure: usize,
+    },
+    InvalidDuration {
+        measure: usize,
+    },
+    InvalidTimeSignature {
+        measure: usize,
+    },
+}
+
+/// Read the first part of a partwise MusicXML document.
+pub fn parse(document: &str) -> Result<Import, MusicXmlError> {
+    let root = xml::parse(document).map_err(MusicXmlError::Xml)?;
+
+    if root.name != "score-partwise" {
+        return Err(MusicXmlError::NotPartwise(root.name));
+    }
+
+    let mut parts = root.elements().filter(|e| e.name == "part");
+    let part = parts.next().ok_or(MusicXmlError::NoPart)?;
+    let mut reader = PartReader {
+        divisions: 1,
+        time_signature: TimeSignature::COMMON_TIME,
+        voice: None,
+        measures: vec![],
+        directions: vec![],
+        wedge: None,
+        hairpins: vec![],
+        unsupported: vec![],
+    };
+
+    for measure in part.elements().filter(|e| e.name == "measure") {
+        reader.measure(measure)?;
+    }
+
+    for other in parts {
+        let id = other.attribute("id").unwrap_or_default().to_string();
+
+        reader.unsupported.push(Unsupported::Part(id));
+    }
+
+    Ok(Import {
+        staff: Staff {
+            measures: reader.measures,
+            hairpins: reader.hairpins,
+        },
+        unsupported: reader.unsupported,
+    })
+}
+
+struct PartReader {
+    /// Divisions of a quarter note.
+    divisions: u32,
+    time_signature: TimeSignature,
+    /// The voice which is read, the first one in the part.
+    voice: Option<String>,
+    measures: Vec<Measure>,
+    /// Dynamics of the directions before the next note.
+    directions: Vec<Attribute>,
+    /// Wedge which starts at the next note, or which started at a note.
+    wedge: Option<(HairpinKind, Option<NotePosition>)>,
+    hairpins: Vec<Hairpin>,
+    unsupported: Vec<Unsupported>,
+}
+
+impl PartReader {
+    fn report(&mut self, unsupported: Unsupported) {
+        if !self.unsupported.contains(&unsupported) {
+            self.unsupported.push(unsupported);
+        }
+    }
+
+    fn measure(&mut self, element: &Element) -> Result<(), MusicXmlError> {
+        let index = self.measures.len();
+
+        // The time signature can change at the beginning of the measure
+        for attributes in element.elements().filter(|e| e.name == "attributes") {
+            if let Some(divisions) = attributes.child_text("divisions") {
+                self.divisions = divisions
+                    .parse()
+                    .ok()
+                    .filter(|d| *d > 0)
+                    .ok_or(MusicXmlError::InvalidDuration { measure: index })?;
+            }
+
+            if let Some(time) = attributes.child("time") {
+                self.time_signature = time_signature(time)
+                    .ok_or(MusicXmlError::InvalidTimeSignature { measure: index })?;
+            }
+        }
+
+        let mut measure = Measure::new(self.time_signature, vec![]);
+
+        measure.pickup = element.attribute("implicit") == Some("yes");
+
+        for child in element.elements() {
+            match child.name.as_str() {
+                "note" => self.note(child, &mut measure)?,
+                "direction" => self.direction(child, &measure),
+                "harmony" => {
+                    if let Some(chord) = self.harmony(child, index)? {
+                        measure.chord_symbols.push(ChordSymbol {
+                            note_index: measure.notes.len(),
+                            chord,
+                        });
+                    }
+                }
+                "forward" => {
+                    let voice = child.child_text("voice").unwrap_or("1".to_string());
+
+                    if self.voice.as_ref().is_none_or(|v| *v == voice) {
+                        let ticks = self.ticks(child, index)?;
+
+                        measure
+                            .notes
+                            .extend(Duration::fill(ticks, DIVISIONS).into_iter().map(Note::Rest));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.measures.push(measure);
+
+        Ok(())
+    }
+
+    fn note(&mut self, element: &Element, measure: &mut Measure) -> Result<(), MusicXmlError> {
+        let index = self.measures.len();
+
+        if element.has_child("grace") {
+            self.report(Unsupported::GraceNote { measure: index });
+            return Ok(());
+        }
+
+        let mut voice = element.child_text("voice").unwrap_or("1".to_string());
+
+        // Notes on the other staves of a part are like other voices
+        if let Some(staff) = element.child_text("staff").filter(|s| s != "1") {
+            voice = format!("{voice} (staff {staff})");
+        }
+
+        if *self.voice.get_or_insert(voice.clone()) != voice {
+            self.report(Unsupported::Voice {
+                measure: index,
+                voice,
+            });
+            return Ok(());
+        }
+
+        let pitch = match element.child("pitch") {
+            Some(pitch) => {
+                Some(parse_pitch(pitch).ok_or(MusicXmlError::InvalidPitch { measure: index })?)
+            }
+            None => None,
+        };
+
+        if element.has_child("chord") {
+            self.report(Unsupported::Chord { measure: index });
+
+            // Replace the previous note of the chord if this one is higher
+            if let (Some(pitch), Some(Note::Tone(previous, _))) = (pitch, measure.notes.last_mut())
+                && pitch.to_half_notes() > previous.to_half_notes()
+            {
+                *previous = pitch;
+            }
+
+            return Ok(());
+        }
+
+        let durations = match note_type(element) {
+            Some(duration) => vec![duration],
+            // Whole measure rests often have no type
+            None => Duration::fill(self.ticks(element, index)?, DIVISIONS),
+        };
+
+        if durations.is_empty() {
+            self.report(Unsupported::ShortNote { measure: index });
+            return Ok(());
+        }
+
+        let first = measure.notes.len();
+
+        for duration in durations {
+            measure.notes.push(match pitch {
+                Some(pitch) => Note::Tone(pitch, duration),
+                None => Note::Rest(duration),
+            });
+        }
+
+        let last = measure.notes.len() - 1;
+        let mut attributes: Vec<(usize, Attribute)> = std::mem::take(&mut self.directions)
+            .into_iter()
+            .map(|attribute| (first, attribute))
+            .collect();
+
+        // Notes without a type may be several tied ones
+        if pitch.is_some() {
+            attributes.extend((first..last).map(|i| (i, Attribute::Tie)));
+        }
+
+        if element
+            .elements()
+            .any(|e| e.name == "tie" && e.attribute("type") == Some("start"))
+        {
+            attributes.push((last, Attribute::Tie));
+        }
+
+        for notations in element.elements().filter(|e| e.name == "notations") {
+            for child in notations.elements() {
+                match (child.name.as_str(), child.attribute("type")) {
+                    ("slur", Some("start")) => attributes.push((first, Attribute::SlurStart)),
+                    ("slur", Some("stop")) => attributes.push((last, Attribute::SlurStop)),
+                    ("articulations", _) => attributes.extend(
+                        child
+                            .elements()
+                            .filter_map(|e| articulation(&e.name))
+                            .map(|a| (first, Attribute::Articulation(a))),
+                    ),
+                    (name, _) => {
+                        if let Some(fermata) = articulation(name) {
+                            attributes.push((first, Attribute::Articulation(fermata)));
+                        }
+                    }
+                }
+            }
+        }
+
+        measure
+            .attributes
+            .extend(
+                attributes
+                    .into_iter()
+                    .map(|(note_index, attribute)| NoteAttribute {
+                        note_index,
+                        attribute,
+                    }),
+            );
+
+        if let Some((_, start)) = &mut self.wedge {
+            start.get_or_insert(NotePosition {
+                measure: index,
+                note: first,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Dynamics are kept for the next note, wedges start at the next note
+    /// and stop at the previous one.
+    fn direction(&mut self, element: &Element, measure: &Measure) {
+        for direction_type in element.elements().filter(|e| e.name == "direction-type") {
+            for child in direction_type.elements() {
+                match (child.name.as_str(), child.attribute("type")) {
+                    ("dynamics", _) => self.directions.extend(
+                        child
+                            .elements()
+                            .filter_map(|e| Dynamic::parse(&e.name))
+                            .map(Attribute::Dynamic),
+                    ),
+                    ("wedge", Some("crescendo")) => {
+                        self.wedge = Some((HairpinKind::Crescendo, None));
+                    }
+                    ("wedge", Some("diminuendo")) => {
+                        self.wedge = Some((HairpinKind::Diminuendo, None));
+                    }
+                    ("wedge", Some("stop")) => {
+                        let end = match measure.notes.len().checked_sub(1) {
+                            Some(note) => Some(NotePosition {
+                                measure: self.measures.len(),
+                                note,
+                            }),
+                            None => self.measures.last().and_then(|m| {
+                                Some(NotePosition {
+                                    measure: self.measures.len() - 1,
+                                    note: m.notes.len().checked_sub(1)?,
+                                })
+                            }),
+                        };
+
+                        if let Some((kind, Some(start))) = self.wedge.take()
+                            && let Some(end) = end
+                        {
+                            self.hairpins.push(Hairpin { kind, start, end });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    /// Length of the `<duration>` in `DIVISIONS` per quarter, up to
+    /// `MAX_TICKS`.
+    fn ticks(&self, element: &Element, index: usize) -> Result<u32, MusicXmlError> {
+        element
+            .child_text("duration")
+            .and_then(|d| d.parse::<u32>().ok())
+            .and_then(|d| d.checked_mul(DIVISIONS))
+            .map(|d| d / self.divisions)
+            .filter(|d| *d <= MAX_TICKS)
+            .ok_or(MusicXmlError::InvalidDuration { measure: index })
+    }
+
+    fn harmony(&mut self, element: &Element, index: usize) -> Result<Option<Chord>, MusicXmlError> {
+        let kind = element.child_text("kind").unwrap_or_default();
+
+        // No chord, like N.C.
+        if kind == "none" {
+            return Ok(None);
+        }
+
+        let chord = element.child("root").and_then(|root| {
+            let root = harmony_pitch(root, "root", SYMBOL_OCTAVE)?;
+            let (_, _, base) = HARMONY_KINDS.iter().find(|(_, k, _)| *k == kind)?;
+            let mut intervals = base.intervals().to_vec();
+
+            for degree in element.elements().filter(|e| e.name == "degree") {
+                apply_degree(&mut intervals, degree)?;
+            }
+
+            let mut chord = Chord::from_intervals(root, &intervals)?;
+
+            if let Some(bass) = element.child("bass") {
+                chord.bass = Some(harmony_pitch(bass, "bass", SYMBOL_OCTAVE - 1)?);
+            }
+
+            Some(chord)
+        });
+
+        if chord.is_none() {
+            self.report(Unsupported::Harmony {
+                measure: index,
+                kind,
+            });
+        }
+
+        Ok(chord)
+    }
+}
+
+fn articulation(name: &str) -> Option<Articulation> {
+    ARTICULATIONS
+        .iter()
+        .find(|(_, element)| *element == name)
+        .map(|(articulation, _)| *articulation)
+}
+
+fn time_signature(time: &Element) -> Option<TimeSignature> {
+    // Compound numerators like 3+2 are summed
+    let numerator = time
+        .child_text("beats")?
+        .split('+')
+        .try_fold(0u8, |sum, n| sum.checked_add(n.trim().parse().ok()?))
+        .filter(|n| *n > 0)?;
+    let denominator = time.child_text("beat-type")?.parse().ok()?;
+
+    Some(TimeSignature::new(numerator, denominator))
+}
+
+fn parse_step(step: &str) -> Option<PitchClass> {
+    let mut chars = step.chars();
+
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => PitchClass::parse(c),
+        _ => None,
+    }
+}
+
+/// Microtonal alterations are not supported.
+fn parse_alter(alter: Option<String>) -> Option<Accidental> {
+    match alter {
+        Some(alter) => {
+            let alter: f64 = alter.parse().ok()?;
+
+            if alter.fract() != 0.0 {
+                return None;
+            }
+
+            Accidental::from_offset(alter as i8)
+        }
+        None => Some(Accidental::Natural),
+    }
+}
+
+fn parse_pitch(element: &Element) -> Option<Pitch> {
+    Some(Pitch {
+        class: parse_step(&element.child_text("step")?)?,
+        accidental: parse_alter(element.child_text("alter"))?,
+        octave: element.child_text("octave")?.parse().ok()?,
+    })
+}
+
+/// Root or bass of a harmony, which have no octave.
+fn harmony_pitch(element: &Element, prefix: &str, octave: i8) -> Option<Pitch> {
+    let mut pitch = Pitch::new(parse_step(&element.child_text(&format!("{prefix}-step"))?)?);
+
+    pitch.accidental = parse_alter(element.child_text(&format!("{prefix}-alter")))?;
+    pitch.octave = octave;
+
+    Some(pitch)
+}
+
+fn note_type(element: &Element) -> Option<Duration> {
+    let text = element.child_text("type")?;
+    let name = DurationName::ALL
+        .into_iter()
+        .find(|name| type_name(*name) == text)?;
+    let dots = element.elements().filter(|e| e.name == "dot").count();
+    let duration = Duration::dotted(name, dots.min(MAX_DOTS) as u8);
+
+    match element.child("time-modification") {
+        Some(modification) => {
+            let actual = modification.child_text("actual-notes")?.parse().ok()?;
+            let normal = modification.child_text("normal-notes")?.parse().ok()?;
+
+            Some(duration.in_tuplet(Tuplet::new(actual, normal)))
+        }
+        None => Some(duration),
+    }
+}
+
+fn apply_degree(intervals: &mut Vec<SpelledInterval>, degree: &Element) -> Option<()> {
+    let value: u8 = degree.child_text("degree-value")?.parse().ok()?;
+    let alter = parse_alter(degree.child_text("degree-alter"))? as i8;
+    let degree_type = match degree.child_text("degree-type")?.as_str() {
+        "add" => DegreeType::Add,
+        "alter" => DegreeType::Alter,
+        "subtract" => DegreeType::Subtract,
+        _ => return None,
+    };
+    let unaltered = SpelledInterval::unaltered_degree(value)?;
+    let interval = SpelledInterval::from_steps(unaltered.steps(), unaltered.half_notes() + alter)?;
+
+    intervals.retain(|i| i.number != value || degree_type == DegreeType::Add);
+
+    if degree_type != DegreeType::Subtract {
+        intervals.push(interval);
+    }
+
+    Some(())
+}
+
+impl Display for MusicXmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MusicXmlError::Xml(error) => write!(f, "invalid XML: {error}"),
+            MusicXmlError::NotPartwise(root) => {
+                write!(f, "expected score-partwise document, found {root}")
+            }
+            MusicXmlError::NoPart => f.write_str("the score has no part"),
+            MusicXmlError::InvalidPitch { measure } => {
+                write!(f, "invalid pitch in measure {}", measure + 1)
+            }
+            MusicXmlError::InvalidDuration { measure } => {
+                write!(f, "invalid duration in measure {}", measure + 1)
+            }
+            MusicXmlError::InvalidTimeSignature { measure } => {
+                write!(f, "invalid time signature in measure {}", measure + 1)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MusicXmlError {}
+
+impl Display for Unsupported {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Unsupported::Chord { measure } => write!(
+                f,
+                "chord in measure {}, only the highest note is kept",
+                measure + 1
+            ),
+            Unsupported::Voice { measure, voice } => {
+                write!(f, "voice {voice} in measure {} is left out", measure + 1)
+            }
+            Unsupported::GraceNote { measure } => {
+                write!(f, "grace note in measure {} is left out", measure + 1)
+            }
+            Unsupported::ShortNote { measure } => write!(
+                f,
+                "note shorter than a 64th in measure {} is left out",
+                measure + 1
+            ),
+            Unsupported::Harmony { measure, kind } => {
+                write!(f, "harmony {kind} in measure {} is left out", measure + 1)
+            }
+            Unsupported::Part(id) => write!(f, "part {id} is left out"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        fraction::Fraction,
+        musicxml::{progression_to_string, staff_to_string},
+        scale::scale_from_string,
+    };
+
+    fn score(measures: &str) -> String {
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<score-partwise version="4.0">
+  <part-list><score-part id="P1"/><score-part id="P2"/></part-list>
+  <part id="P1">{measures}</part>
+  <part id="P2"/>
+</score-partwise>"#
+        )
+    }
+
+    #[test]
+    fn test_staff_round_trip() {
+        let pitches = scale_from_string("F##4 Bbb4 C5");
+        let mut first = Measure::new(
+            TimeSignature::new(3, 4),
+            vec![
+                Note::Tone(pitches[0], Duration::dotted(DurationName::Quarter, 1)),
+                Note::Tone(pitches[1], Duration::new(DurationName::Eigth)),
+                Note::Rest(Duration::new(DurationName::Quarter)),
+            ],
+        );
+
+        first.chord_symbols.push(ChordSymbol {
+            note_index: 1,
+            chord: Chord::parse("Bbm7b5/Fb").unwrap(),
+        });
+
+        let mut second = Measure::new(
+            TimeSignature::new(2, 4),
+            vec![Note::Tone(pitches[2], Duration::new(DurationName::Half))],
+        );
+
+        second.chord_symbols.push(ChordSymbol {
+            note_index: 0,
+            chord: Chord::parse("C7#9b13").unwrap(),
+        });
+
+        let staff = Staff::new(vec![first, second]);
+
+        let import = parse(&staff_to_string(&staff, "Round trip")).unwrap();
+
+        assert_eq!(import.staff, staff);
+        assert!(import.unsupported.is_empty());
+    }
+
+    #[test]
+    fn test_tuplet_round_trip() {
+        let pitches = scale_from_string("C4 D4 E4 F4");
+        let triplet = Duration::new(DurationName::Eigth).in_tuplet(Tuplet::TRIPLET);
+        let staff = Staff::new(vec![Measure::new(
+            TimeSignature::new(2, 4),
+            vec![
+                Note::Tone(pitches[0], triplet),
+                Note::Tone(pitches[1], triplet),
+                Note::Tone(pitches[2], triplet),
+                Note::Tone(pitches[3], Duration::new(DurationName::Quarter)),
+            ],
+        )]);
+        let xml = staff_to_string(&staff, "Triplets");
+
+        assert!(xml.contains("<divisions>192</divisions>"));
+        assert!(xml.contains("<duration>64</duration>"));
+        assert!(xml.contains("<actual-notes>3</actual-notes>"));
+        assert_eq!(parse(&xml).unwrap().staff, staff);
+    }
+
+    #[test]
+    fn test_pickup_round_trip() {
+        let pitches = scale_from_string("G4 C5 E5");
+        let notes: Vec<Note> = [
+            (pitches[0], DurationName::Quarter),
+            (pitches[1], DurationName::Half),
+            (pitches[2], DurationName::Quarter),
+        ]
+        .into_iter()
+        .map(|(pitch, name)| Note::Tone(pitch, Duration::new(name)))
+        .collect();
+        let staff = Staff::rebar(TimeSignature::new(3, 4), &notes, Fraction::new(1, 4)).unwrap();
+        let xml = staff_to_string(&staff, "Pickup");
+
+        assert!(xml.contains(r#"<measure number="0" implicit="yes">"#));
+        assert!(xml.contains(r#"<measure number="1">"#));
+        assert_eq!(parse(&xml).unwrap().staff, staff);
+    }
+
+    #[test]
+    fn test_attributes_round_trip() {
+        let pitches = scale_from_string("C4 D4");
+        let quarter = Duration::new(DurationName::Quarter);
+        let attribute = |note_index, attribute| NoteAttribute {
+            note_index,
+            attribute,
+        };
+        let mut first = Measure::new(
+            TimeSignature::new(2, 4),
+            vec![
+                Note::Tone(pitches[0], quarter),
+                Note::Tone(pitches[1], quarter),
+            ],
+        );
+
+        first.attributes = vec![
+            attribute(0, Attribute::Dynamic(Dynamic::P)),
+            attribute(0, Attribute::SlurStart),
+            attribute(0, Attribute::Articulation(Articulation::Staccato)),
+            attribute(1, Attribute::Tie),
+        ];
+
+        let mut second = Measure::new(
+            TimeSignature::new(2, 4),
+            vec![Note::Tone(pitches[1], quarter), Note::Rest(quarter)],
+        );
+
+        second.attributes = vec![
+            attribute(0, Attribute::Dynamic(Dynamic::F)),
+            attribute(0, Attribute::SlurStop),
+            attribute(0, Attribute::Articulation(Articulation::Marcato)),
+            attribute(1, Attribute::Articulation(Articulation::Fermata)),
+        ];
+
+        let mut staff = Staff::new(vec![first, second]);
+
+        staff.hairpins.push(Hairpin {
+            kind: HairpinKind::Crescendo,
+            start: NotePosition {
+                measure: 0,
+                note: 0,
+            },
+            end: NotePosition {
+                measure: 0,
+                note: 1,
+            },
+        });
+
+        let xml: String = staff_to_string(&staff, "Attributes")
+            .lines()
+            .map(str::trim)
+            .collect();
+
+        assert!(xml.contains(r#"<tie type="start"/><type>quarter</type>"#));
+        assert!(xml.contains(r#"<tied type="stop"/><slur type="stop" number="1"/>"#));
+        assert!(xml.contains("<articulations><strong-accent/></articulations>"));
+        assert!(xml.contains("<dynamics><p/></dynamics>"));
+        assert!(xml.contains(r#"<wedge type="stop"/>"#));
+        assert_eq!(parse(&xml).unwrap().staff, staff);
+    }
+
+    #[test]
+    fn test_progression_chords() {
+        let chords: Vec<Chord> = ["Dm7", "G7sus4", "C6/9", "CaugMaj7"]
+            .iter()
+            .map(|s| Chord::parse(s).unwrap())
+            .collect();
+
+        let import = parse(&progression_to_string(
+            &chords,
+            TimeSignature::COMMON_TIME,
+            "Progression",
+        ))
+        .unwrap();
+        let symbols: Vec<Chord> = import
+            .staff
+            .measures
+            .iter()
+            .flat_map(|m| m.chord_symbols.iter().map(|s| s.chord.clone()))
+            .collect();
+
+        assert_eq!(symbols, chords);
+        assert_eq!(
+            import.staff.measures[0].notes,
+            vec![Note::Tone(
+                Pitch::parse("C5").unwrap(),
+                Duration::new(DurationName::Whole)
+            )]
+        );
+        assert!(
+            import
+                .unsupported
+                .contains(&Unsupported::Chord { measure: 0 })
+        );
+    }
+
+    #[test]
+    fn test_voices_and_ties() {
+        let import = parse(&score(
+            r#"<measure number="1">
+      <attributes><divisions>2</divisions><time><beats>2</beats><beat-type>4</beat-type></time></attributes>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>4</duration><tie type="start"/><voice>1</voice></note>
+      <backup><duration>4</duration></backup>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>4</duration><voice>2</voice><type>half</type></note>
+    </measure>
+    <measure number="2">
+      <note><grace/><pitch><step>D</step><octave>5</octave></pitch><voice>1</voice><type>eighth</type></note>
+      <note><pitch><step>E</step><octave>5</octave></pitch><duration>1</duration><tie type="stop"/><voice>1</voice><type>eighth</type></note>
+      <forward><duration>1</duration><voice>1</voice></forward>
+      <note><rest/><duration>2</duration><voice>1</voice></note>
+      <harmony><root><root-step>C</root-step></root><kind>power</kind></harmony>
+    </measure>"#,
+        ))
+        .unwrap();
+
+        let e = Pitch::parse("E5").unwrap();
+        let eighth = Duration::new(DurationName::Eigth);
+        let quarter = Duration::new(DurationName::Quarter);
+
+        assert_eq!(
+            import.staff.measures[0].notes,
+            vec![Note::Tone(e, Duration::new(DurationName::Half))]
+        );
+        assert_eq!(
+            import.staff.measures[1].notes,
+            vec![
+                Note::Tone(e, eighth),
+                Note::Rest(eighth),
+                Note::Rest(quarter)
+            ]
+        );
+        assert_eq!(
+            import.staff.measures[1].time_signature,
+            TimeSignature::new(2, 4)
+        );
+        assert!(import.staff.measures[0].has_attribute(0, Attribute::Tie));
+        assert!(import.staff.measures[1].attributes.is_empty());
+        assert_eq!(
+            import.unsupported,
+            vec![
+                Unsupported::Voice {
+                    measure: 0,
+                    voice: "2".to_string()
+                },
+                Unsupported::GraceNote { measure: 1 },
+                Unsupported::Harmony {
+                    measure: 1,
+                    kind: "power".to_string()
+                },
+                Unsupported::Part("P2".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_short_notes() {
+        let import = parse(&score(
+            r#"<measure>
+      <attributes><divisions>10080</divisions></attributes>
+      <direction><direction-type><dynamics><p/></dynamics></direction-type></direction>
+      <note><rest/><duration>1</duration><tie type="start"/></note>
+      <note><pitch><step>C</step><octave>4</octave></pitch><duration>40320</duration></note>
+    </measure>"#,
+        ))
+        .unwrap();
+        let measure = &import.staff.measures[0];
+
+        assert_eq!(import.unsupported[0], Unsupported::ShortNote { measure: 0 });
+        assert_eq!(measure.notes.len(), 1);
+        assert!(measure.has_attribute(0, Attribute::Dynamic(Dynamic::P)));
+    }
+
+    #[test]
+    fn test_invalid_documents() {
+        assert_eq!(
+            parse("<score-timewise/>"),
+            Err(MusicXmlError::NotPartwise("score-timewise".to_string()))
+        );
+        assert_eq!(parse("<score-partwise/>"), Err(MusicXmlError::NoPart));
+        assert_eq!(
+            parse(&score(
+                r#"<measure><note><pitch><step>H</step><octave>4</octave></pitch><duration>1</duration></note></measure>"#
+            )),
+            Err(MusicXmlError::InvalidPitch { measure: 0 })
+        );
+        assert!(matches!(parse("<a>"), Err(MusicXmlError::Xml(_))));
+
+        for duration in ["4294967295", "1000000"] {
+            assert_eq!(
+                parse(&score(&format!(
+                    "<measure><note><rest/><duration>{duration}</duration></note></measure>"
+                ))),
+                Err(MusicXmlError::InvalidDuration { measure: 0 })
+            );
+        }
+
+        let dots = "<dot/>".repeat(100);
+        let import = parse(&score(&format!(
+            "<measure><note><rest/><duration>1</duration><type>quarter</type>{dots}</note></measure>"
+        )))
+        .unwrap();
+
+        assert_eq!(
+            import.staff.measures[0].notes[0].duration().dots,
+            MAX_DOTS as u8
+        );
+
+        for beats in ["200+100", "0"] {
+            assert_eq!(
+                parse(&score(&format!(
+                    "<measure><attributes><time><beats>{beats}</beats><beat-type>4</beat-type></time></attributes></measure>"
+                ))),
+                Err(MusicXmlError::InvalidTimeSignature { measure: 0 })
+            );
+        }
+    }
+}
@@ -0,0 +1,230 @@
+//! MusicXML (partwise) documents of staves, scales and chord progressions.
+use crate::{
+    chord::{Chord, ChordQuality},
+    note::{Articulation, DurationName},
+};
+
+pub mod export;
+pub mod import;
+mod xml;
+
+pub use export::{progression_to_string, scales_to_string, staff_to_string};
+pub use import::{Import, MusicXmlError, Unsupported, parse};
+
+/// Divisions of a quarter note, enough for double dotted 64th notes.
+const DIVISIONS: u32 = 64;
+
+/// Articulations and their elements in `<articulations>`, the fermata is
+/// an element of `<notations>` of its own.
+const ARTICULATIONS: [(Articulation, &str); 6] = [
+    (Articulation::Staccato, "staccato"),
+    (Articulation::Staccatissimo, "staccatissimo"),
+    (Articulation::Tenuto, "tenuto"),
+    (Articulation::Accent, "accent"),
+    (Articulation::Marcato, "strong-accent"),
+    (Articulation::Fermata, "fermata"),
+];
+
+/// Chord qualities and the `<kind>` values of harmonies. Qualities without
+/// a kind of their own are written as a simpler kind and `<degree>`s.
+const HARMONY_KINDS: [(ChordQuality, &str, ChordQuality); 29] = [
+    (ChordQuality::Major, "major", ChordQuality::Major),
+    (ChordQuality::Minor, "minor", ChordQuality::Minor),
+    (
+        ChordQuality::Diminished,
+        "diminished",
+        ChordQuality::Diminished,
+    ),
+    (
+        ChordQuality::Augmented,
+        "augmented",
+        ChordQuality::Augmented,
+    ),
+    (
+        ChordQuality::Suspended2,
+        "suspended-second",
+        ChordQuality::Suspended2,
+    ),
+    (
+        ChordQuality::Suspended4,
+        "suspended-fourth",
+        ChordQuality::Suspended4,
+    ),
+    (ChordQuality::Major6, "major-sixth", ChordQuality::Major6),
+    (ChordQuality::Minor6, "minor-sixth", ChordQuality::Minor6),
+    (ChordQuality::Dominant7, "dominant", ChordQuality::Dominant7),
+    (ChordQuality::Major7, "major-seventh", ChordQuality::Major7),
+    (ChordQuality::Minor7, "minor-seventh", ChordQuality::Minor7),
+    (
+        ChordQuality::MinorMajor7,
+        "major-minor",
+        ChordQuality::MinorMajor7,
+    ),
+    (
+        ChordQuality::HalfDiminished7,
+        "half-diminished",
+        ChordQuality::HalfDiminished7,
+    ),
+    (
+        ChordQuality::Diminished7,
+        "diminished-seventh",
+        ChordQuality::Diminished7,
+    ),
+    (
+        ChordQuality::Augmented7,
+        "augmented-seventh",
+        ChordQuality::Augmented7,
+    ),
+    (
+        ChordQuality::Dominant9,
+        "dominant-ninth",
+        ChordQuality::Dominant9,
+    ),
+    (ChordQuality::Major9, "major-ninth", ChordQuality::Major9),
+    (ChordQuality::Minor9, "minor-ninth", ChordQuality::Minor9),
+    (
+        ChordQuality::Dominant11,
+        "dominant-11th",
+        ChordQuality::Dominant11,
+    ),
+    (ChordQuality::Major11, "major-11th", ChordQuality::Major11),
+    (ChordQuality::Minor11, "minor-11th", ChordQuality::Minor11),
+    (
+        ChordQuality::Dominant13,
+        "dominant-13th",
+        ChordQuality::Dominant13,
+    ),
+    (ChordQuality::Major13, "major-13th", ChordQuality::Major13),
+    (ChordQuality::Minor13, "minor-13th", ChordQuality::Minor13),
+    (
+        ChordQuality::AugmentedMajor7,
+        "augmented",
+        ChordQuality::Augmented,
+    ),
+    (
+        ChordQuality::Dominant7Suspended2,
+        "suspended-second",
+        ChordQuality::Suspended2,
+    ),
+    (
+        ChordQuality::Dominant7Suspended4,
+        "suspended-fourth",
+        ChordQuality::Suspended4,
+    ),
+    (ChordQuality::SixNine, "major-sixth", ChordQuality::Major6),
+    (
+        ChordQuality::MinorSixNine,
+        "minor-sixth",
+        ChordQuality::Minor6,
+    ),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum DegreeType {
+    Add,
+    Alter,
+    Subtract,
+}
+
+/// `<degree>` of a harmony, the alteration is in half notes compared to
+/// the major (or perfect) interval, and to the minor one for sevenths.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Degree {
+    value: u8,
+    alter: i8,
+    degree_type: DegreeType,
+}
+
+fn type_name(name: DurationName) -> &'static str {
+    match name {
+        DurationName::Whole => "whole",
+        DurationName::Half => "half",
+        DurationName::Quarter => "quarter",
+        DurationName::Eigth => "eighth",
+        DurationName::Sixteenth => "16th",
+        DurationName::ThirtySecond => "32nd",
+        DurationName::SixtyFourth => "64th",
+    }
+}
+
+/// The kind and the degrees which describe the chord in a harmony.
+fn harmony_kind(chord: &Chord) -> (&'static str, Vec<Degree>) {
+    let (_, kind, base) = HARMONY_KINDS
+        .iter()
+        .find(|(quality, _, _)| *quality == chord.quality)
+        .expect("Every chord quality has a harmony kind");
+    let base_intervals = base.intervals();
+    let intervals = chord.intervals();
+    let mut degrees = vec![];
+
+    for interval in &intervals {
+        if base_intervals.contains(interval) {
+            continue;
+        }
+
+        let degree_type = if base_intervals.iter().any(|i| i.number == interval.number) {
+            DegreeType::Alter
+        } else {
+            DegreeType::Add
+        };
+        degrees.push(Degree {
+            value: interval.number,
+            alter: interval.alteration(),
+            degree_type,
+        });
+    }
+
+    for interval in base_intervals {
+        if !intervals.iter().any(|i| i.number == interval.number) {
+            degrees.push(Degree {
+                value: interval.number,
+                alter: 0,
+                degree_type: DegreeType::Subtract,
+            });
+        }
+    }
+
+    (kind, degrees)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn degrees(symbol: &str) -> (&'static str, Vec<(u8, i8, DegreeType)>) {
+        let (kind, degrees) = harmony_kind(&Chord::parse(symbol).unwrap());
+
+        (
+            kind,
+            degrees
+                .iter()
+                .map(|d| (d.value, d.alter, d.degree_type))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_harmony_kind() {
+        assert_eq!(degrees("Cm7"), ("minor-seventh", vec![]));
+        assert_eq!(
+            degrees("C7b9"),
+            ("dominant", vec![(9, -1, DegreeType::Add)])
+        );
+        assert_eq!(
+            degrees("C7b5"),
+            ("dominant", vec![(5, -1, DegreeType::Alter)])
+        );
+        assert_eq!(
+            degrees("C6/9"),
+            ("major-sixth", vec![(9, 0, DegreeType::Add)])
+        );
+        assert_eq!(
+            degrees("C7sus4"),
+            ("suspended-fourth", vec![(7, 0, DegreeType::Add)])
+        );
+        assert_eq!(
+            degrees("CaugMaj7"),
+            ("augmented", vec![(7, 1, DegreeType::Add)])
+        );
+    }
+}
@@ -0,0 +1,460 @@
+//! Minimal XML writing with indentation and parsing into a tree, enough
+//! for MusicXML documents.
+use std::fmt::Display;
+
+pub struct XmlWriter {
+    output: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    pub fn new() -> Self {
+        XmlWriter {
+            output: String::new(),
+            depth: 0,
+        }
+    }
+
+    pub fn declaration(&mut self, line: &str) {
+        self.output.push_str(line);
+        self.output.push('\n');
+    }
+
+    pub fn open(&mut self, tag: &str) {
+        self.open_with(tag, &[]);
+    }
+
+    pub fn open_with(&mut self, tag: &str, attributes: &[(&str, &str)]) {
+        self.line(&format!("<{tag}{}>", format_attributes(attributes)));
+        self.depth += 1;
+    }
+
+    pub fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.line(&format!("</{tag}>"));
+    }
+
+    /// Element with text content.
+    pub fn text(&mut self, tag: &str, text: &str) {
+        self.text_with(tag, &[], text);
+    }
+
+    pub fn text_with(&mut self, tag: &str, attributes: &[(&str, &str)], text: &str) {
+        self.line(&format!(
+            "<{tag}{}>{}</{tag}>",
+            format_attributes(attributes),
+            escape(text)
+        ));
+    }
+
+    pub fn empty(&mut self, tag: &str) {
+        self.empty_with(tag, &[]);
+    }
+
+    pub fn empty_with(&mut self, tag: &str, attributes: &[(&str, &str)]) {
+        self.line(&format!("<{tag}{}/>", format_attributes(attributes)));
+    }
+
+    pub fn finish(self) -> String {
+        self.output
+    }
+
+    fn line(&mut self, content: &str) {
+        for _ in 0..self.depth {
+            self.output.push_str("  ");
+        }
+
+        self.output.push_str(content);
+        self.output.push('\n');
+    }
+}
+
+fn format_attributes(attributes: &[(&str, &str)]) -> String {
+    attributes
+        .iter()
+        .map(|(name, value)| format!(" {name}=\"{}\"", escape(value)))
+        .collect()
+}
+
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            _ => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Element {
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Node>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct XmlError {
+    /// Byte offset in the document.
+    pub position: usize,
+    pub message: &'static str,
+}
+
+impl Element {
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Child elements, text is left out.
+    pub fn elements(&self) -> impl Iterator<Item = &Element> {
+        self.children.iter().filter_map(|node| match node {
+            Node::Element(element) => Some(element),
+            Node::Text(_) => None,
+        })
+    }
+
+    pub fn child(&self, name: &str) -> Option<&Element> {
+        self.elements().find(|e| e.name == name)
+    }
+
+    pub fn has_child(&self, name: &str) -> bool {
+        self.child(name).is_some()
+    }
+
+    /// Text content without the surrounding whitespace.
+    pub fn text(&self) -> String {
+        let text: String = self
+            .children
+            .iter()
+            .filter_map(|node| match node {
+                Node::Text(text) => Some(text.as_str()),
+                Node::Element(_) => None,
+            })
+            .collect();
+
+        text.trim().to_string()
+    }
+
+    pub fn child_text(&self, name: &str) -> Option<String> {
+        self.child(name).map(Element::text)
+    }
+}
+
+/// Deepest nesting of elements, MusicXML needs less than ten levels.
+const MAX_DEPTH: usize = 256;
+
+/// Parse the root element of the document. The XML declaration, the
+/// doctype, comments and processing instructions are skipped.
+pub fn parse(input: &str) -> Result<Element, XmlError> {
+    let mut parser = Parser {
+        input,
+        position: 0,
+        depth: 0,
+    };
+
+    parser.skip_misc()?;
+
+    let root = parser.element()?;
+
+    parser.skip_misc()?;
+
+    if parser.position < input.len() {
+        return Err(parser.error("content after the root element"));
+    }
+
+    Ok(root)
+}
+
+struct Parser<'a> {
+    input: &'a str,
+    position: usize,
+    /// Number of elements open around the current one.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.input[self.position..]
+    }
+
+    fn error(&self, message: &'static str) -> XmlError {
+        XmlError {
+            position: self.position,
+            message,
+        }
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+
+        self.position += rest.len() - rest.trim_start().len();
+    }
+
+    /// Move after the next `end`.
+    fn skip_past(&mut self, end: &str) -> Result<(), XmlError> {
+        match self.rest().find(end) {
+            Some(i) => {
+                self.position += i + end.len();
+                Ok(())
+            }
+            None => Err(self.error("unexpected end of document")),
+        }
+    }
+
+    fn expect(&mut self, s: &str) -> Result<(), XmlError> {
+        if self.rest().starts_with(s) {
+            self.position += s.len();
+            Ok(())
+        } else {
+            Err(self.error("unexpected character"))
+        }
+    }
+
+    fn skip_misc(&mut self) -> Result<(), XmlError> {
+        loop {
+            self.skip_whitespace();
+
+            if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<!DOCTYPE") {
+                // The internal subset in brackets may contain '>'
+                let end = self.rest().find(['[', '>']);
+
+                if end.is_some_and(|i| self.rest().as_bytes()[i] == b'[') {
+                    self.skip_past("]")?;
+                }
+
+                self.skip_past(">")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn name(&mut self) -> Result<String, XmlError> {
+        let length = self
+            .rest()
+            .find(|c: char| c.is_whitespace() || matches!(c, '>' | '/' | '='))
+            .unwrap_or(self.rest().len());
+
+        if length == 0 {
+            return Err(self.error("missing name"));
+        }
+
+        let name = self.rest()[..length].to_string();
+
+        self.position += length;
+
+        Ok(name)
+    }
+
+    fn element(&mut self) -> Result<Element, XmlError> {
+        self.expect("<")?;
+
+        let mut element = Element {
+            name: self.name()?,
+            attributes: vec![],
+            children: vec![],
+        };
+
+        loop {
+            self.skip_whitespace();
+
+            if self.rest().starts_with("/>") {
+                self.position += 2;
+                return Ok(element);
+            }
+
+            if self.rest().starts_with('>') {
+                self.position += 1;
+                break;
+            }
+
+            let name = self.name()?;
+
+            self.skip_whitespace();
+            self.expect("=")?;
+            self.skip_whitespace();
+
+            let quote = match self.rest().chars().next() {
+                Some(q @ ('"' | '\'')) => q,
+                _ => return Err(self.error("missing quote of attribute value")),
+            };
+
+            self.position += 1;
+
+            let end = self
+                .rest()
+                .find(quote)
+                .ok_or_else(|| self.error("unexpected end of document"))?;
+            let value =
+                unescape(&self.rest()[..end]).ok_or_else(|| self.error("invalid entity"))?;
+
+            self.position += end + 1;
+            element.attributes.push((name, value));
+        }
+
+        loop {
+            if self.rest().starts_with("</") {
+                self.position += 2;
+
+                if self.name()? != element.name {
+                    return Err(self.error("mismatched closing tag"));
+                }
+
+                self.skip_whitespace();
+                self.expect(">")?;
+
+                return Ok(element);
+            } else if self.rest().starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if self.rest().starts_with("<![CDATA[") {
+                let start = self.position + 9;
+
+                self.skip_past("]]>")?;
+                element
+                    .children
+                    .push(Node::Text(self.input[start..self.position - 3].to_string()));
+            } else if self.rest().starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if self.rest().starts_with('<') {
+                if self.depth == MAX_DEPTH {
+                    return Err(self.error("elements nested too deep"));
+                }
+
+                self.depth += 1;
+
+                let child = self.element()?;
+
+                self.depth -= 1;
+                element.children.push(Node::Element(child));
+            } else if self.rest().is_empty() {
+                return Err(self.error("unexpected end of document"));
+            } else {
+                let end = self.rest().find('<').unwrap_or(self.rest().len());
+                let text =
+                    unescape(&self.rest()[..end]).ok_or_else(|| self.error("invalid entity"))?;
+
+                self.position += end;
+                element.children.push(Node::Text(text));
+            }
+        }
+    }
+}
+
+/// Replace the predefined and the numeric character references.
+fn unescape(text: &str) -> Option<String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start + 1..];
+
+        let end = rest.find(';')?;
+        let entity = &rest[..end];
+
+        result.push(match entity {
+            "amp" => '&',
+            "lt" => '<',
+            "gt" => '>',
+            "quot" => '"',
+            "apos" => '\'',
+            _ => {
+                let code = match entity.strip_prefix("#x") {
+                    Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                    None => entity.strip_prefix('#')?.parse().ok()?,
+                };
+
+                char::from_u32(code)?
+            }
+        });
+        rest = &rest[end + 1..];
+    }
+
+    result.push_str(rest);
+
+    Some(result)
+}
+
+impl Display for XmlError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} at byte {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for XmlError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_and_parse() {
+        let mut writer = XmlWriter::new();
+
+        writer.declaration(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+        writer.open_with("root", &[("title", "Tom & \"Jerry\"")]);
+        writer.text("name", "<b>");
+        writer.empty_with("flag", &[("on", "yes")]);
+        writer.close("root");
+
+        let root = parse(&writer.finish()).unwrap();
+
+        assert_eq!(root.name, "root");
+        assert_eq!(root.attribute("title"), Some("Tom & \"Jerry\""));
+        assert_eq!(root.child_text("name"), Some("<b>".to_string()));
+        assert_eq!(root.child("flag").unwrap().attribute("on"), Some("yes"));
+        assert_eq!(root.elements().count(), 2);
+    }
+
+    #[test]
+    fn test_parse_document() {
+        let document = r#"<?xml version="1.0"?>
+<!DOCTYPE a PUBLIC "-//A//EN" "a.dtd" [ <!ENTITY x "y"> ]>
+<!-- comment -->
+<a x='1'>text &#65;&#x42; <![CDATA[<raw>]]><b/><!-- c --></a>
+"#;
+
+        let root = parse(document).unwrap();
+
+        assert_eq!(root.attribute("x"), Some("1"));
+        assert_eq!(root.text(), "text AB <raw>");
+        assert!(root.has_child("b"));
+
+        assert_eq!(
+            parse("<a><b></a>").unwrap_err().message,
+            "mismatched closing tag"
+        );
+        assert_eq!(
+            parse("<a>").unwrap_err().message,
+            "unexpected end of document"
+        );
+        assert_eq!(parse("<a>&bad;</a>").unwrap_err().message, "invalid entity");
+
+        let nested = "<a>".repeat(200_000);
+
+        assert_eq!(
+            parse(&nested).unwrap_err().message,
+            "elements nested too deep"
+        );
+    }
+}
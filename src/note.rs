@@ -1,6 +1,15 @@
-use crate::pitch::Pitch;
+use std::{
+    cmp::{Ordering, Reverse},
+    ops::Add,
+};
 
-#[derive(Debug)]
+use crate::{fraction::Fraction, pitch::Pitch};
+
+/// Ticks per quarter note for lengths computed inside the crate, enough
+/// for a 64th note in a triplet.
+pub const PPQ: u32 = 960;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DurationName {
     Whole = 1,
     Half = 2,
@@ -11,15 +20,408 @@ pub enum DurationName {
     SixtyFourth = 64,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Duration {
     pub name: DurationName,
     /// 0 means no dots, 1 dot, 2 dots, etc.
     pub dots: u8,
+    /// The note is part of a tuplet and shorter or longer than its name.
+    pub tuplet: Option<Tuplet>,
+}
+
+/// `actual` notes played in the time of `normal` notes of the same value,
+/// a triplet is 3 in the time of 2.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Tuplet {
+    pub actual: u8,
+    pub normal: u8,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Note {
     Tone(Pitch, Duration),
     Rest(Duration),
 }
+
+/// Marking of a single note. The notes of a measure keep their markings
+/// in `beat::Measure::attributes`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Attribute {
+    /// Tied to the next note, which may be in the next measure.
+    Tie,
+    SlurStart,
+    SlurStop,
+    Articulation(Articulation),
+    /// Dynamic from the note on, until the next one.
+    Dynamic(Dynamic),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Articulation {
+    Staccato,
+    Staccatissimo,
+    Tenuto,
+    Accent,
+    /// Strong accent, written as ^.
+    Marcato,
+    Fermata,
+}
+
+/// Dynamics from softest to loudest, and the sforzando which only
+/// stresses its note.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dynamic {
+    Ppp,
+    Pp,
+    P,
+    Mp,
+    Mf,
+    F,
+    Ff,
+    Fff,
+    Sfz,
+}
+
+impl DurationName {
+    pub const ALL: [DurationName; 7] = [
+        DurationName::Whole,
+        DurationName::Half,
+        DurationName::Quarter,
+        DurationName::Eigth,
+        DurationName::Sixteenth,
+        DurationName::ThirtySecond,
+        DurationName::SixtyFourth,
+    ];
+}
+
+impl Dynamic {
+    pub const ALL: [Dynamic; 9] = [
+        Dynamic::Ppp,
+        Dynamic::Pp,
+        Dynamic::P,
+        Dynamic::Mp,
+        Dynamic::Mf,
+        Dynamic::F,
+        Dynamic::Ff,
+        Dynamic::Fff,
+        Dynamic::Sfz,
+    ];
+
+    /// Name as written in scores, like "mf".
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dynamic::Ppp => "ppp",
+            Dynamic::Pp => "pp",
+            Dynamic::P => "p",
+            Dynamic::Mp => "mp",
+            Dynamic::Mf => "mf",
+            Dynamic::F => "f",
+            Dynamic::Ff => "ff",
+            Dynamic::Fff => "fff",
+            Dynamic::Sfz => "sfz",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Dynamic::ALL.into_iter().find(|d| d.name() == name)
+    }
+
+    /// MIDI velocity, 16 steps from ppp to fff.
+    pub fn velocity(&self) -> u8 {
+        match self {
+            Dynamic::Fff => 127,
+            Dynamic::Sfz => 112,
+            _ => 16 * (*self as u8 + 1),
+        }
+    }
+
+    /// Level (not sforzando) closest to the MIDI velocity.
+    pub fn from_velocity(velocity: u8) -> Self {
+        Dynamic::ALL[..8]
+            .iter()
+            .copied()
+            .min_by_key(|d| d.velocity().abs_diff(velocity))
+            .unwrap_or(Dynamic::Mf)
+    }
+}
+
+impl Tuplet {
+    pub const TRIPLET: Tuplet = Tuplet {
+        actual: 3,
+        normal: 2,
+    };
+
+    pub fn new(actual: u8, normal: u8) -> Self {
+        Tuplet { actual, normal }
+    }
+}
+
+impl Duration {
+    pub fn new(name: DurationName) -> Self {
+        Duration {
+            name,
+            dots: 0,
+            tuplet: None,
+        }
+    }
+
+    pub fn dotted(name: DurationName, dots: u8) -> Self {
+        Duration {
+            name,
+            dots,
+            tuplet: None,
+        }
+    }
+
+    /// The same duration inside a tuplet.
+    pub fn in_tuplet(self, tuplet: Tuplet) -> Self {
+        Duration {
+            tuplet: Some(tuplet),
+            ..self
+        }
+    }
+
+    /// Exact length in whole notes. Every dot adds the half of the previous
+    /// value and a tuplet scales it by `normal / actual`, so a triplet
+    /// eighth is 1/12.
+    pub fn as_fraction(&self) -> Fraction {
+        let base = Fraction::new(1, self.name as i64);
+        let dots = Fraction::new((1 << (self.dots + 1)) - 1, 1 << self.dots);
+        let tuplet = self.tuplet.map_or(Fraction::from_integer(1), |t| {
+            Fraction::new(t.normal as i64, t.actual.max(1) as i64)
+        });
+
+        base * dots * tuplet
+    }
+
+    /// Compare the lengths, so a dotted eighth triplet is as long as an
+    /// eighth.
+    pub fn cmp_length(&self, other: &Self) -> Ordering {
+        self.as_fraction().cmp(&other.as_fraction())
+    }
+
+    /// Length in MIDI ticks when a quarter note is `ticks_per_quarter`
+    /// ticks, rounded down when it is not a whole number of ticks.
+    pub fn to_ticks(&self, ticks_per_quarter: u32) -> u32 {
+        (self.as_fraction() * Fraction::from_integer(4 * ticks_per_quarter as i64)).floor() as u32
+    }
+
+    /// Length in seconds when `quarters_per_minute` quarter notes are
+    /// played in a minute.
+    pub fn to_seconds(&self, quarters_per_minute: f64) -> f64 {
+        self.as_fraction().to_f64() * 4.0 * 60.0 / quarters_per_minute
+    }
+
+    /// The fewest notes (with the fewest dots) which last `ticks` together,
+    /// the longer ones first. What remains shorter than a 64th is left out.
+    pub fn fill(ticks: u32, ticks_per_quarter: u32) -> Vec<Duration> {
+        let whole = Duration::new(DurationName::Whole);
+        let whole_ticks = whole.to_ticks(ticks_per_quarter);
+
+        // Whole notes are taken first, so only the last two wholes need to
+        // be searched
+        let wholes = ticks
+            .checked_div(whole_ticks)
+            .map_or(0, |count| count.saturating_sub(1));
+        let remainder = ticks - wholes * whole_ticks;
+
+        let candidates: Vec<(usize, Duration)> = DurationName::ALL
+            .iter()
+            .flat_map(|name| (0..=2).map(|dots| Duration::dotted(*name, dots)))
+            .map(|duration| (duration.to_ticks(ticks_per_quarter) as usize, duration))
+            .filter(|(length, _)| *length > 0)
+            .collect();
+
+        // Cheapest way to build every length up to `remainder`: number of
+        // notes, number of dots and the last note
+        let mut best: Vec<Option<(usize, usize, Duration)>> = vec![None; remainder as usize + 1];
+
+        for length in 1..best.len() {
+            best[length] = candidates
+                .iter()
+                .filter(|(l, _)| *l <= length)
+                .filter_map(|(l, duration)| {
+                    let (count, dots) = match best[length - l] {
+                        Some((count, dots, _)) => (count, dots),
+                        None if *l == length => (0, 0),
+                        None => return None,
+                    };
+
+                    Some((count + 1, dots + duration.dots as usize, *duration))
+                })
+                .min_by_key(|(count, dots, _)| (*count, *dots));
+        }
+
+        let mut length = (0..best.len())
+            .rev()
+            .find(|l| best[*l].is_some())
+            .unwrap_or(0);
+        let mut durations = vec![whole; wholes as usize];
+
+        while let Some((_, _, duration)) = best[length] {
+            durations.push(duration);
+            length -= duration.to_ticks(ticks_per_quarter) as usize;
+        }
+
+        durations.sort_by_key(|duration| Reverse(duration.to_ticks(ticks_per_quarter)));
+
+        durations
+    }
+}
+
+/// Durations add up to an exact length in whole notes.
+impl Add for Duration {
+    type Output = Fraction;
+
+    fn add(self, other: Duration) -> Fraction {
+        self.as_fraction() + other.as_fraction()
+    }
+}
+
+impl Add<Duration> for Fraction {
+    type Output = Fraction;
+
+    fn add(self, other: Duration) -> Fraction {
+        self + other.as_fraction()
+    }
+}
+
+impl Note {
+    pub fn duration(&self) -> &Duration {
+        match self {
+            Note::Tone(_, duration) => duration,
+            Note::Rest(duration) => duration,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(
+            Duration::fill(480 * 8, 480),
+            vec![Duration::new(DurationName::Whole); 2]
+        );
+        assert_eq!(
+            Duration::fill(480 * 7, 480),
+            vec![Duration::dotted(DurationName::Whole, 2)]
+        );
+        assert_eq!(
+            Duration::fill(480 * 5, 480),
+            vec![
+                Duration::new(DurationName::Whole),
+                Duration::new(DurationName::Quarter)
+            ]
+        );
+        assert_eq!(
+            Duration::fill(480 * 7 / 4, 480),
+            vec![Duration::dotted(DurationName::Quarter, 2)]
+        );
+        assert_eq!(
+            Duration::fill(600, 480),
+            vec![
+                Duration::new(DurationName::Quarter),
+                Duration::new(DurationName::Sixteenth)
+            ]
+        );
+        assert!(Duration::fill(0, 480).is_empty());
+        assert!(Duration::fill(480, 0).is_empty());
+
+        let long = Duration::fill(100_000, 64);
+
+        assert_eq!(long.len(), 391);
+        assert_eq!(long[0], Duration::dotted(DurationName::Whole, 1));
+        assert!(long[1..390] == [Duration::new(DurationName::Whole); 389]);
+        assert_eq!(long[390], Duration::new(DurationName::Eigth));
+    }
+
+    #[test]
+    fn test_as_fraction() {
+        use DurationName::*;
+
+        assert_eq!(Duration::new(Quarter).as_fraction(), Fraction::new(1, 4));
+        assert_eq!(Duration::dotted(Half, 1).as_fraction(), Fraction::new(3, 4));
+        assert_eq!(
+            Duration::dotted(Quarter, 2).as_fraction(),
+            Fraction::new(7, 16)
+        );
+        assert_eq!(
+            Duration::new(Eigth)
+                .in_tuplet(Tuplet::TRIPLET)
+                .as_fraction(),
+            Fraction::new(1, 12)
+        );
+        assert_eq!(
+            Duration::new(Sixteenth)
+                .in_tuplet(Tuplet::new(5, 4))
+                .as_fraction(),
+            Fraction::new(1, 20)
+        );
+        assert_eq!(
+            Duration::new(ThirtySecond)
+                .in_tuplet(Tuplet::new(7, 8))
+                .as_fraction(),
+            Fraction::new(1, 28)
+        );
+
+        let triplet = Duration::new(Quarter).in_tuplet(Tuplet::TRIPLET);
+
+        assert_eq!(
+            [triplet; 3]
+                .into_iter()
+                .fold(Fraction::ZERO, |sum, d| sum + d),
+            Fraction::new(1, 2)
+        );
+        assert_eq!(
+            Duration::new(Eigth) + Duration::new(Sixteenth),
+            Fraction::new(3, 16)
+        );
+        assert_eq!(
+            Duration::new(Quarter).cmp_length(&Duration::dotted(Eigth, 2)),
+            Ordering::Greater
+        );
+        assert_eq!(triplet.cmp_length(&Duration::new(Quarter)), Ordering::Less);
+        assert_eq!(
+            Duration::dotted(Eigth, 1)
+                .in_tuplet(Tuplet::TRIPLET)
+                .cmp_length(&Duration::new(Eigth)),
+            Ordering::Equal
+        );
+        assert_ne!(
+            Duration::dotted(Eigth, 1).in_tuplet(Tuplet::TRIPLET),
+            Duration::new(Eigth)
+        );
+    }
+
+    #[test]
+    fn test_dynamics() {
+        assert_eq!(Dynamic::parse("mp"), Some(Dynamic::Mp));
+        assert_eq!(Dynamic::parse("mfp"), None);
+        assert_eq!(Dynamic::Mf.velocity(), 80);
+        assert_eq!(Dynamic::from_velocity(86), Dynamic::Mf);
+        assert_eq!(Dynamic::from_velocity(120), Dynamic::Fff);
+        assert_eq!(Dynamic::from_velocity(1), Dynamic::Ppp);
+        assert!(
+            Dynamic::ALL
+                .iter()
+                .all(|d| Dynamic::parse(d.name()) == Some(*d))
+        );
+    }
+
+    #[test]
+    fn test_ticks_and_seconds() {
+        use DurationName::*;
+
+        let triplet = Duration::new(Eigth).in_tuplet(Tuplet::TRIPLET);
+
+        assert_eq!(Duration::dotted(Quarter, 1).to_ticks(480), 720);
+        assert_eq!(triplet.to_ticks(480), 160);
+        assert_eq!(triplet.to_ticks(100), 33);
+        assert_eq!(Duration::dotted(SixtyFourth, 2).to_ticks(480), 52);
+        assert_eq!(Duration::new(Half).to_seconds(120.0), 1.0);
+        assert_eq!(Duration::dotted(Quarter, 1).to_seconds(90.0), 1.0);
+    }
+}
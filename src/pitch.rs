@@ -1,4 +1,9 @@
-use std::fmt::Display;
+use std::{
+    fmt::Display,
+    ops::{Add, Sub},
+};
+
+use crate::{interval::SpelledInterval, key::KeySignature};
 
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum PitchClass {
@@ -24,10 +29,89 @@ pub enum Accidental {
 pub struct Pitch {
     pub class: PitchClass,
     pub accidental: Accidental,
-    pub octave: u8,
+    /// Octave in scientific pitch notation, middle C is C4.
+    pub octave: i8,
+}
+
+/// Tuning of A4 in Hz.
+pub const CONCERT_A: f64 = 440.0;
+
+/// How to spell a pitch when it is computed from a number or respelled.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SpellingPreference {
+    /// Naturals and sharps, like C# and A#.
+    Sharps,
+    /// Naturals and flats, like Db and Bb.
+    Flats,
+    /// Notes of the key signature with this many sharps (or flats if it is
+    /// negative), so F is spelled E# with 6 sharps. Other notes are spelled
+    /// with sharps in sharp keys and with flats in flat keys. Counts beyond
+    /// 14 are spelled as 14.
+    KeySignature(i8),
+    /// The least accidentals, and the more common of the black keys: C# Eb
+    /// F# Ab Bb.
+    Simplest,
 }
 
+const SHARP_NAMES: [(PitchClass, Accidental); 12] = [
+    (PitchClass::C, Accidental::Natural),
+    (PitchClass::C, Accidental::Sharp),
+    (PitchClass::D, Accidental::Natural),
+    (PitchClass::D, Accidental::Sharp),
+    (PitchClass::E, Accidental::Natural),
+    (PitchClass::F, Accidental::Natural),
+    (PitchClass::F, Accidental::Sharp),
+    (PitchClass::G, Accidental::Natural),
+    (PitchClass::G, Accidental::Sharp),
+    (PitchClass::A, Accidental::Natural),
+    (PitchClass::A, Accidental::Sharp),
+    (PitchClass::B, Accidental::Natural),
+];
+
+const FLAT_NAMES: [(PitchClass, Accidental); 12] = [
+    (PitchClass::C, Accidental::Natural),
+    (PitchClass::D, Accidental::Flat),
+    (PitchClass::D, Accidental::Natural),
+    (PitchClass::E, Accidental::Flat),
+    (PitchClass::E, Accidental::Natural),
+    (PitchClass::F, Accidental::Natural),
+    (PitchClass::G, Accidental::Flat),
+    (PitchClass::G, Accidental::Natural),
+    (PitchClass::A, Accidental::Flat),
+    (PitchClass::A, Accidental::Natural),
+    (PitchClass::B, Accidental::Flat),
+    (PitchClass::B, Accidental::Natural),
+];
+
+const SIMPLEST_NAMES: [(PitchClass, Accidental); 12] = [
+    (PitchClass::C, Accidental::Natural),
+    (PitchClass::C, Accidental::Sharp),
+    (PitchClass::D, Accidental::Natural),
+    (PitchClass::E, Accidental::Flat),
+    (PitchClass::E, Accidental::Natural),
+    (PitchClass::F, Accidental::Natural),
+    (PitchClass::F, Accidental::Sharp),
+    (PitchClass::G, Accidental::Natural),
+    (PitchClass::A, Accidental::Flat),
+    (PitchClass::A, Accidental::Natural),
+    (PitchClass::B, Accidental::Flat),
+    (PitchClass::B, Accidental::Natural),
+];
+
 impl PitchClass {
+    pub fn parse(c: char) -> Option<Self> {
+        match c {
+            'C' => Some(PitchClass::C),
+            'D' => Some(PitchClass::D),
+            'E' => Some(PitchClass::E),
+            'F' => Some(PitchClass::F),
+            'G' => Some(PitchClass::G),
+            'A' => Some(PitchClass::A),
+            'B' => Some(PitchClass::B),
+            _ => None,
+        }
+    }
+
     pub fn next(&self) -> Self {
         match self {
             PitchClass::C => PitchClass::D,
@@ -39,6 +123,71 @@ impl PitchClass {
             PitchClass::B => PitchClass::C,
         }
     }
+
+    /// Position of the letter name in C D E F G A B.
+    pub fn index(&self) -> u8 {
+        match self {
+            PitchClass::C => 0,
+            PitchClass::D => 1,
+            PitchClass::E => 2,
+            PitchClass::F => 3,
+            PitchClass::G => 4,
+            PitchClass::A => 5,
+            PitchClass::B => 6,
+        }
+    }
+
+    pub fn from_index(index: u8) -> Self {
+        match index % 7 {
+            0 => PitchClass::C,
+            1 => PitchClass::D,
+            2 => PitchClass::E,
+            3 => PitchClass::F,
+            4 => PitchClass::G,
+            5 => PitchClass::A,
+            _ => PitchClass::B,
+        }
+    }
+}
+
+impl Accidental {
+    /// Parse the accidental at the beginning of the string, returns the
+    /// accidental (if any) and the rest of the string.
+    pub fn parse_prefix(s: &str) -> (Option<Self>, &str) {
+        let prefixes = [
+            ("𝄫", Accidental::DoubleFlat),
+            ("bb", Accidental::DoubleFlat),
+            ("b", Accidental::Flat),
+            ("♭", Accidental::Flat),
+            ("𝄪", Accidental::DoubleSharp),
+            ("x", Accidental::DoubleSharp),
+            ("##", Accidental::DoubleSharp),
+            ("#", Accidental::Sharp),
+            ("♯", Accidental::Sharp),
+            ("n", Accidental::Natural),
+            ("♮", Accidental::Natural),
+        ];
+
+        for (prefix, accidental) in prefixes {
+            if let Some(rest) = s.strip_prefix(prefix) {
+                return (Some(accidental), rest);
+            }
+        }
+
+        (None, s)
+    }
+
+    /// Accidental which raises or lowers by `offset` half notes.
+    pub fn from_offset(offset: i8) -> Option<Self> {
+        match offset {
+            -2 => Some(Accidental::DoubleFlat),
+            -1 => Some(Accidental::Flat),
+            0 => Some(Accidental::Natural),
+            1 => Some(Accidental::Sharp),
+            2 => Some(Accidental::DoubleSharp),
+            _ => None,
+        }
+    }
 }
 
 impl Pitch {
@@ -51,33 +200,12 @@ impl Pitch {
     }
 
     pub fn parse(s: &str) -> Option<Self> {
-        let mut chars = s.chars().peekable();
-
-        let class = match chars.next()? {
-            'C' => PitchClass::C,
-            'D' => PitchClass::D,
-            'E' => PitchClass::E,
-            'F' => PitchClass::F,
-            'G' => PitchClass::G,
-            'A' => PitchClass::A,
-            'B' => PitchClass::B,
-            _ => return None,
-        };
+        let mut chars = s.chars();
 
-        let accidental = match chars.peek() {
-            Some('𝄫') => Some(Accidental::DoubleFlat),
-            Some('b') => Some(Accidental::Flat),
-            Some('#') => Some(Accidental::Sharp),
-            Some('x') => Some(Accidental::DoubleSharp),
-            Some('n') => Some(Accidental::Natural),
-            _ => None,
-        };
+        let class = PitchClass::parse(chars.next()?)?;
+        let (accidental, rest) = Accidental::parse_prefix(chars.as_str());
 
-        if accidental.is_some() {
-            chars.next(); // consume the accidental
-        }
-
-        let octave = chars.collect::<String>().parse().ok()?;
+        let octave = rest.parse().ok()?;
 
         Some(Pitch {
             class,
@@ -96,25 +224,19 @@ impl Pitch {
     /// octave if the pitch overflows.
     pub fn build_next(&self, half_notes: i8) -> Self {
         let next_pitch_class = self.class.next();
-        let numeric_degree = self.to_numeric_scale_degree();
-
-        let mut raised_degree = numeric_degree + half_notes;
-
-        let raised_octave = if raised_degree > 11 {
-            raised_degree %= 12;
 
+        // The octave changes with the letter name, so Bb raised by half is
+        // Cb of the next octave.
+        let raised_octave = if self.class == PitchClass::B {
             self.octave + 1
         } else {
             self.octave
         };
 
-        let next_base = next_pitch_class as u8;
-
-        println!(
-            "[build_next] original: {self:?} raised by {half_notes} halves: {numeric_degree} -> {raised_degree}"
-        );
+        let raised = self.to_half_notes() + half_notes as i16;
+        let next_base = raised_octave as i16 * 12 + next_pitch_class as i16;
 
-        let accidental = match raised_degree - next_base as i8 {
+        let accidental = match raised - next_base {
             -2 => Accidental::DoubleFlat,
             -1 => Accidental::Flat,
             0 => Accidental::Natural,
@@ -139,112 +261,218 @@ impl Pitch {
         num + acc
     }
 
-    // Here we need to know that we tend to make a flat or sharp pitch note.
-    //pub fn add_interval(&self, interval: &Interval) -> Self {
-    //    let mut raised_octave = self.octave;
-    //    let mut raised = self.to_numeric_scale_degree() + *interval as u8;
+    /// Pitch class as a number from 0 to 11 regardless of the octave, so
+    /// B# is 0 and Cb is 11.
+    pub fn chroma(&self) -> i8 {
+        self.to_numeric_scale_degree().rem_euclid(12)
+    }
 
-    //    if raised > Interval::Major7th as u8 {
-    //        raised_octave += 1;
-    //        raised -= 12;
-    //    }
+    /// Number of half notes from C0, so B#3 and C4 are the same height.
+    pub fn to_half_notes(&self) -> i16 {
+        self.octave as i16 * 12 + self.to_numeric_scale_degree() as i16
+    }
 
-    //    Into::<Interval>::into(raised).to_pitch(raised_octave)
-    //}
+    /// Number of letter names from C0.
+    fn letter_steps(&self) -> i16 {
+        self.octave as i16 * 7 + self.class.index() as i16
+    }
 
-    pub fn same_pitch(&self, other: &Self) -> bool {
-        self.same_tone(other) && self.octave == other.octave
+    /// Raise the pitch by a spelled interval, the letter name follows the
+    /// interval number: C raised by an augmented 4th is F#, by a diminished
+    /// 5th is Gb. Returns `None` if the result would need more than two
+    /// accidentals or gets out of the octave range.
+    pub fn checked_add_interval(&self, interval: &SpelledInterval) -> Option<Self> {
+        let steps = self.letter_steps() + interval.steps() as i16;
+        let half_notes = self.to_half_notes() + interval.half_notes() as i16;
+
+        Pitch::from_steps(steps, half_notes)
     }
 
-    fn from(value: u8, is_sharp: bool) -> Self {
-        let mut pitch = Pitch {
-            class: PitchClass::C,
-            accidental: Accidental::Natural,
-            octave: 0,
+    /// Lower the pitch by a spelled interval.
+    pub fn checked_sub_interval(&self, interval: &SpelledInterval) -> Option<Self> {
+        let steps = self.letter_steps() - interval.steps() as i16;
+        let half_notes = self.to_half_notes() - interval.half_notes() as i16;
+
+        Pitch::from_steps(steps, half_notes)
+    }
+
+    /// Same as `checked_add_interval` but panics if the result cannot be
+    /// spelled.
+    pub fn add_interval(&self, interval: &SpelledInterval) -> Self {
+        self.checked_add_interval(interval)
+            .unwrap_or_else(|| panic!("Cannot spell {self:?} raised by {interval}"))
+    }
+
+    /// Same as `checked_sub_interval` but panics if the result cannot be
+    /// spelled.
+    pub fn sub_interval(&self, interval: &SpelledInterval) -> Self {
+        self.checked_sub_interval(interval)
+            .unwrap_or_else(|| panic!("Cannot spell {self:?} lowered by {interval}"))
+    }
+
+    /// Interval between the two pitches. If `other` is lower than `self`
+    /// the interval is measured from `other` up to `self`. Returns `None`
+    /// if the interval would be more than doubly augmented or diminished.
+    pub fn interval_to(&self, other: &Self) -> Option<SpelledInterval> {
+        let steps = other.letter_steps() - self.letter_steps();
+        let half_notes = other.to_half_notes() - self.to_half_notes();
+
+        let (steps, half_notes) = if steps < 0 || (steps == 0 && half_notes < 0) {
+            (-steps, -half_notes)
+        } else {
+            (steps, half_notes)
         };
 
-        // C1    B#0
-        // C#1   Db1
-        // D1    Cx1, Ebb1
-        // D#1   Eb1, Fbb1
-        // E1    Dx1, Fb1
-        // F1    E#1, Gbb1
-        // F#1   Gb1
-        // G1    Fx1, Abb1
-        // G#1   Ab1
-        // A1    Gx1, Bbb1
-        // A#1   Bb1, Cbb1
-        // B1    Ax1, Cb1
-        //
-        // Bb1 needs to find in the 2nd list, so basically we need to create
-        // equivalence groups
-
-        //let pitch_str = match value {
-        //    0 => "C0",
-        //    1 => {
-        //        if is_sharp {
-        //            "C#0"
-        //        } else {
-        //            "Db0"
-        //        }
-        //    }
-        //    2 => {
-        //        pitch.class = PitchClass::D;
-        //    }
-        //    3 => {
-        //        if is_sharp {
-        //            pitch.class = PitchClass::D;
-        //            pitch.accidental = Some(Accidental::Sharp);
-        //        }
-        //    }
-        //    4 => {
-        //        if is_sharp {
-        //            pitch.class = PitchClass::E;
-        //        } else {
-        //            pitch.class = PitchClass::F;
-        //            pitch.accidental = Some(Accidental::Flat);
-        //        }
-        //    }
-        //    5 => {
-        //        if is_sharp {
-        //            pitch.class = PitchClass::E;
-        //            pitch.accidental = Some(Accidental::Sharp);
-        //        } else {
-        //            pitch.class = PitchClass::F;
-        //        }
-        //    }
-        //    6 => {
-        //        if is_sharp {
-        //            pitch.class = PitchClass::F;
-        //            pitch.accidental = Some(Accidental::Sharp);
-        //        } else {
-        //            pitch.class = PitchClass::G;
-        //            pitch.accidental = Some(Accidental::Flat);
-        //        }
-        //    }
-        //    7 => PitchClass::G,
-        //    8 => {
-        //        if is_sharp {
-        //            Pitch::parse("G#0")
-        //        } else {
-        //            Pitch::parse("Ab0")
-        //        }
-        //    }
-
-        //    9 => PitchClass::A,
-        //    11 => PitchClass::B,
-        //    _ => unreachable!(),
-        //};
-
-        //Pitch::parse(pitch_str).expect("Cannot parse pitch")
-        pitch
-    }
-
-    fn same_tone(&self, other: &Self) -> bool {
-        let t1 = self.class as i8 - self.accidental as i8;
-        let t2 = other.class as i8 - other.accidental as i8;
-
-        t1 == t2
+        SpelledInterval::from_steps(u8::try_from(steps).ok()?, i8::try_from(half_notes).ok()?)
+    }
+
+    fn from_steps(steps: i16, half_notes: i16) -> Option<Self> {
+        let class = PitchClass::from_index(steps.rem_euclid(7) as u8);
+        let octave = i8::try_from(steps.div_euclid(7)).ok()?;
+        let natural = octave as i16 * 12 + class as i16;
+        let accidental = Accidental::from_offset(i8::try_from(half_notes - natural).ok()?)?;
+
+        Some(Pitch {
+            class,
+            accidental,
+            octave,
+        })
+    }
+
+    /// Spell the pitch which is `half_notes` above C0.
+    pub fn from_half_notes(half_notes: i16, preference: SpellingPreference) -> Self {
+        let names = match preference {
+            SpellingPreference::Sharps => &SHARP_NAMES,
+            SpellingPreference::Flats => &FLAT_NAMES,
+            SpellingPreference::Simplest => &SIMPLEST_NAMES,
+            SpellingPreference::KeySignature(count) => {
+                let key = KeySignature::new(count.clamp(-KeySignature::MAX, KeySignature::MAX))
+                    .unwrap_or_default();
+                let pitch = Pitch::from_half_notes(half_notes, SpellingPreference::Sharps);
+
+                if let Some(in_key) = pitch
+                    .enharmonics()
+                    .into_iter()
+                    .find(|p| p.accidental == key.accidental(p.class))
+                {
+                    return in_key;
+                }
+
+                if count < 0 { &FLAT_NAMES } else { &SHARP_NAMES }
+            }
+        };
+        let (class, accidental) = names[half_notes.rem_euclid(12) as usize];
+
+        Pitch {
+            class,
+            accidental,
+            octave: half_notes.div_euclid(12) as i8,
+        }
+    }
+
+    /// Spell the same sounding pitch in a different way, like Fb as E or
+    /// Cb4 as B3.
+    pub fn respell(&self, preference: SpellingPreference) -> Self {
+        Pitch::from_half_notes(self.to_half_notes(), preference)
+    }
+
+    /// All the spellings of the same sounding pitch with at most double
+    /// accidentals, from the lowest letter name: Cx D Ebb for D.
+    pub fn enharmonics(&self) -> Vec<Self> {
+        let steps = self.letter_steps();
+        let half_notes = self.to_half_notes();
+
+        (steps - 2..=steps + 2)
+            .filter_map(|s| Pitch::from_steps(s, half_notes))
+            .collect()
+    }
+
+    /// Check if the two pitches sound the same, like B#3 and C4.
+    pub fn is_enharmonic(&self, other: &Self) -> bool {
+        self.to_half_notes() == other.to_half_notes()
+    }
+
+    /// MIDI note number of the pitch, middle C (C4) is 60. Returns `None`
+    /// if the pitch is out of the MIDI range. B#3 is 60 as well as Cb4 is
+    /// 59.
+    pub fn to_midi(&self) -> Option<u8> {
+        u8::try_from(self.to_half_notes() + 12)
+            .ok()
+            .filter(|n| *n <= 127)
+    }
+
+    /// Spell the MIDI note number as a pitch.
+    pub fn from_midi(note: u8, preference: SpellingPreference) -> Self {
+        Pitch::from_half_notes(note as i16 - 12, preference)
+    }
+
+    /// Frequency of the pitch in equal temperament where A4 is `reference`
+    /// Hz (like `CONCERT_A` or 442).
+    pub fn frequency(&self, reference: f64) -> f64 {
+        let a4 = 4 * 12 + PitchClass::A as i16;
+
+        reference * 2f64.powf((self.to_half_notes() - a4) as f64 / 12.0)
+    }
+
+    /// Find the closest pitch of a frequency, returns the pitch and the
+    /// distance from it in cents (between -50 and 50). `None` is returned
+    /// when a frequency isn't positive and finite, or the pitch is out of
+    /// the octave range.
+    pub fn from_frequency(
+        frequency: f64,
+        reference: f64,
+        preference: SpellingPreference,
+    ) -> Option<(Self, f64)> {
+        let valid = |f: f64| f.is_finite() && f > 0.0;
+
+        if !valid(frequency) || !valid(reference) {
+            return None;
+        }
+
+        let a4 = 4 * 12 + PitchClass::A as i16;
+        let distance = 12.0 * (frequency / reference).log2();
+        let closest = distance.round();
+        let half_notes = a4 as f64 + closest;
+
+        if !(i8::MIN as f64 * 12.0..(i8::MAX as f64 + 1.0) * 12.0).contains(&half_notes) {
+            return None;
+        }
+
+        let pitch = Pitch::from_half_notes(half_notes as i16, preference);
+
+        Some((pitch, (distance - closest) * 100.0))
+    }
+
+    /// Check if the pitches sound the same, see `is_enharmonic`.
+    pub fn same_pitch(&self, other: &Self) -> bool {
+        self.is_enharmonic(other)
+    }
+}
+
+impl Add<SpelledInterval> for Pitch {
+    type Output = Pitch;
+
+    fn add(self, interval: SpelledInterval) -> Pitch {
+        self.add_interval(&interval)
+    }
+}
+
+impl Sub<SpelledInterval> for Pitch {
+    type Output = Pitch;
+
+    fn sub(self, interval: SpelledInterval) -> Pitch {
+        self.sub_interval(&interval)
+    }
+}
+
+/// Interval between two pitches, see `Pitch::interval_to`.
+impl Sub<Pitch> for Pitch {
+    type Output = SpelledInterval;
+
+    fn sub(self, other: Pitch) -> SpelledInterval {
+        other
+            .interval_to(&self)
+            .unwrap_or_else(|| panic!("Cannot spell interval between {other:?} and {self:?}"))
     }
 }
 
@@ -358,6 +586,8 @@ mod tests {
             ("Fb3", "G𝄫3", 1),
             ("F3", "Gb3", 1),
             ("F#3", "G3", 1),
+            ("Bb3", "Cb4", 1),
+            ("B#3", "C#4", 1),
         ];
 
         for (base, raised, halves) in pairs {
@@ -368,4 +598,177 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_add_interval() {
+        let triples = vec![
+            ("C4", "A4", "F#4"),
+            ("C4", "d5", "Gb4"),
+            ("Bb3", "M3", "D4"),
+            ("Bb3", "m7", "Ab4"),
+            ("B3", "m2", "C4"),
+            ("F#4", "M3", "A#4"),
+            ("E4", "M9", "F#5"),
+            ("C4", "P8", "C5"),
+            ("B#3", "m2", "C#4"),
+            ("Cb4", "M7", "Bb4"),
+        ];
+
+        for (base, interval, raised) in triples {
+            let interval = SpelledInterval::parse(interval).unwrap();
+
+            assert_eq!(
+                Pitch::parse(base).unwrap() + interval,
+                Pitch::parse(raised).unwrap(),
+                "{base} + {interval}"
+            );
+            assert_eq!(
+                Pitch::parse(raised).unwrap() - interval,
+                Pitch::parse(base).unwrap(),
+                "{raised} - {interval}"
+            );
+            assert_eq!(
+                Pitch::parse(raised).unwrap() - Pitch::parse(base).unwrap(),
+                interval,
+                "{raised} - {base}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_interval_to() {
+        let c4 = Pitch::parse("C4").unwrap();
+        let g3 = Pitch::parse("G3").unwrap();
+        let cb4 = Pitch::parse("Cb4").unwrap();
+
+        assert_eq!(c4.interval_to(&g3), SpelledInterval::parse("P4"));
+        assert_eq!(c4.interval_to(&cb4), SpelledInterval::parse("A1"));
+        assert_eq!(
+            Pitch::parse("Cx4")
+                .unwrap()
+                .checked_add_interval(&SpelledInterval::parse("A3").unwrap()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_midi_numbers() {
+        let pairs = vec![
+            ("C4", Some(60)),
+            ("A4", Some(69)),
+            ("B#3", Some(60)),
+            ("Cb4", Some(59)),
+            ("C-1", Some(0)),
+            ("G9", Some(127)),
+            ("G#9", None),
+            ("Cb-1", None),
+        ];
+
+        for (pitch, midi) in pairs {
+            assert_eq!(Pitch::parse(pitch).unwrap().to_midi(), midi, "{pitch}");
+        }
+
+        assert_eq!(
+            Pitch::from_midi(61, SpellingPreference::Sharps),
+            Pitch::parse("C#4").unwrap()
+        );
+        assert_eq!(
+            Pitch::from_midi(61, SpellingPreference::Flats),
+            Pitch::parse("Db4").unwrap()
+        );
+        assert_eq!(
+            Pitch::from_midi(0, SpellingPreference::Flats),
+            Pitch::parse("C-1").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_frequency() {
+        let a4 = Pitch::parse("A4").unwrap();
+
+        assert_eq!(a4.frequency(CONCERT_A), 440.0);
+        assert_eq!(a4.frequency(442.0), 442.0);
+        assert_eq!(Pitch::parse("A3").unwrap().frequency(CONCERT_A), 220.0);
+        assert!((Pitch::parse("C4").unwrap().frequency(CONCERT_A) - 261.626).abs() < 0.001);
+
+        let (pitch, cents) =
+            Pitch::from_frequency(445.0, CONCERT_A, SpellingPreference::Sharps).unwrap();
+
+        assert_eq!(pitch, a4);
+        assert!((cents - 19.56).abs() < 0.01);
+
+        let (pitch, _) =
+            Pitch::from_frequency(277.18, CONCERT_A, SpellingPreference::Flats).unwrap();
+
+        assert_eq!(pitch, Pitch::parse("Db4").unwrap());
+
+        for frequency in [0.0, -440.0, f64::NAN, f64::INFINITY, f64::MIN_POSITIVE] {
+            assert_eq!(
+                Pitch::from_frequency(frequency, CONCERT_A, SpellingPreference::Sharps),
+                None
+            );
+        }
+
+        assert_eq!(
+            Pitch::from_frequency(440.0, 0.0, SpellingPreference::Sharps),
+            None
+        );
+    }
+
+    #[test]
+    fn test_enharmonics() {
+        let spellings = |p: &str| -> Vec<String> {
+            Pitch::parse(p)
+                .unwrap()
+                .enharmonics()
+                .iter()
+                .map(|p| format!("{p}{}", p.octave))
+                .collect()
+        };
+
+        assert_eq!(spellings("D4"), vec!["C𝄪4", "D4", "E𝄫4"]);
+        assert_eq!(spellings("G#4"), vec!["G#4", "Ab4"]);
+        assert_eq!(spellings("C4"), vec!["B#3", "C4", "D𝄫4"]);
+        assert!(
+            Pitch::parse("B#3")
+                .unwrap()
+                .is_enharmonic(&Pitch::parse("C4").unwrap())
+        );
+        assert!(
+            Pitch::parse("E#4")
+                .unwrap()
+                .is_enharmonic(&Pitch::parse("F4").unwrap())
+        );
+        assert!(
+            !Pitch::parse("C#4")
+                .unwrap()
+                .is_enharmonic(&Pitch::parse("Db5").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_respell() {
+        let cases = vec![
+            ("Fb4", SpellingPreference::Sharps, "E4"),
+            ("A#4", SpellingPreference::Flats, "Bb4"),
+            ("Cb4", SpellingPreference::Simplest, "B3"),
+            ("D#4", SpellingPreference::Simplest, "Eb4"),
+            ("Gb4", SpellingPreference::Simplest, "F#4"),
+            ("F4", SpellingPreference::KeySignature(6), "E#4"),
+            ("B3", SpellingPreference::KeySignature(-6), "Cb4"),
+            ("Bb3", SpellingPreference::KeySignature(2), "A#3"),
+            ("A#3", SpellingPreference::KeySignature(-1), "Bb3"),
+            ("C4", SpellingPreference::KeySignature(-1), "C4"),
+            ("G4", SpellingPreference::KeySignature(9), "Fx4"),
+            ("G4", SpellingPreference::KeySignature(100), "Fx4"),
+        ];
+
+        for (pitch, preference, respelled) in cases {
+            assert_eq!(
+                Pitch::parse(pitch).unwrap().respell(preference),
+                Pitch::parse(respelled).unwrap(),
+                "{pitch} {preference:?}"
+            );
+        }
+    }
 }
@@ -0,0 +1,779 @@
+//! Roman numeral chord progressions like `I vi ii V7` or `V65/V`.
+use std::fmt::Display;
+
+use crate::{
+    chord::{self, Chord, ChordQuality},
+    interval::SpelledInterval,
+    pitch::{Accidental, Pitch},
+    scale::{Scale, ScaleDegree, ScaleType},
+    scales::{harmonic_minor, modes},
+};
+
+/// Quality of the chord shown by the case and the marks of the numeral.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumeralQuality {
+    Major,
+    Minor,
+    Diminished,
+    HalfDiminished,
+    Augmented,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RomanNumeral {
+    /// Chromatic alteration of the degree, like the flat of bVII.
+    pub accidental: Accidental,
+    /// Scale degree from 1 to 7.
+    pub degree: u8,
+    pub quality: NumeralQuality,
+    /// 5 for triads, 7 for seventh chords and 9 for ninth chords.
+    pub extension: u8,
+    /// 0 is root position, 1 is first inversion (I6 or V65), etc.
+    pub inversion: u8,
+    /// The chord which is tonicized by a secondary function, the ii of
+    /// viio7/ii.
+    pub secondary: Option<Box<RomanNumeral>>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Progression {
+    pub numerals: Vec<RomanNumeral>,
+}
+
+/// How a chord fits into the key.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HarmonicFunction {
+    /// All the chord tones are in the key.
+    Diatonic,
+    /// Secondary dominant or leading tone chord, like V7/V.
+    Secondary,
+    /// Chord borrowed from the parallel mode with this name, like bVI from
+    /// aeolian in a major key.
+    ModalMixture(String),
+    /// Chord which cannot be explained by the key.
+    Chromatic,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Analysis {
+    pub chord: Chord,
+    /// Numeral of the chord, `None` when its root has no degree in the key.
+    pub numeral: Option<RomanNumeral>,
+    pub function: HarmonicFunction,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RomanParseError {
+    Empty,
+    InvalidNumeral(String),
+    InvalidFigure(String),
+}
+
+const NUMERALS: [(&str, u8); 7] = [
+    ("VII", 7),
+    ("VI", 6),
+    ("IV", 4),
+    ("V", 5),
+    ("III", 3),
+    ("II", 2),
+    ("I", 1),
+];
+
+/// Figured bass of inversions with the extension and the inversion they
+/// mean.
+const FIGURES: [(&str, u8, u8); 8] = [
+    ("64", 5, 2),
+    ("65", 7, 1),
+    ("43", 7, 2),
+    ("42", 7, 3),
+    ("6", 5, 1),
+    ("7", 7, 0),
+    ("9", 9, 0),
+    ("2", 7, 3),
+];
+
+impl RomanNumeral {
+    /// Parse a numeral like `I`, `bVII`, `ii7`, `viiø7`, `V65` or `V7/V`.
+    pub fn parse(s: &str) -> Result<Self, RomanParseError> {
+        let invalid = || RomanParseError::InvalidNumeral(s.to_string());
+
+        let (numeral, secondary) = match s.split_once('/') {
+            Some((numeral, target)) => (numeral, Some(Box::new(RomanNumeral::parse(target)?))),
+            None => (s, None),
+        };
+
+        let (accidental, rest) = match Accidental::parse_prefix(numeral) {
+            (Some(Accidental::Natural), _) => return Err(invalid()),
+            (accidental, rest) => (accidental.unwrap_or(Accidental::Natural), rest),
+        };
+
+        let numeral_len = rest
+            .find(|c: char| !matches!(c, 'I' | 'V' | 'i' | 'v'))
+            .unwrap_or(rest.len());
+        let (letters, mut rest) = rest.split_at(numeral_len);
+
+        let degree = NUMERALS
+            .iter()
+            .find(|(n, _)| letters.eq_ignore_ascii_case(n))
+            .map(|(_, degree)| *degree)
+            .ok_or_else(invalid)?;
+
+        let upper = if letters.chars().all(|c| c.is_ascii_uppercase()) {
+            true
+        } else if letters.chars().all(|c| c.is_ascii_lowercase()) {
+            false
+        } else {
+            return Err(invalid());
+        };
+
+        let mut quality = if upper {
+            NumeralQuality::Major
+        } else {
+            NumeralQuality::Minor
+        };
+
+        for (mark, marked, needs_upper) in [
+            ("°", NumeralQuality::Diminished, false),
+            ("o", NumeralQuality::Diminished, false),
+            ("ø", NumeralQuality::HalfDiminished, false),
+            ("+", NumeralQuality::Augmented, true),
+        ] {
+            if let Some(r) = rest.strip_prefix(mark) {
+                if upper != needs_upper {
+                    return Err(invalid());
+                }
+
+                quality = marked;
+                rest = r;
+
+                break;
+            }
+        }
+
+        let (extension, inversion) = if rest.is_empty() {
+            (5, 0)
+        } else {
+            FIGURES
+                .iter()
+                .find(|(figure, _, _)| *figure == rest)
+                .map(|(_, extension, inversion)| (*extension, *inversion))
+                .ok_or_else(|| RomanParseError::InvalidFigure(s.to_string()))?
+        };
+
+        // Half diminished chord is always a seventh chord
+        let extension = if quality == NumeralQuality::HalfDiminished {
+            extension.max(7)
+        } else {
+            extension
+        };
+
+        Ok(RomanNumeral {
+            accidental,
+            degree,
+            quality,
+            extension,
+            inversion,
+            secondary,
+        })
+    }
+
+    /// Scale which is used for the numeral: the key itself or the key of
+    /// the chord tonicized by a secondary function. Major chords are
+    /// tonicized by a major scale, the others by harmonic minor.
+    fn tonic_scale(&self, key: &Scale) -> Option<Scale> {
+        let Some(target) = &self.secondary else {
+            return Some(key.clone());
+        };
+
+        let target_root = target.root(key)?;
+        let scale_type = match target.quality {
+            NumeralQuality::Major | NumeralQuality::Augmented => ScaleType {
+                name: "ionian".to_string(),
+                scale_degrees: modes::IONIAN.to_vec(),
+            },
+            _ => ScaleType {
+                name: "harmonic minor".to_string(),
+                scale_degrees: harmonic_minor::AEOLIAN_HARMONIC.to_vec(),
+            },
+        };
+
+        Scale::checked_new(&target_root, &scale_type)
+    }
+
+    /// Root of the chord in the key.
+    pub fn root(&self, key: &Scale) -> Option<Pitch> {
+        let scale = self.tonic_scale(key)?;
+        let pitch = *scale.pitches.get((self.degree as usize).checked_sub(1)?)?;
+
+        let accidental = Accidental::from_offset(pitch.accidental as i8 + self.accidental as i8)?;
+
+        Some(Pitch {
+            accidental,
+            ..pitch
+        })
+    }
+
+    /// Interval of the scale tone `steps` degrees above the root degree,
+    /// only if the numeral is not chromatic.
+    fn diatonic_interval(
+        &self,
+        scale: &Scale,
+        root: &Pitch,
+        steps: usize,
+    ) -> Option<SpelledInterval> {
+        if self.accidental != Accidental::Natural {
+            return None;
+        }
+
+        let len = scale.pitches.len();
+        let index = (self.degree as usize).checked_sub(1)? + steps;
+        let mut pitch = *scale.pitches.get(index.checked_rem(len)?)?;
+
+        for _ in 0..index / len {
+            pitch = pitch.checked_add_interval(&SpelledInterval::OCTAVE)?;
+        }
+
+        root.interval_to(&pitch)
+    }
+
+    /// Build the chord of the numeral in a key. The quality of the triad
+    /// comes from the numeral, the 7th and 9th come from the scale (IV7 is
+    /// a major seventh chord in major) unless the marks say otherwise.
+    pub fn realize(&self, key: &Scale) -> Option<Chord> {
+        let scale = self.tonic_scale(key)?;
+        let root = self.root(key)?;
+
+        let mut intervals = match self.quality {
+            NumeralQuality::Major => vec![SpelledInterval::MAJOR_3RD, SpelledInterval::PERFECT_5TH],
+            NumeralQuality::Minor => vec![SpelledInterval::MINOR_3RD, SpelledInterval::PERFECT_5TH],
+            NumeralQuality::Diminished | NumeralQuality::HalfDiminished => {
+                vec![SpelledInterval::MINOR_3RD, SpelledInterval::DIMINISHED_5TH]
+            }
+            NumeralQuality::Augmented => {
+                vec![SpelledInterval::MAJOR_3RD, SpelledInterval::AUGMENTED_5TH]
+            }
+        };
+
+        intervals.insert(0, SpelledInterval::UNISON);
+
+        if self.extension >= 7 {
+            let seventh = match self.quality {
+                NumeralQuality::Diminished => SpelledInterval::DIMINISHED_7TH,
+                NumeralQuality::HalfDiminished => SpelledInterval::MINOR_7TH,
+                _ => match self.diatonic_interval(&scale, &root, 6) {
+                    Some(SpelledInterval::MAJOR_7TH) => SpelledInterval::MAJOR_7TH,
+                    _ => SpelledInterval::MINOR_7TH,
+                },
+            };
+
+            intervals.push(seventh);
+        }
+
+        if self.extension >= 9 {
+            let ninth = match self.diatonic_interval(&scale, &root, 8) {
+                Some(SpelledInterval::MINOR_9TH) => SpelledInterval::MINOR_9TH,
+                _ => SpelledInterval::MAJOR_9TH,
+            };
+
+            intervals.push(ninth);
+        }
+
+        let mut chord = Chord::from_intervals(root, &intervals)?;
+
+        if self.inversion > 0 {
+            chord.bass = chord.chord_tones().get(self.inversion as usize).copied();
+        }
+
+        Some(chord)
+    }
+}
+
+impl Progression {
+    /// Parse numerals separated by spaces, dashes or bar lines, like
+    /// `I vi ii V7` or `I - IV - V`.
+    pub fn parse(s: &str) -> Result<Self, RomanParseError> {
+        let numerals = s
+            .split(|c: char| c.is_whitespace() || matches!(c, '-' | '|' | ','))
+            .filter(|n| !n.is_empty())
+            .map(RomanNumeral::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        if numerals.is_empty() {
+            return Err(RomanParseError::Empty);
+        }
+
+        Ok(Progression { numerals })
+    }
+
+    /// Chords of the progression in a key, `None` if some numeral cannot
+    /// be spelled in that key.
+    pub fn realize(&self, key: &Scale) -> Option<Vec<Chord>> {
+        self.numerals.iter().map(|n| n.realize(key)).collect()
+    }
+}
+
+/// Parallel modes where the borrowed chords are looked for, the most
+/// common sources first.
+const PARALLEL_MODES: [(&str, [ScaleDegree; 7]); 8] = [
+    ("aeolian", modes::AEOLIAN),
+    ("harmonic minor", harmonic_minor::AEOLIAN_HARMONIC),
+    ("ionian", modes::IONIAN),
+    ("mixolydian", modes::MIXOLYDIAN),
+    ("dorian", modes::DORIAN),
+    ("phrygian", modes::PHRYGIAN),
+    ("lydian", modes::LYDIAN),
+    ("locrian", modes::LOCRIAN),
+];
+
+/// Give the Roman numeral and the function of each chord in the key.
+pub fn analyze(key: &Scale, chords: &[Chord]) -> Vec<Analysis> {
+    chords
+        .iter()
+        .map(|chord| analyze_chord(key, chord))
+        .collect()
+}
+
+/// Same as `analyze` but the chords are identified from the pitches first.
+/// `None` is returned for the pitch sets which are not chords.
+pub fn analyze_pitches(key: &Scale, pitch_sets: &[Vec<Pitch>]) -> Vec<Option<Analysis>> {
+    pitch_sets
+        .iter()
+        .map(|pitches| {
+            chord::identify(pitches)
+                .into_iter()
+                .next()
+                .map(|m| analyze_chord(key, &m.chord))
+        })
+        .collect()
+}
+
+/// Check if all the chord tones are in the scale with the same spelling.
+fn contains_chord(scale: &Scale, chord: &Chord) -> bool {
+    chord.chord_tones().iter().all(|tone| {
+        scale
+            .pitches
+            .iter()
+            .any(|p| p.class == tone.class && p.accidental == tone.accidental)
+    })
+}
+
+/// Degree and chromatic alteration of a pitch in the key, so Ab is b6 in
+/// C major.
+fn degree_of(key: &Scale, pitch: &Pitch) -> Option<(u8, Accidental)> {
+    let steps = (pitch.class.index() as i8 - key.root_note.class.index() as i8).rem_euclid(7);
+    let scale_pitch = key.pitches.get(steps as usize)?;
+    let offset = (pitch.chroma() - scale_pitch.chroma() + 6).rem_euclid(12) - 6;
+
+    Some((steps as u8 + 1, Accidental::from_offset(offset)?))
+}
+
+/// Numeral of a chord on the given degree, the quality and figures come
+/// from the chord.
+fn numeral_of(chord: &Chord, degree: u8, accidental: Accidental) -> RomanNumeral {
+    let quality = match chord.quality {
+        ChordQuality::Minor
+        | ChordQuality::Minor6
+        | ChordQuality::Minor7
+        | ChordQuality::MinorMajor7
+        | ChordQuality::MinorSixNine
+        | ChordQuality::Minor9
+        | ChordQuality::Minor11
+        | ChordQuality::Minor13 => NumeralQuality::Minor,
+        ChordQuality::Diminished | ChordQuality::Diminished7 => NumeralQuality::Diminished,
+        ChordQuality::HalfDiminished7 => NumeralQuality::HalfDiminished,
+        ChordQuality::Augmented | ChordQuality::Augmented7 | ChordQuality::AugmentedMajor7 => {
+            NumeralQuality::Augmented
+        }
+        _ => NumeralQuality::Major,
+    };
+
+    let intervals = chord.quality.intervals();
+    let extension = if intervals.iter().any(|i| i.number == 9) && chord.quality.has_seventh() {
+        9
+    } else if chord.quality.has_seventh() {
+        7
+    } else {
+        5
+    };
+
+    let inversion = chord
+        .bass
+        .and_then(|bass| {
+            chord
+                .chord_tones()
+                .iter()
+                .position(|tone| tone.chroma() == bass.chroma())
+        })
+        .unwrap_or(0) as u8;
+
+    RomanNumeral {
+        accidental,
+        degree,
+        quality,
+        extension,
+        inversion,
+        secondary: None,
+    }
+}
+
+/// Find the degree which is tonicized by the chord as a secondary dominant
+/// or leading tone chord. Only major and minor triads can be tonicized.
+fn secondary_target(key: &Scale, chord: &Chord) -> Option<RomanNumeral> {
+    let dominant = matches!(
+        chord.quality,
+        ChordQuality::Major | ChordQuality::Dominant7 | ChordQuality::Dominant9
+    );
+    let leading_tone = matches!(
+        chord.quality,
+        ChordQuality::Diminished | ChordQuality::Diminished7 | ChordQuality::HalfDiminished7
+    );
+
+    if !dominant && !leading_tone {
+        return None;
+    }
+
+    let triads = key.triads();
+
+    for degree in 2..=key.pitches.len().min(7) as u8 {
+        let Some(Some(target)) = triads.get(degree as usize - 1) else {
+            continue;
+        };
+        let quality = match target.quality {
+            ChordQuality::Major => NumeralQuality::Major,
+            ChordQuality::Minor => NumeralQuality::Minor,
+            _ => continue,
+        };
+
+        let tonicizing_root = if dominant {
+            target
+                .root
+                .checked_add_interval(&SpelledInterval::PERFECT_5TH)
+        } else {
+            target
+                .root
+                .checked_sub_interval(&SpelledInterval::MINOR_2ND)
+        };
+
+        if tonicizing_root
+            .is_some_and(|r| r.class == chord.root.class && r.accidental == chord.root.accidental)
+        {
+            return Some(RomanNumeral {
+                accidental: Accidental::Natural,
+                degree,
+                quality,
+                extension: 5,
+                inversion: 0,
+                secondary: None,
+            });
+        }
+    }
+
+    None
+}
+
+fn analyze_chord(key: &Scale, chord: &Chord) -> Analysis {
+    let Some((degree, accidental)) = degree_of(key, &chord.root) else {
+        return Analysis {
+            chord: chord.clone(),
+            numeral: None,
+            function: HarmonicFunction::Chromatic,
+        };
+    };
+    let mut numeral = numeral_of(chord, degree, accidental);
+
+    let function = if accidental == Accidental::Natural && contains_chord(key, chord) {
+        HarmonicFunction::Diatonic
+    } else if let Some(target) = secondary_target(key, chord) {
+        numeral.accidental = Accidental::Natural;
+        numeral.degree = if numeral.quality == NumeralQuality::Major {
+            5
+        } else {
+            7
+        };
+        numeral.secondary = Some(Box::new(target));
+
+        HarmonicFunction::Secondary
+    } else if let Some((name, _)) = PARALLEL_MODES.iter().find(|(_, degrees)| {
+        let parallel = Scale::checked_new(
+            &key.root_note,
+            &ScaleType {
+                name: String::new(),
+                scale_degrees: degrees.to_vec(),
+            },
+        );
+
+        parallel.is_some_and(|parallel| contains_chord(&parallel, chord))
+    }) {
+        HarmonicFunction::ModalMixture(name.to_string())
+    } else {
+        HarmonicFunction::Chromatic
+    };
+
+    Analysis {
+        chord: chord.clone(),
+        numeral: Some(numeral),
+        function,
+    }
+}
+
+impl Display for RomanNumeral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let numeral = NUMERALS
+            .iter()
+            .find(|(_, degree)| *degree == self.degree)
+            .map(|(numeral, _)| *numeral)
+            .unwrap_or("?");
+
+        if self.accidental != Accidental::Natural {
+            f.write_fmt(format_args!("{}", self.accidental))?;
+        }
+
+        match self.quality {
+            NumeralQuality::Major | NumeralQuality::Augmented => f.write_str(numeral)?,
+            _ => f.write_str(&numeral.to_lowercase())?,
+        }
+
+        match self.quality {
+            NumeralQuality::Diminished => f.write_str("°")?,
+            NumeralQuality::HalfDiminished => f.write_str("ø")?,
+            NumeralQuality::Augmented => f.write_str("+")?,
+            _ => {}
+        }
+
+        let figure = FIGURES
+            .iter()
+            .find(|(_, extension, inversion)| {
+                *extension == self.extension && *inversion == self.inversion
+            })
+            .map(|(figure, _, _)| *figure)
+            .unwrap_or("");
+
+        f.write_str(figure)?;
+
+        if let Some(target) = &self.secondary {
+            f.write_fmt(format_args!("/{target}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for Progression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, numeral) in self.numerals.iter().enumerate() {
+            if i > 0 {
+                f.write_str(" ")?;
+            }
+
+            f.write_fmt(format_args!("{numeral}"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Display for RomanParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RomanParseError::Empty => f.write_str("empty progression"),
+            RomanParseError::InvalidNumeral(s) => write!(f, "invalid roman numeral {s}"),
+            RomanParseError::InvalidFigure(s) => write!(f, "invalid figure in {s}"),
+        }
+    }
+}
+
+impl std::error::Error for RomanParseError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(root: &str, name: &str, degrees: &[crate::scale::ScaleDegree]) -> Scale {
+        Scale::from_pitch_string(
+            root,
+            &ScaleType {
+                name: name.to_string(),
+                scale_degrees: degrees.to_vec(),
+            },
+        )
+    }
+
+    fn realized(progression: &str, key: &Scale) -> Vec<String> {
+        Progression::parse(progression)
+            .unwrap()
+            .realize(key)
+            .unwrap()
+            .iter()
+            .map(|c| c.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_realize_in_major() {
+        let c_major = key("C4", "ionian", &modes::IONIAN);
+
+        assert_eq!(
+            realized("I vi ii V7 I", &c_major),
+            vec!["C", "Am", "Dm", "G7", "C"]
+        );
+        assert_eq!(
+            realized("I7 IV7 viiø7 iii7", &c_major),
+            vec!["Cmaj7", "Fmaj7", "Bm7b5", "Em7"]
+        );
+        assert_eq!(
+            realized("I6 V65 I64 V42 bVII", &c_major),
+            vec!["C/E", "G7/B", "C/G", "G7/F", "Bb"]
+        );
+        assert_eq!(
+            realized("V7/V viio7/ii V/vi V7/IV", &c_major),
+            vec!["D7", "C#dim7", "E", "C7"]
+        );
+
+        let mut numeral = RomanNumeral::parse("I").unwrap();
+        numeral.degree = 0;
+
+        assert_eq!(numeral.root(&c_major), None);
+        assert_eq!(numeral.realize(&c_major), None);
+    }
+
+    #[test]
+    fn test_realize_in_modes() {
+        let f_lydian = key("F3", "lydian", &modes::LYDIAN);
+
+        assert_eq!(realized("I iv V III", &f_lydian), vec!["F", "Bm", "C", "A"]);
+
+        let a_minor = key("A3", "aeolian", &modes::AEOLIAN);
+
+        assert_eq!(
+            realized("i iv V7 i VI III+", &a_minor),
+            vec!["Am", "Dm", "E7", "Am", "F", "Caug"]
+        );
+        assert_eq!(realized("V9", &a_minor), vec!["E7b9"]);
+    }
+
+    fn analyzed(key: &Scale, chords: &str) -> Vec<(String, HarmonicFunction)> {
+        let chords: Vec<Chord> = chords
+            .split_whitespace()
+            .map(|c| Chord::parse(c).unwrap())
+            .collect();
+
+        analyze(key, &chords)
+            .into_iter()
+            .map(|a| (a.numeral.unwrap().to_string(), a.function))
+            .collect()
+    }
+
+    #[test]
+    fn test_analyze_chords() {
+        let c_major = key("C4", "ionian", &modes::IONIAN);
+
+        assert_eq!(
+            analyzed(&c_major, "C Am/C Dm7 G7/B Bm7b5 Cmaj7"),
+            vec![
+                ("I".to_string(), HarmonicFunction::Diatonic),
+                ("vi6".to_string(), HarmonicFunction::Diatonic),
+                ("ii7".to_string(), HarmonicFunction::Diatonic),
+                ("V65".to_string(), HarmonicFunction::Diatonic),
+                ("viiø7".to_string(), HarmonicFunction::Diatonic),
+                ("I7".to_string(), HarmonicFunction::Diatonic),
+            ]
+        );
+        assert_eq!(
+            analyzed(&c_major, "D7 C#dim7 E Fm Ab Bb F#"),
+            vec![
+                ("V7/V".to_string(), HarmonicFunction::Secondary),
+                ("vii°7/ii".to_string(), HarmonicFunction::Secondary),
+                ("V/vi".to_string(), HarmonicFunction::Secondary),
+                (
+                    "iv".to_string(),
+                    HarmonicFunction::ModalMixture("aeolian".to_string())
+                ),
+                (
+                    "bVI".to_string(),
+                    HarmonicFunction::ModalMixture("aeolian".to_string())
+                ),
+                (
+                    "bVII".to_string(),
+                    HarmonicFunction::ModalMixture("aeolian".to_string())
+                ),
+                ("#IV".to_string(), HarmonicFunction::Chromatic),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_analyze_outside_degrees() {
+        let degree = |degree, modifier| ScaleDegree { degree, modifier };
+        let degrees = [
+            degree(1, Accidental::Natural),
+            degree(2, Accidental::Natural),
+            degree(3, Accidental::DoubleFlat),
+            degree(4, Accidental::Natural),
+            degree(5, Accidental::Natural),
+            degree(6, Accidental::Natural),
+            degree(7, Accidental::Natural),
+        ];
+        // E# is three half notes above the third degree Ebb
+        let analysis = analyze(
+            &key("C4", "altered", &degrees),
+            &[Chord::parse("E#").unwrap()],
+        );
+
+        assert_eq!(analysis[0].numeral, None);
+        assert_eq!(analysis[0].function, HarmonicFunction::Chromatic);
+    }
+
+    #[test]
+    fn test_analyze_pitches() {
+        let a_minor = key("A3", "aeolian", &modes::AEOLIAN);
+        let pitch_sets = vec![
+            crate::scale::scale_from_string("A2 C3 E3 A3"),
+            crate::scale::scale_from_string("G#2 B2 D3 E3"),
+            crate::scale::scale_from_string("C3"),
+        ];
+
+        let analysis = analyze_pitches(&a_minor, &pitch_sets);
+
+        assert_eq!(
+            analysis[0]
+                .as_ref()
+                .unwrap()
+                .numeral
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "i"
+        );
+        assert_eq!(
+            analysis[1]
+                .as_ref()
+                .unwrap()
+                .numeral
+                .as_ref()
+                .unwrap()
+                .to_string(),
+            "V65"
+        );
+        assert_eq!(
+            analysis[1].as_ref().unwrap().function,
+            HarmonicFunction::ModalMixture("harmonic minor".to_string())
+        );
+        assert_eq!(analysis[2], None);
+    }
+
+    #[test]
+    fn test_parse_numerals() {
+        let progression = Progression::parse("I - #iv°7 | viiø7/V V+ bVI9").unwrap();
+
+        assert_eq!(progression.to_string(), "I #iv°7 viiø7/V V+ bVI9");
+        assert_eq!(
+            RomanNumeral::parse("Iv"),
+            Err(RomanParseError::InvalidNumeral("Iv".to_string()))
+        );
+        assert_eq!(
+            RomanNumeral::parse("V8"),
+            Err(RomanParseError::InvalidFigure("V8".to_string()))
+        );
+        assert_eq!(Progression::parse("  "), Err(RomanParseError::Empty));
+    }
+}
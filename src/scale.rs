@@ -1,14 +1,18 @@
 use std::fmt::Display;
 
-use crate::pitch::{Accidental, Pitch};
+use crate::{
+    chord::Chord,
+    interval::SpelledInterval,
+    pitch::{Accidental, Pitch},
+};
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ScaleDegree {
     pub degree: u8,
     pub modifier: Accidental,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ScaleType {
     pub name: String,
     pub scale_degrees: Vec<ScaleDegree>,
@@ -21,10 +25,22 @@ pub struct Scale {
     pub pitches: Vec<Pitch>,
 }
 
+/// Direction of playing a scale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Ascending,
+    Descending,
+    /// Up to the top and back to the root.
+    AscendingDescending,
+}
+
 /// Interval distances of scale degrees.
 pub const SCALE_NATURAL_DEGREES: [u8; 7] = [0, 2, 4, 5, 7, 9, 11];
 
 impl Scale {
+    /// Scale of the type from the root. Panics if a degree cannot be
+    /// spelled from the root, use `checked_new` for scales which are not
+    /// known in advance.
     pub fn new(root_note: &Pitch, scale_type: &ScaleType) -> Self {
         Scale {
             root_note: *root_note,
@@ -33,49 +49,200 @@ impl Scale {
         }
     }
 
+    /// Same as `new` but returns `None` if a degree cannot be spelled from
+    /// the root, like a diminished 3rd above Cb.
+    pub fn checked_new(root_note: &Pitch, scale_type: &ScaleType) -> Option<Self> {
+        let pitches = scale_type
+            .scale_degrees
+            .iter()
+            .map(|degree| root_note.checked_add_interval(&degree.interval()?))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Scale {
+            root_note: *root_note,
+            scale_type: scale_type.clone(),
+            pitches,
+        })
+    }
+
     pub fn from_pitch_string(root: &str, scale_type: &ScaleType) -> Self {
         let p = Pitch::parse(root).unwrap();
 
         Scale::new(&p, scale_type)
     }
 
+    /// Half notes of each degree from the root. The degrees can be in any
+    /// number and repeat a degree number, like b3 and 3 in the blues scale.
     pub fn to_numeric_degrees(degrees: &[ScaleDegree]) -> Vec<i8> {
-        let mut result = vec![0];
+        degrees.iter().map(ScaleDegree::half_notes).collect()
+    }
+
+    /// Pitches of the degrees from the root. Each degree is spelled with its
+    /// own letter name, so #4 from C is F# and b5 is Gb.
+    pub fn build_scale(root_note: &Pitch, degrees: &[ScaleDegree]) -> Vec<Pitch> {
+        degrees
+            .iter()
+            .map(|degree| {
+                let interval = degree
+                    .interval()
+                    .unwrap_or_else(|| panic!("Degree {degree} is not an interval"));
+
+                root_note.add_interval(&interval)
+            })
+            .collect()
+    }
+}
+
+impl ScaleDegree {
+    pub const fn new(degree: u8, modifier: Accidental) -> Self {
+        ScaleDegree { degree, modifier }
+    }
+
+    /// Half notes from the root, degrees past 7 are in the next octaves.
+    /// Degrees too high for `i8` give `i8::MAX`.
+    pub fn half_notes(&self) -> i8 {
+        let steps = self.degree.saturating_sub(1) as i16;
+        let half_notes = SCALE_NATURAL_DEGREES[steps as usize % 7] as i16
+            + 12 * (steps / 7)
+            + self.modifier as i16;
 
-        for i in 1..degrees.len() {
-            let nd = SCALE_NATURAL_DEGREES[i] as i8 + degrees[i].modifier as i8;
+        half_notes.min(i8::MAX as i16) as i8
+    }
+
+    /// Interval from the root, `None` if it would be more than doubly
+    /// augmented or diminished.
+    pub fn interval(&self) -> Option<SpelledInterval> {
+        SpelledInterval::from_steps(self.degree.saturating_sub(1), self.half_notes())
+    }
+}
 
-            result.push(nd);
+impl ScaleType {
+    /// Mode starting from the degree `n + 1` of the scale, so the mode 1 of
+    /// ionian is dorian. The mode is named after the parent and its number.
+    /// Returns `None` if `n` is past the last degree or a degree of the mode
+    /// would need more than two accidentals.
+    pub fn mode(&self, n: usize) -> Option<ScaleType> {
+        let len = self.scale_degrees.len();
+
+        if n >= len {
+            return None;
         }
 
-        result
+        let half_notes = Scale::to_numeric_degrees(&self.scale_degrees);
+        let first = self.scale_degrees[n].degree as i16;
+        let scale_degrees = (0..len)
+            .map(|i| {
+                let index = (i + n) % len;
+                let degree =
+                    (self.scale_degrees[index].degree as i16 - first).rem_euclid(7) as u8 + 1;
+                let from_root = (half_notes[index] - half_notes[n]).rem_euclid(12);
+                let natural = SCALE_NATURAL_DEGREES[degree as usize - 1] as i8;
+
+                Some(ScaleDegree {
+                    degree,
+                    modifier: Accidental::from_offset(from_root - natural)?,
+                })
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let name = if n == 0 {
+            self.name.clone()
+        } else {
+            format!("{} mode {}", self.name, n + 1)
+        };
+
+        Some(ScaleType {
+            name,
+            scale_degrees,
+        })
     }
 
-    pub fn build_scale(root_note: &Pitch, degrees: &[ScaleDegree]) -> Vec<Pitch> {
-        let mut result = vec![*root_note];
-        let mut i = 1;
-        let mut last_pitch = *root_note;
-        let mut last_degree = 0i8;
-        let numeric_degrees = Scale::to_numeric_degrees(degrees);
+    /// All the modes of the scale from the first degree, the first one is
+    /// the scale itself.
+    pub fn modes(&self) -> Vec<ScaleType> {
+        (0..self.scale_degrees.len())
+            .filter_map(|n| self.mode(n))
+            .collect()
+    }
+}
+
+impl Scale {
+    /// Pitches of the scale played through `octaves` octaves and ending on
+    /// the root, like the scale exercises.
+    pub fn run(&self, octaves: u8, direction: Direction) -> Vec<Pitch> {
+        let mut ascending = vec![];
+        let mut octave_root = self.root_note;
 
-        loop {
-            let next_degree = degrees[i];
-            let next_num_degree = numeric_degrees[(next_degree.degree - 1) as usize];
-            let half_notes = next_num_degree - last_degree;
-            let next_pitch = last_pitch.build_next(half_notes);
+        for octave in 0..octaves {
+            for pitch in &self.pitches {
+                let mut pitch = *pitch;
 
-            last_pitch = next_pitch;
-            last_degree = next_num_degree;
+                for _ in 0..octave {
+                    pitch = pitch.add_interval(&SpelledInterval::OCTAVE);
+                }
+
+                ascending.push(pitch);
+            }
+
+            octave_root = octave_root.add_interval(&SpelledInterval::OCTAVE);
+        }
 
-            result.push(next_pitch);
+        ascending.push(octave_root);
 
-            i += 1;
+        match direction {
+            Direction::Ascending => ascending,
+            Direction::Descending => ascending.into_iter().rev().collect(),
+            Direction::AscendingDescending => {
+                let descending: Vec<Pitch> = ascending.iter().rev().skip(1).copied().collect();
 
-            if i == degrees.len() {
-                break result;
+                ascending.extend(descending);
+                ascending
             }
         }
     }
+
+    /// Triads built on each degree by stacking thirds of the scale. The
+    /// triad of degree `i + 1` is at index `i`, and is `None` when its
+    /// tones don't form a known chord. Only seven note scales have triads.
+    pub fn triads(&self) -> Vec<Option<Chord>> {
+        self.stack_thirds(3)
+    }
+
+    /// Seventh chords built on each degree by stacking thirds of the scale,
+    /// so C ionian gives Cmaj7 Dm7 Em7 Fmaj7 G7 Am7 Bm7b5.
+    pub fn seventh_chords(&self) -> Vec<Option<Chord>> {
+        self.stack_thirds(4)
+    }
+
+    /// Stack `count` thirds from each degree. The chord tones skip every
+    /// other pitch of the scale, which are thirds only in seven note
+    /// scales, so the other scales have no chords.
+    fn stack_thirds(&self, count: usize) -> Vec<Option<Chord>> {
+        let len = self.pitches.len();
+
+        if len != 7 {
+            return vec![];
+        }
+
+        (0..len)
+            .map(|degree| {
+                let root = self.pitches[degree];
+                let intervals = (0..count)
+                    .map(|i| {
+                        let index = degree + 2 * i;
+                        let mut pitch = self.pitches[index % len];
+
+                        for _ in 0..index / len {
+                            pitch = pitch.add_interval(&SpelledInterval::OCTAVE);
+                        }
+
+                        root.interval_to(&pitch)
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+
+                Chord::from_intervals(root, &intervals)
+            })
+            .collect()
+    }
 }
 
 impl Display for ScaleDegree {
@@ -105,9 +272,6 @@ impl Display for Scale {
     }
 }
 
-// TODO implement a feature like, one F lydian I want chord progression:
-// I iv V III or something
-
 // Here we need to learn const generic
 
 //pub fn compute_scale(root_note: &Pitch, scale: &[ScaleDegree; 7]) -> Vec<Pitch> {
@@ -140,7 +304,24 @@ pub fn scale_from_string(scale: &str) -> Vec<Pitch> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{modes, pitch::PitchClass};
+    use crate::{
+        pitch::PitchClass,
+        scales::{bebop, harmonic_minor, melodic_minor, modes, pentatonic, symmetric},
+    };
+
+    fn scale_type(name: &str, degrees: &[ScaleDegree]) -> ScaleType {
+        ScaleType {
+            name: name.to_string(),
+            scale_degrees: degrees.to_vec(),
+        }
+    }
+
+    fn symbols(chords: &[Option<Chord>]) -> Vec<String> {
+        chords
+            .iter()
+            .map(|c| c.as_ref().map_or("-".to_string(), |c| c.to_string()))
+            .collect()
+    }
 
     #[test]
     fn compute_scale_test() {
@@ -152,8 +333,231 @@ mod tests {
         let a_ionian = Scale::new(&a, &ionian);
 
         assert_eq!(
-            scale_from_string("A1 B1 C#1 D1 E1 F#1 G#1"),
+            scale_from_string("A1 B1 C#2 D2 E2 F#2 G#2"),
             a_ionian.pitches
         );
     }
+
+    #[test]
+    fn test_modes() {
+        let degrees = |scale_type: &ScaleType| scale_type.scale_degrees.clone();
+
+        let church = scale_type("ionian", &modes::IONIAN).modes();
+        let hand_written = [
+            modes::IONIAN,
+            modes::DORIAN,
+            modes::PHRYGIAN,
+            modes::LYDIAN,
+            modes::MIXOLYDIAN,
+            modes::AEOLIAN,
+            modes::LOCRIAN,
+        ];
+
+        assert_eq!(church.len(), 7);
+        assert_eq!(church[1].name, "ionian mode 2");
+
+        for (mode, expected) in church.iter().zip(hand_written) {
+            assert_eq!(degrees(mode), expected.to_vec(), "{}", mode.name);
+        }
+
+        let harmonic = scale_type("harmonic minor", &harmonic_minor::AEOLIAN_HARMONIC).modes();
+        let hand_written = [
+            harmonic_minor::AEOLIAN_HARMONIC,
+            harmonic_minor::LOCRIAN_NATURAL_6,
+            harmonic_minor::IONIAN_SHARP_5,
+            harmonic_minor::DORIAN_SHARP_4,
+            harmonic_minor::PHRYGIAN_DOMINANT,
+            harmonic_minor::LYDIAN_SHARP_2,
+            harmonic_minor::SUPER_LOCRIAN,
+        ];
+
+        for (mode, expected) in harmonic.iter().zip(hand_written) {
+            assert_eq!(degrees(mode), expected.to_vec(), "{}", mode.name);
+        }
+
+        let melodic = scale_type("melodic minor", &melodic_minor::MELODIC_MINOR);
+        let degree = |degree, modifier| ScaleDegree { degree, modifier };
+
+        assert_eq!(
+            melodic.mode(6).map(|mode| degrees(&mode)),
+            Some(vec![
+                degree(1, Accidental::Natural),
+                degree(2, Accidental::Flat),
+                degree(3, Accidental::Flat),
+                degree(4, Accidental::Flat),
+                degree(5, Accidental::Flat),
+                degree(6, Accidental::Flat),
+                degree(7, Accidental::Flat),
+            ])
+        );
+        assert_eq!(melodic.mode(7), None);
+    }
+
+    #[test]
+    fn test_unspellable_scales() {
+        let fb = Pitch::parse("Fb4").unwrap();
+        let ultralocrian = scale_type("ultralocrian", &harmonic_minor::SUPER_LOCRIAN);
+
+        assert!(Scale::checked_new(&fb, &ultralocrian).is_none());
+
+        let high = ScaleDegree::new(255, Accidental::Natural);
+
+        assert_eq!(high.half_notes(), i8::MAX);
+        assert_eq!(high.interval(), None);
+    }
+
+    #[test]
+    fn test_scales_of_any_length() {
+        let names = |root: &str, degrees: &[ScaleDegree]| -> Vec<String> {
+            Scale::from_pitch_string(root, &scale_type("", degrees))
+                .pitches
+                .iter()
+                .map(|p| format!("{p}{}", p.octave))
+                .collect()
+        };
+
+        assert_eq!(
+            names("A3", &pentatonic::BLUES),
+            vec!["A3", "C4", "D4", "Eb4", "E4", "G4"]
+        );
+        assert_eq!(
+            names("G4", &pentatonic::MAJOR_PENTATONIC),
+            vec!["G4", "A4", "B4", "D5", "E5"]
+        );
+        assert_eq!(
+            names("F4", &bebop::BEBOP_DOMINANT),
+            vec!["F4", "G4", "A4", "Bb4", "C5", "D5", "Eb5", "E5"]
+        );
+        assert_eq!(
+            names("C4", &symmetric::DOMINANT_DIMINISHED),
+            vec!["C4", "Db4", "D#4", "E4", "F#4", "G4", "A4", "Bb4"]
+        );
+        assert_eq!(
+            names("E4", &symmetric::WHOLE_TONE),
+            vec!["E4", "F#4", "G#4", "A#4", "B#4", "D5"]
+        );
+
+        let major_pentatonic = scale_type("major pentatonic", &pentatonic::MAJOR_PENTATONIC);
+
+        assert_eq!(
+            major_pentatonic.mode(4).unwrap().scale_degrees,
+            pentatonic::MINOR_PENTATONIC.to_vec()
+        );
+        assert_eq!(
+            Scale::from_pitch_string("A3", &scale_type("blues", &pentatonic::BLUES))
+                .run(1, Direction::Descending)
+                .len(),
+            7
+        );
+    }
+
+    #[test]
+    fn test_scale_run() {
+        let g_major = Scale::from_pitch_string("G3", &scale_type("ionian", &modes::IONIAN));
+
+        assert_eq!(
+            g_major.run(1, Direction::Descending),
+            scale_from_string("G4 F#4 E4 D4 C4 B3 A3 G3")
+        );
+        assert_eq!(g_major.run(2, Direction::Ascending).len(), 15);
+        assert_eq!(
+            g_major.run(1, Direction::AscendingDescending),
+            scale_from_string("G3 A3 B3 C4 D4 E4 F#4 G4 F#4 E4 D4 C4 B3 A3 G3")
+        );
+    }
+
+    #[test]
+    fn test_seventh_chords() {
+        let c_ionian = Scale::from_pitch_string("C4", &scale_type("ionian", &modes::IONIAN));
+
+        assert_eq!(
+            symbols(&c_ionian.seventh_chords()),
+            vec!["Cmaj7", "Dm7", "Em7", "Fmaj7", "G7", "Am7", "Bm7b5"]
+        );
+
+        let a_harmonic = Scale::from_pitch_string(
+            "A3",
+            &scale_type("harmonic minor", &harmonic_minor::AEOLIAN_HARMONIC),
+        );
+
+        assert_eq!(
+            symbols(&a_harmonic.seventh_chords()),
+            vec![
+                "AmMaj7", "Bm7b5", "CaugMaj7", "Dm7", "E7", "Fmaj7", "G#dim7"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_triads() {
+        let f_lydian = Scale::from_pitch_string("F3", &scale_type("lydian", &modes::LYDIAN));
+
+        assert_eq!(
+            symbols(&f_lydian.triads()),
+            vec!["F", "G", "Am", "Bdim", "C", "Dm", "Em"]
+        );
+    }
+
+    #[test]
+    fn test_triads_keep_degrees() {
+        let degree = |degree, modifier| ScaleDegree { degree, modifier };
+        let degrees = [
+            degree(1, Accidental::Natural),
+            degree(2, Accidental::Natural),
+            degree(3, Accidental::DoubleFlat),
+            degree(4, Accidental::Natural),
+            degree(5, Accidental::DoubleSharp),
+            degree(6, Accidental::Natural),
+            degree(7, Accidental::Natural),
+        ];
+        let scale = Scale::from_pitch_string("C4", &scale_type("altered", &degrees));
+
+        assert_eq!(
+            symbols(&scale.triads()),
+            vec!["Cbb3##5", "Dm", "-", "F", "G𝄪bb3bb5", "Ambb5", "Bdim"]
+        );
+    }
+
+    #[test]
+    fn test_no_triads_without_seven_notes() {
+        let c_pentatonic = Scale::from_pitch_string(
+            "C4",
+            &scale_type("major pentatonic", &pentatonic::MAJOR_PENTATONIC),
+        );
+
+        assert!(c_pentatonic.triads().is_empty());
+        assert!(c_pentatonic.seventh_chords().is_empty());
+    }
+
+    #[test]
+    fn test_all_modes_harmonize() {
+        let all_modes = [
+            modes::IONIAN,
+            modes::DORIAN,
+            modes::PHRYGIAN,
+            modes::LYDIAN,
+            modes::MIXOLYDIAN,
+            modes::AEOLIAN,
+            modes::LOCRIAN,
+            harmonic_minor::AEOLIAN_HARMONIC,
+            harmonic_minor::LOCRIAN_NATURAL_6,
+            harmonic_minor::IONIAN_SHARP_5,
+            harmonic_minor::DORIAN_SHARP_4,
+            harmonic_minor::PHRYGIAN_DOMINANT,
+            harmonic_minor::LYDIAN_SHARP_2,
+            harmonic_minor::SUPER_LOCRIAN,
+        ];
+
+        for degrees in all_modes {
+            let scale = Scale::from_pitch_string("D4", &scale_type("mode", &degrees));
+
+            for chord in scale.triads().iter().chain(&scale.seventh_chords()) {
+                let chord = chord.as_ref().unwrap();
+                assert!(chord.alterations.is_empty(), "{scale} {chord}");
+            }
+
+            assert_eq!(scale.triads().len(), 7);
+            assert_eq!(scale.seventh_chords().len(), 7);
+        }
+    }
 }
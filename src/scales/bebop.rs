@@ -0,0 +1,75 @@
+//! Bebop scales, seven note scales with a chromatic passing note so the
+//! chord tones fall on the beats.
+use crate::{
+    pitch::Accidental::{Flat, Natural, Sharp},
+    scale::ScaleDegree,
+};
+
+/// Mixolydian with the major 7th added.
+pub const BEBOP_DOMINANT: [ScaleDegree; 8] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Natural),
+    ScaleDegree::new(3, Natural),
+    ScaleDegree::new(4, Natural),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(6, Natural),
+    ScaleDegree::new(7, Flat),
+    ScaleDegree::new(7, Natural),
+];
+
+/// Ionian with the #5 added.
+pub const BEBOP_MAJOR: [ScaleDegree; 8] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Natural),
+    ScaleDegree::new(3, Natural),
+    ScaleDegree::new(4, Natural),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(5, Sharp),
+    ScaleDegree::new(6, Natural),
+    ScaleDegree::new(7, Natural),
+];
+
+/// Dorian with the major 3rd added.
+pub const BEBOP_DORIAN: [ScaleDegree; 8] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Natural),
+    ScaleDegree::new(3, Flat),
+    ScaleDegree::new(3, Natural),
+    ScaleDegree::new(4, Natural),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(6, Natural),
+    ScaleDegree::new(7, Flat),
+];
+
+/// Melodic minor with the #5 added.
+pub const BEBOP_MELODIC_MINOR: [ScaleDegree; 8] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Natural),
+    ScaleDegree::new(3, Flat),
+    ScaleDegree::new(4, Natural),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(5, Sharp),
+    ScaleDegree::new(6, Natural),
+    ScaleDegree::new(7, Natural),
+];
+
+/// Lydian dominant with the 5th added after the #4.
+pub const BEBOP_LYDIAN_DOMINANT: [ScaleDegree; 8] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Natural),
+    ScaleDegree::new(3, Natural),
+    ScaleDegree::new(4, Sharp),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(6, Natural),
+    ScaleDegree::new(7, Flat),
+    ScaleDegree::new(7, Natural),
+];
+
+/// Bebop scales with their names.
+pub const SCALES: [(&[&str], &[ScaleDegree]); 5] = [
+    (&["bebop dominant", "bebop"], &BEBOP_DOMINANT),
+    (&["bebop major"], &BEBOP_MAJOR),
+    (&["bebop dorian", "bebop minor"], &BEBOP_DORIAN),
+    (&["bebop melodic minor"], &BEBOP_MELODIC_MINOR),
+    (&["bebop lydian dominant"], &BEBOP_LYDIAN_DOMINANT),
+];
@@ -0,0 +1,33 @@
+//! Harmonic major scale, the parent of its modes made by `ScaleType::modes`.
+use crate::{pitch::Accidental, scale::ScaleDegree};
+
+pub const HARMONIC_MAJOR: [ScaleDegree; 7] = [
+    ScaleDegree {
+        degree: 1,
+        modifier: Accidental::Natural,
+    },
+    ScaleDegree {
+        degree: 2,
+        modifier: Accidental::Natural,
+    },
+    ScaleDegree {
+        degree: 3,
+        modifier: Accidental::Natural,
+    },
+    ScaleDegree {
+        degree: 4,
+        modifier: Accidental::Natural,
+    },
+    ScaleDegree {
+        degree: 5,
+        modifier: Accidental::Natural,
+    },
+    ScaleDegree {
+        degree: 6,
+        modifier: Accidental::Flat,
+    },
+    ScaleDegree {
+        degree: 7,
+        modifier: Accidental::Natural,
+    },
+];
@@ -187,7 +187,8 @@ pub const LYDIAN_SHARP_2: [ScaleDegree; 7] = [
     },
 ];
 
-// Altered scale and this is melodic minor mode
+// Also called ultralocrian, the altered scale of melodic minor has a minor
+// 7th instead, it is the seventh mode of `melodic_minor::MELODIC_MINOR`.
 pub const SUPER_LOCRIAN: [ScaleDegree; 7] = [
     ScaleDegree {
         degree: 1,
@@ -0,0 +1,183 @@
+//! Finding the scales which fit a melody.
+use crate::{
+    beat::Staff,
+    note::Note,
+    pitch::{Pitch, SpellingPreference},
+    scale::Scale,
+};
+
+use super::ScaleRegistry;
+
+/// Octave of the roots of the identified scales.
+const ROOT_OCTAVE: i8 = 4;
+
+/// Scale found for a set of pitches.
+#[derive(Clone, Debug)]
+pub struct ScaleMatch {
+    /// The scale with its root, its type is named after the mode.
+    pub scale: Scale,
+    /// Pitches which are not in the scale, one per pitch class.
+    pub outside: Vec<Pitch>,
+    /// Pitches of the scale which were not played.
+    pub unused: Vec<Pitch>,
+}
+
+impl ScaleRegistry {
+    /// Scales on every root which contain the pitches, allowing up to
+    /// `max_outside` pitch classes out of the scale. Pitches are compared
+    /// by pitch class, so enharmonic spellings match. The best fits come
+    /// first: the fewest pitches outside, then the fewest unused scale
+    /// tones, then the scales whose root is the first pitch.
+    pub fn identify(&self, pitches: &[Pitch], max_outside: usize) -> Vec<ScaleMatch> {
+        let mut played: Vec<Pitch> = vec![];
+
+        for pitch in pitches {
+            if !played.iter().any(|p| p.chroma() == pitch.chroma()) {
+                played.push(*pitch);
+            }
+        }
+
+        let Some(first) = played.first().map(Pitch::chroma) else {
+            return vec![];
+        };
+
+        let mut matches: Vec<(usize, ScaleMatch)> = vec![];
+
+        for (order, scale_type) in self.scale_types().iter().enumerate() {
+            for chroma in 0..12 {
+                let root = root_spelling(&played, chroma);
+                let Some(scale) = Scale::checked_new(&root, scale_type) else {
+                    continue;
+                };
+                let outside: Vec<Pitch> = played
+                    .iter()
+                    .filter(|p| !scale.pitches.iter().any(|s| s.chroma() == p.chroma()))
+                    .copied()
+                    .collect();
+
+                if outside.len() > max_outside {
+                    continue;
+                }
+
+                let unused = scale
+                    .pitches
+                    .iter()
+                    .filter(|s| !played.iter().any(|p| p.chroma() == s.chroma()))
+                    .copied()
+                    .collect();
+
+                matches.push((
+                    order,
+                    ScaleMatch {
+                        scale,
+                        outside,
+                        unused,
+                    },
+                ));
+            }
+        }
+
+        matches.sort_by_key(|(order, m)| {
+            (
+                m.outside.len(),
+                m.unused.len(),
+                m.scale.root_note.chroma() != first,
+                *order,
+            )
+        });
+        matches.into_iter().map(|(_, m)| m).collect()
+    }
+
+    /// Same as `identify` with the pitches of the notes of the staff.
+    pub fn identify_staff(&self, staff: &Staff, max_outside: usize) -> Vec<ScaleMatch> {
+        let pitches: Vec<Pitch> = staff
+            .measures
+            .iter()
+            .flat_map(|measure| &measure.notes)
+            .filter_map(|note| match note {
+                Note::Tone(pitch, _) => Some(*pitch),
+                Note::Rest(_) => None,
+            })
+            .collect();
+
+        self.identify(&pitches, max_outside)
+    }
+}
+
+/// Root on the pitch class, spelled like the played pitch if there is one.
+fn root_spelling(played: &[Pitch], chroma: i8) -> Pitch {
+    match played.iter().find(|p| p.chroma() == chroma) {
+        Some(pitch) => Pitch {
+            octave: ROOT_OCTAVE,
+            ..*pitch
+        },
+        None => Pitch::from_half_notes(
+            ROOT_OCTAVE as i16 * 12 + chroma as i16,
+            SpellingPreference::Simplest,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        beat::TimeSignature,
+        note::{Duration, DurationName},
+        scale::scale_from_string,
+    };
+
+    fn names(matches: &[ScaleMatch]) -> Vec<String> {
+        matches
+            .iter()
+            .map(|m| format!("{} {}", m.scale.root_note, m.scale.scale_type.name))
+            .collect()
+    }
+
+    #[test]
+    fn test_identify() {
+        let registry = ScaleRegistry::new();
+        let lick = scale_from_string("A3 C4 D4 Eb4 E4 G4 A4");
+        let matches = registry.identify(&lick, 0);
+
+        assert_eq!(names(&matches[..1]), vec!["A blues"]);
+        assert!(matches[0].outside.is_empty());
+        assert!(matches[0].unused.is_empty());
+
+        let dorian = scale_from_string("D4 E4 F4 G4 A4 B4 C5");
+        let matches = registry.identify(&dorian, 0);
+
+        assert_eq!(names(&matches[..2]), vec!["D dorian", "C ionian"]);
+        assert!(matches.iter().all(|m| m.scale.pitches.len() >= 7));
+
+        let chromatic = scale_from_string("C4 D4 E4 F4 F#4 G4");
+
+        assert!(registry.identify(&chromatic, 0)[0].unused.len() <= 2);
+        assert!(registry.identify(&[], 0).is_empty());
+    }
+
+    #[test]
+    fn test_identify_staff() {
+        let quarter = Duration::new(DurationName::Quarter);
+        let notes = scale_from_string("E4 F4 G#4 A4 B4 C5 D5 E5")
+            .into_iter()
+            .map(|pitch| Note::Tone(pitch, quarter))
+            .collect();
+        let staff = Staff::from_notes(TimeSignature::COMMON_TIME, notes);
+        let matches = ScaleRegistry::new().identify_staff(&staff, 1);
+
+        // The same pitches as A harmonic minor, the root decides
+        assert_eq!(
+            names(&matches[..2]),
+            vec!["E phrygian dominant", "A harmonic minor"]
+        );
+
+        let phrygian = matches
+            .iter()
+            .find(|m| names(std::slice::from_ref(*m)) == ["E phrygian"])
+            .unwrap();
+
+        assert_eq!(phrygian.outside, scale_from_string("G#4"));
+        assert_eq!(phrygian.unused, scale_from_string("G4"));
+    }
+}
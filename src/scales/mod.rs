@@ -1,2 +1,59 @@
+pub mod bebop;
+pub mod double_harmonic;
+pub mod harmonic_major;
 pub mod harmonic_minor;
+pub mod identify;
+pub mod melodic_minor;
 pub mod modes;
+pub mod pentatonic;
+pub mod registry;
+pub mod symmetric;
+
+pub use identify::ScaleMatch;
+pub use registry::{RegistryError, ScaleRegistry};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scale::{Scale, ScaleDegree, ScaleType};
+
+    /// Pitches of the mode `n` of the parent scale from `root`.
+    fn mode_pitches(parent: &[ScaleDegree], n: usize, root: &str) -> String {
+        let parent = ScaleType {
+            name: String::new(),
+            scale_degrees: parent.to_vec(),
+        };
+        let scale = Scale::from_pitch_string(root, &parent.mode(n).unwrap());
+
+        scale
+            .pitches
+            .iter()
+            .map(|p| p.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn test_rotated_modes() {
+        assert_eq!(
+            mode_pitches(&melodic_minor::MELODIC_MINOR, 6, "C4"),
+            "C Db Eb Fb Gb Ab Bb"
+        );
+        assert_eq!(
+            mode_pitches(&melodic_minor::MELODIC_MINOR, 3, "F4"),
+            "F G A B C D Eb"
+        );
+        assert_eq!(
+            mode_pitches(&harmonic_major::HARMONIC_MAJOR, 1, "D4"),
+            "D E F G Ab B C"
+        );
+        assert_eq!(
+            mode_pitches(&double_harmonic::DOUBLE_HARMONIC_MAJOR, 3, "A4"),
+            "A B C D# E F G#"
+        );
+        assert_eq!(
+            mode_pitches(&double_harmonic::DOUBLE_HARMONIC_MAJOR, 6, "B4"),
+            "B C Db E F G Ab"
+        );
+    }
+}
@@ -0,0 +1,50 @@
+//! Pentatonic and blues scales
+use crate::{
+    pitch::Accidental::{Flat, Natural, Sharp},
+    scale::ScaleDegree,
+};
+
+pub const MAJOR_PENTATONIC: [ScaleDegree; 5] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Natural),
+    ScaleDegree::new(3, Natural),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(6, Natural),
+];
+
+pub const MINOR_PENTATONIC: [ScaleDegree; 5] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(3, Flat),
+    ScaleDegree::new(4, Natural),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(7, Flat),
+];
+
+/// Minor pentatonic with the blue note b5 between the 4th and the 5th.
+pub const BLUES: [ScaleDegree; 6] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(3, Flat),
+    ScaleDegree::new(4, Natural),
+    ScaleDegree::new(5, Flat),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(7, Flat),
+];
+
+/// Major pentatonic with the blue note b3 leading to the 3rd, spelled #2
+/// as it goes up.
+pub const MAJOR_BLUES: [ScaleDegree; 6] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Natural),
+    ScaleDegree::new(2, Sharp),
+    ScaleDegree::new(3, Natural),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(6, Natural),
+];
+
+/// Pentatonic and blues scales with their names.
+pub const SCALES: [(&[&str], &[ScaleDegree]); 4] = [
+    (&["major pentatonic", "pentatonic"], &MAJOR_PENTATONIC),
+    (&["minor pentatonic"], &MINOR_PENTATONIC),
+    (&["blues", "minor blues"], &BLUES),
+    (&["major blues"], &MAJOR_BLUES),
+];
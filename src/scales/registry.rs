@@ -0,0 +1,380 @@
+//! Catalogue of scale types looked up by name.
+use std::{collections::HashMap, fmt::Display};
+
+use crate::scale::{ScaleDegree, ScaleType};
+
+use super::{
+    bebop, double_harmonic, harmonic_major, harmonic_minor, melodic_minor, modes, pentatonic,
+    symmetric,
+};
+
+/// Scale families as the parent scale and the names of its modes from the
+/// first degree.
+const FAMILIES: [([ScaleDegree; 7], [&[&str]; 7]); 5] = [
+    (
+        modes::IONIAN,
+        [
+            &["ionian", "major"],
+            &["dorian"],
+            &["phrygian"],
+            &["lydian"],
+            &["mixolydian", "dominant"],
+            &["aeolian", "natural minor", "minor"],
+            &["locrian"],
+        ],
+    ),
+    (
+        harmonic_minor::AEOLIAN_HARMONIC,
+        [
+            &["harmonic minor", "aeolian #7"],
+            &["locrian natural 6", "locrian #6"],
+            &["ionian #5", "ionian augmented"],
+            &["dorian #4", "ukrainian dorian", "romanian minor"],
+            &["phrygian dominant", "spanish phrygian", "freygish"],
+            &["lydian #2"],
+            &["ultralocrian", "super locrian bb7", "altered diminished"],
+        ],
+    ),
+    (
+        melodic_minor::MELODIC_MINOR,
+        [
+            &["melodic minor", "jazz minor", "ascending melodic minor"],
+            &["dorian b2", "phrygian #6", "phrygian natural 6"],
+            &["lydian augmented", "lydian #5"],
+            &["lydian dominant", "lydian b7", "overtone", "acoustic"],
+            &["mixolydian b6", "aeolian dominant", "hindu"],
+            &[
+                "locrian natural 2",
+                "locrian #2",
+                "aeolian b5",
+                "half diminished",
+            ],
+            &["altered", "super locrian", "altered dominant"],
+        ],
+    ),
+    (
+        harmonic_major::HARMONIC_MAJOR,
+        [
+            &["harmonic major", "ionian b6"],
+            &["dorian b5", "locrian #2 #6"],
+            &["phrygian b4", "altered natural 5"],
+            &["lydian b3", "melodic minor #4"],
+            &["mixolydian b2"],
+            &["lydian augmented #2", "lydian #2 #5"],
+            &["locrian bb7"],
+        ],
+    ),
+    (
+        double_harmonic::DOUBLE_HARMONIC_MAJOR,
+        [
+            &[
+                "double harmonic major",
+                "byzantine",
+                "arabic",
+                "gypsy major",
+            ],
+            &["lydian #2 #6"],
+            &["ultraphrygian", "phrygian b4 bb7"],
+            &["hungarian minor", "gypsy minor", "double harmonic minor"],
+            &["oriental", "mixolydian b2 b5"],
+            &["ionian #2 #5"],
+            &["locrian bb3 bb7"],
+        ],
+    ),
+];
+
+/// Most suggestions given for an unknown name.
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Scale types with their names and aliases. In the lists of names the
+/// first name is the usual one and the rest are aliases. The lookup
+/// ignores case, hyphens and extra spaces, so "Whole-Tone" finds "whole
+/// tone".
+#[derive(Clone, Debug)]
+pub struct ScaleRegistry {
+    scale_types: Vec<ScaleType>,
+    /// Normalized names and aliases to the index of the scale type.
+    names: HashMap<String, usize>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RegistryError {
+    /// No scale has the name, the known names closest to it are suggested.
+    Unknown {
+        name: String,
+        suggestions: Vec<String>,
+    },
+    /// The name or alias is already used by another scale.
+    NameTaken(String),
+}
+
+impl ScaleRegistry {
+    /// Registry without any scales.
+    pub fn empty() -> Self {
+        ScaleRegistry {
+            scale_types: vec![],
+            names: HashMap::new(),
+        }
+    }
+
+    /// Registry with all the scales of the crate, the modes of the scale
+    /// families and the other scales in `scales`.
+    pub fn new() -> Self {
+        let mut registry = ScaleRegistry::empty();
+
+        for (parent, names) in FAMILIES {
+            let parent = ScaleType {
+                name: names[0][0].to_string(),
+                scale_degrees: parent.to_vec(),
+            };
+
+            for (aliases, mode) in names.iter().zip(parent.modes()) {
+                registry.insert(&mode.scale_degrees, aliases);
+            }
+        }
+
+        for (aliases, degrees) in pentatonic::SCALES
+            .into_iter()
+            .chain(bebop::SCALES)
+            .chain(symmetric::SCALES)
+        {
+            registry.insert(degrees, aliases);
+        }
+
+        registry
+    }
+
+    /// Built-in scales are unique, the first name is kept if there is a
+    /// clash.
+    fn insert(&mut self, degrees: &[ScaleDegree], aliases: &[&str]) {
+        let index = self.scale_types.len();
+
+        self.scale_types.push(ScaleType {
+            name: aliases[0].to_string(),
+            scale_degrees: degrees.to_vec(),
+        });
+
+        for alias in aliases {
+            self.names.entry(normalize(alias)).or_insert(index);
+        }
+    }
+
+    /// Add a scale type under its own name and the aliases. Nothing is
+    /// added if any of the names is taken.
+    pub fn register(
+        &mut self,
+        scale_type: ScaleType,
+        aliases: &[&str],
+    ) -> Result<(), RegistryError> {
+        let names: Vec<String> = std::iter::once(scale_type.name.as_str())
+            .chain(aliases.iter().copied())
+            .map(normalize)
+            .collect();
+
+        if let Some(taken) = names.iter().find(|name| self.names.contains_key(*name)) {
+            return Err(RegistryError::NameTaken(taken.clone()));
+        }
+
+        let index = self.scale_types.len();
+
+        self.scale_types.push(scale_type);
+
+        for name in names {
+            self.names.insert(name, index);
+        }
+
+        Ok(())
+    }
+
+    /// Scale type by its name or any alias.
+    pub fn get(&self, name: &str) -> Option<&ScaleType> {
+        self.names
+            .get(&normalize(name))
+            .map(|index| &self.scale_types[*index])
+    }
+
+    /// Same as `get`, but an unknown name gives an error with suggestions.
+    pub fn lookup(&self, name: &str) -> Result<&ScaleType, RegistryError> {
+        self.get(name).ok_or_else(|| RegistryError::Unknown {
+            name: name.to_string(),
+            suggestions: self.suggestions(name),
+        })
+    }
+
+    /// Known names closest to `name`, the closest first. Names which start
+    /// with it come first, then the ones with a small edit distance.
+    pub fn suggestions(&self, name: &str) -> Vec<String> {
+        let name = normalize(name);
+        let limit = (name.chars().count() / 3).max(2);
+        let mut candidates: Vec<(usize, &String)> = self
+            .names
+            .keys()
+            .filter_map(|known| {
+                if !name.is_empty() && known.starts_with(&name) {
+                    Some((0, known))
+                } else {
+                    let distance = edit_distance(&name, known);
+
+                    (distance <= limit).then_some((distance, known))
+                }
+            })
+            .collect();
+
+        candidates.sort();
+        candidates
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, known)| known.clone())
+            .collect()
+    }
+
+    /// All the scale types in the order they were added.
+    pub fn scale_types(&self) -> &[ScaleType] {
+        &self.scale_types
+    }
+}
+
+impl Default for ScaleRegistry {
+    fn default() -> Self {
+        ScaleRegistry::new()
+    }
+}
+
+/// Lowercase name with hyphens, underscores and runs of spaces turned into
+/// single spaces.
+fn normalize(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| c.is_whitespace() || c == '-' || c == '_')
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Levenshtein distance of the two strings in characters.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + (ca != *cb) as usize;
+
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+impl Display for RegistryError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RegistryError::Unknown { name, suggestions } if suggestions.is_empty() => {
+                write!(f, "unknown scale {name}")
+            }
+            RegistryError::Unknown { name, suggestions } => {
+                write!(
+                    f,
+                    "unknown scale {name}, did you mean {}?",
+                    suggestions.join(", ")
+                )
+            }
+            RegistryError::NameTaken(name) => write!(f, "scale name {name} is already taken"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pitch::Accidental, scale::Scale};
+
+    #[test]
+    fn test_lookup() {
+        let registry = ScaleRegistry::new();
+
+        assert_eq!(registry.get("major").unwrap().name, "ionian");
+        assert_eq!(registry.get("Dorian").unwrap().name, "dorian");
+        assert_eq!(registry.get("  Super-Locrian ").unwrap().name, "altered");
+        assert_eq!(registry.get("whole_tone").unwrap().name, "whole tone");
+        assert_eq!(
+            registry.get("lydian dominant").unwrap().scale_degrees,
+            vec![
+                ScaleDegree::new(1, Accidental::Natural),
+                ScaleDegree::new(2, Accidental::Natural),
+                ScaleDegree::new(3, Accidental::Natural),
+                ScaleDegree::new(4, Accidental::Sharp),
+                ScaleDegree::new(5, Accidental::Natural),
+                ScaleDegree::new(6, Accidental::Natural),
+                ScaleDegree::new(7, Accidental::Flat),
+            ]
+        );
+
+        let e_phrygian_dominant =
+            Scale::from_pitch_string("E4", registry.lookup("spanish phrygian").unwrap());
+
+        assert_eq!(e_phrygian_dominant.pitches[2].to_string(), "G#");
+    }
+
+    #[test]
+    fn test_suggestions() {
+        let registry = ScaleRegistry::new();
+
+        assert_eq!(
+            registry.lookup("dorain"),
+            Err(RegistryError::Unknown {
+                name: "dorain".to_string(),
+                suggestions: vec!["dorian".to_string()],
+            })
+        );
+        assert_eq!(
+            registry.suggestions("bebop d"),
+            vec!["bebop dominant", "bebop dorian", "bebop"]
+        );
+        assert!(registry.suggestions("xyzzy").is_empty());
+        assert_eq!(
+            registry.lookup("mixolidian").unwrap_err().to_string(),
+            "unknown scale mixolidian, did you mean mixolydian?"
+        );
+    }
+
+    #[test]
+    fn test_register() {
+        let mut registry = ScaleRegistry::new();
+        let hirajoshi = ScaleType {
+            name: "hirajoshi".to_string(),
+            scale_degrees: vec![
+                ScaleDegree::new(1, Accidental::Natural),
+                ScaleDegree::new(2, Accidental::Natural),
+                ScaleDegree::new(3, Accidental::Flat),
+                ScaleDegree::new(5, Accidental::Natural),
+                ScaleDegree::new(6, Accidental::Flat),
+            ],
+        };
+
+        assert_eq!(registry.register(hirajoshi.clone(), &["Japanese"]), Ok(()));
+        assert_eq!(registry.get("japanese"), Some(&hirajoshi));
+        assert_eq!(
+            registry.register(hirajoshi.clone(), &[]),
+            Err(RegistryError::NameTaken("hirajoshi".to_string()))
+        );
+
+        let kumoi = ScaleType {
+            name: "kumoi".to_string(),
+            ..hirajoshi
+        };
+
+        assert_eq!(
+            registry.register(kumoi, &["Minor"]),
+            Err(RegistryError::NameTaken("minor".to_string()))
+        );
+        assert!(registry.get("kumoi").is_none());
+        assert!(ScaleRegistry::empty().get("ionian").is_none());
+    }
+}
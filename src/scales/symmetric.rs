@@ -0,0 +1,71 @@
+//! Scales which repeat the same intervals, so they have only a few
+//! transpositions.
+use crate::{
+    pitch::Accidental::{Flat, Natural, Sharp},
+    scale::ScaleDegree,
+};
+
+pub const WHOLE_TONE: [ScaleDegree; 6] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Natural),
+    ScaleDegree::new(3, Natural),
+    ScaleDegree::new(4, Sharp),
+    ScaleDegree::new(5, Sharp),
+    ScaleDegree::new(7, Flat),
+];
+
+/// Octatonic scale starting with a whole step, used on diminished chords.
+pub const DIMINISHED: [ScaleDegree; 8] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Natural),
+    ScaleDegree::new(3, Flat),
+    ScaleDegree::new(4, Natural),
+    ScaleDegree::new(5, Flat),
+    ScaleDegree::new(6, Flat),
+    ScaleDegree::new(6, Natural),
+    ScaleDegree::new(7, Natural),
+];
+
+/// Octatonic scale starting with a half step, used on dominant chords.
+pub const DOMINANT_DIMINISHED: [ScaleDegree; 8] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Flat),
+    ScaleDegree::new(2, Sharp),
+    ScaleDegree::new(3, Natural),
+    ScaleDegree::new(4, Sharp),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(6, Natural),
+    ScaleDegree::new(7, Flat),
+];
+
+/// Minor 3rds and half steps taking turns, two augmented triads together.
+pub const AUGMENTED: [ScaleDegree; 6] = [
+    ScaleDegree::new(1, Natural),
+    ScaleDegree::new(2, Sharp),
+    ScaleDegree::new(3, Natural),
+    ScaleDegree::new(5, Natural),
+    ScaleDegree::new(5, Sharp),
+    ScaleDegree::new(7, Natural),
+];
+
+/// Symmetric scales with their names.
+pub const SCALES: [(&[&str], &[ScaleDegree]); 4] = [
+    (&["whole tone", "whole-tone"], &WHOLE_TONE),
+    (
+        &[
+            "diminished",
+            "whole half diminished",
+            "whole-half diminished",
+        ],
+        &DIMINISHED,
+    ),
+    (
+        &[
+            "dominant diminished",
+            "half whole diminished",
+            "half-whole diminished",
+        ],
+        &DOMINANT_DIMINISHED,
+    ),
+    (&["augmented", "hexatonic augmented"], &AUGMENTED),
+];